@@ -188,6 +188,11 @@ Here, $a, b$ are entities, $o$ is an object.
     `-`, `_` or `/` characters.
 
     - Example: `trans/4-20`
+
+    Anything else can be given in double quotes,
+    escaping `\"`, `\\`, newlines as `\n` and tabs as `\t`.
+
+    - Example: `"Mama's Pizza"`
   ],
 
   `money`,
@@ -197,6 +202,16 @@ Here, $a, b$ are entities, $o$ is an object.
     or euros in decimal form (e.g. `4.20 eur`).
 
     `ct` or `eur` may be omitted, if so, cents are assumed.
+
+    Which separators are used depends on the locale
+    chosen with `--locale`:
+    `plain` for `1234.50 €`,
+    `en` for `€1,234.50` and
+    `de` for `1.234,50 €`.
+    `--decimal-separator`, `--thousands-separator` and `--symbol-position`
+    change single parts of it,
+    the separators being one of `.`, `,` and `'`.
+    The euro sign may also come before the amount in any of them.
   ],
 
   `product`,
@@ -261,6 +276,13 @@ on the specifics of how to write them.
 
 - Parameters have placeholder names in `<>`
 - `()` denotes that something is optional
+- Named parameters can be given as `key=value` or `key value`, in any order
+- Each command declares which parameters it takes, of which type
+  - Unknown, missing, repeated or mistyped arguments are errors
+  - The error suggests the closest parameter or word
+    if the given one looks like a typo of it
+  - and shows the usage of the command generated from the same declaration,
+    e.g. `pay <amount:money> from=<entity> to=<entity> [date=<date>] ...`
 
 === Creation
 
@@ -282,7 +304,9 @@ on the specifics of how to write them.
 
   `create concept <name>
   (price <money>)
-  (gtin <gtin>)`,
+  (gtin <gtin>)
+  (category <category:name>)
+  (tags <tag:name>,...)`,
   [
     Registers a new concept `name`.
     Has as default price `money`,
@@ -290,6 +314,34 @@ on the specifics of how to write them.
     If `gtin` is set, you can use it as
     alias equivalent for
     products.
+    `category` groups it with similar concepts for budgets.
+    Each `tag` groups it with others for reports,
+    and deliveries of it have them, too.
+  ],
+
+  `tag <concept> <tag:name>`,
+  [
+    Adds `tag` to the tags of `concept`,
+    including for past deliveries of it.
+    Does nothing if it already has `tag`.
+  ],
+
+  `alias (<kind>) <new:name> = <existing:name>`,
+  [
+    Lets the actor `existing` also be referred to as `new`.
+    It stays the same actor, with the same balances.
+    `kind` is one of `entity`, `concept` or `object`
+    and only needed if `existing` names several kinds of actors.
+  ],
+
+  `rename (<kind>) <old:name> to <new:name>`,
+  [
+    Refers to the actor `old` as `new` from now on.
+    It keeps its identity and balances,
+    and everything referring to it,
+    including past transfers,
+    shows `new` instead.
+    If `old` is an alias, only the alias is renamed.
   ],
 )
 
@@ -305,7 +357,8 @@ on the specifics of how to write them.
   (price <money>)
   from <source:entity>
   to <target:entity>
-  (split <ratio>)`,
+  (split <ratio>)
+  (round <rounding:ident>)`,
   [
     Delivers `product` from `source` to `target`.
     This implies a money transfer of `money`
@@ -318,6 +371,92 @@ on the specifics of how to write them.
     the right-hand part is how much the target pays.
     It defaults to `0:1`,
     meaning the target pays the entirety of `money`.
+
+    If `ratio` doesn't divide `money` into whole cents,
+    `rounding` decides who covers the cent left over:
+    `largest-remainder` (the default, also called `nearest`)
+    rounds up the part closer to the next cent,
+    on a tie the source covers it.
+    `payer` always makes the source cover it,
+    `receiver` always the target.
+    `carry` rounds like `largest-remainder`,
+    but carries the fraction of a cent rounded away
+    over to the next delivery between the same entities
+    that is rounded with `carry`, too.
+  ],
+
+  `receipt
+  from <payer:entity>
+  (at <store:entity>)
+  (total <money>)
+  { <line>... }`,
+  [
+    One purchase by `payer`,
+    split over lines consumed by different entities.
+    Each `line` is
+    `<product> (<qty>) (<price:money>) (for <entity>...)`,
+    costing `qty` times `price`,
+    `price` defaulting to the default price of `product`.
+    The entities after `for` pay an equal part of the line each,
+    delivered to them by `payer`.
+
+    If `money` is set,
+    it has to be the sum of all lines.
+    If `store` is set,
+    `payer` pays it `money`
+    and it delivers every line to `payer`.
+  ],
+
+  `void <label>`,
+  [
+    Undoes the effect of all transfers labelled `label`
+    on the balances and inventories,
+    but keeps them in the ledger marked as voided.
+    Transfers are labelled by giving `as <label>` to
+    `pay`, `deliver` or `receipt`,
+    every label can only be used once.
+    `label` can also be a number $n$
+    to void the $n$-th transfer, counting from 1.
+  ],
+
+  `memo <text:quoted>`,
+  [
+    Not a statement on its own:
+    `pay`, `deliver` and `receipt` all accept a `memo`,
+    a free-form note kept with the transfer
+    and written into all exports.
+  ],
+
+  `tags <tag:name>,...`,
+  [
+    Not a statement on its own either:
+    `pay`, `deliver` and `receipt` all accept `tags`,
+    names separated by commas without whitespace,
+    e.g. `tags=rent,flat`.
+    A delivery also has the tags of its concept.
+    A budget whose category is one of them
+    counts the delivery.
+  ],
+
+  `every <period:ident>
+  from <first:date>
+  (until <last:date>)
+  { <stmt>... }`,
+  [
+    Runs every `stmt` once per occurrence,
+    starting on `first`
+    and then every `day`, `week`, `month` or `year`
+    up to and including `last`,
+    each dated to the occurrence.
+    Only `pay`, `deliver` and `receipt` can be repeated,
+    without a `date` of their own.
+    Monthly and yearly occurrences stay on the day of month of `first`,
+    falling back to the last day of shorter months.
+
+    Without `last`,
+    the ledger needs to be evaluated up to a day
+    (with `--until` on the command line),
+    which also cuts off any later occurrences.
   ],
 )
 
@@ -326,7 +465,8 @@ on the specifics of how to write them.
 #detail(
   `balance
   from <source:entity>
-  to <target:entity>`,
+  to <target:entity>
+  (as-of <date>)`,
   [
     Go through all payments,
     including implied ones,
@@ -334,6 +474,154 @@ on the specifics of how to write them.
     emit how much money `source`
     needs to pay to `target`
     so their balance is equal again.
+
+    With `as-of`,
+    only transfers up to and including `date` are counted.
+    Transfers without a date always count.
+    `nyandere balance <source> <target>` on the command line
+    does the same at the end of a ledger without changing it.
+  ],
+
+  `whatif { <stmt>... }`,
+  [
+    Runs every `stmt` on a copy of the current state
+    and emits how each balance between two entities would change,
+    without keeping any of it.
+  ],
+
+  `budget <entity> <category:name> <limit:money>
+  per <period:ident>`,
+  [
+    Limits what `entity` spends on concepts in `category`
+    to `limit` every `day`, `week`, `month` or `year`.
+    Spending is the expected value `entity` pays
+    for dated deliveries to it
+    whose concept, or the concept of the object, is in `category`
+    or which have it among their tags.
+    A delivery crossing `limit` emits a warning,
+    but happens regardless.
+    Weeks start on Monday.
+    Budgeting the same `entity` and `category` again replaces the budget.
+  ],
+
+  `budget report`,
+  [
+    Emits for every budget and period with spending
+    how much was spent
+    and by how much it was over `limit`, if at all.
+  ],
+
+  `report spending by <grouping:ident>
+  (format <format:ident>)`,
+  [
+    Emits a table of how much each entity spent
+    on each `tag` or `category`, depending on `grouping`.
+    Spending is the expected value an entity pays
+    for deliveries to it, voided ones left out.
+    A delivery counts towards each of its tags.
+    `format` is `table` (the default) for humans
+    or `csv` for spreadsheets.
+  ],
+
+  `report spending per <period:ident>
+  of <entity>
+  (during <first:date>..<last:date>)
+  (format <format:ident>)`,
+  [
+    Emits a table with a row for every `day`, `week`, `month` or `year`
+    from `first` to `last`,
+    with how much `entity` paid and received,
+    the expected value it got and gave through deliveries
+    and the net change of how much it owes all others together.
+    Only dated transfers that weren't voided count.
+    Without `during`,
+    the rows go from the first to the last dated transfer of `entity`.
+    `during` may span at most 10000 periods.
+  ],
+
+  `history
+  (of <entity>)
+  (tag <tag:name>)`,
+  [
+    Emits every transfer in the order they happened,
+    numbered like `void` understands it,
+    with its date, label, tags and memo
+    and whether it was voided.
+    Only those from or to `entity` and with `tag` are emitted,
+    if given.
+    `nyandere history --entity <entity> --tag <tag>` on the command line
+    does the same at the end of a ledger without changing it.
+  ],
+
+  `select <columns>
+  from <table:ident>
+  (where <conditions>)
+  (group by <group:ident>)
+  (order by <column> (asc|desc))
+  (limit <n:nat>)`,
+  [
+    Emits a table of what `columns` says about the rows of `table`,
+    like SQL does.
+    `table` is one of:
+    - `payments`,
+      with `number`, `date`, `from`, `to`, `amount`, `label`, `memo` and `tags`.
+    - `deliveries`,
+      with `number`, `date`, `from`, `to`, `product`, `qty`, `price`,
+      `share` as the expected value `to` pays,
+      `label`, `memo` and `tags`.
+    - `entities`, with `name`.
+    - `concepts`, with `name`, `price`, `gtin`, `category` and `tags`.
+    - `objects`, with `name` and `parent`.
+    Voided transfers aren't in any of them.
+    `columns` are separated by commas,
+    each either `*` for all of them, a column,
+    or `count`, `sum`, `min` or `max` of one, as in `sum(price)`.
+    `count(*)` counts all rows.
+    `conditions` are joined by `and`,
+    each a column, one of `=`, `!=`, `<`, `<=`, `>` and `>=`, and a value.
+    Entities and products compared with are looked up like anywhere else.
+    A tag equals `tags` if it is among them.
+    An empty cell is only unequal to anything.
+    Rows are grouped by the column `group`,
+    or by the `day`, `week`, `month` or `year` of their `date`.
+    Once grouped or aggregated,
+    only `group` can be selected as-is,
+    and it is shown first if it isn't selected.
+    The table is only ordered by `column` if it is selected.
+  ],
+
+  `help (<command> (<form>))`,
+  [
+    Emits the usage, parameters, defaults and examples
+    of every form of `command`,
+    or only of `form` if given, as in `help create concept`.
+    Without `command`, lists every command with what it does.
+    `nyandere explain` on the command line does the same
+    without a script.
+  ],
+
+  `assert <subject> = <expected>`,
+  [
+    Stops the script with an error
+    showing `expected` and the actual value
+    if `subject` isn't `expected`.
+    `subject` is one of:
+
+    - `balance from <source:entity> to <target:entity>`,
+      expecting `money`,
+      positive if `target` owes `source`
+      (which may be negative, as in `-2€`)
+    - `net <entity>`:
+      How much `entity` owes all others together,
+      expecting `money`
+    - `inventory <entity> <product>`:
+      How often `product` was delivered to `entity`
+      minus how often `entity` delivered it,
+      expecting an integer
+      (which may be negative, as in `-3`)
+    - `price <concept>`:
+      Default price of `concept`,
+      expecting `money`
   ],
 )
 
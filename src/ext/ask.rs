@@ -0,0 +1,115 @@
+//! Answer questions about a ledger from the command line
+//! without changing it.
+//!
+//! The ledger is run first, as usual,
+//! but the queries in it are only checked, not answered,
+//! so only the answer to the question asked is printed.
+//! Nothing is written anywhere, in particular not to the ledger itself.
+
+use eyre::{Context, Result};
+
+use crate::{
+    Runtime, eval_from,
+    ext::{Date, config},
+    syntax::ast::{Arg, Command, Stmt, Value},
+};
+
+/// Runs `source` as of `horizon` with queries only checked.
+pub fn evaluate(source: &str, horizon: Option<Date>) -> Result<Runtime> {
+    let mut runtime = Runtime::new();
+    runtime.set_horizon(horizon);
+    runtime.set_quiet(true);
+    let mut runtime = eval_from(runtime, source, 0)?;
+    runtime.set_quiet(false);
+    Ok(runtime)
+}
+
+/// Answers `stmt` on `runtime`, as if it were appended to the ledger.
+pub fn answer(runtime: &mut Runtime, stmt: Stmt) -> Result<()> {
+    let cmd = runtime
+        .repr(stmt)
+        .wrap_err("while understanding the question")?;
+    runtime.fulfil(cmd)?;
+    Ok(())
+}
+
+/// Prints how much `cfg.from` owes `cfg.to`.
+pub fn balance(cfg: config::Balance, horizon: Option<Date>) -> Result<()> {
+    let source = cfg.source.get().wrap_err("while loading source")?;
+    let mut runtime = evaluate(&source, horizon)?;
+
+    let args = vec![
+        Arg::named("from", Value::name(&cfg.from)),
+        Arg::named("to", Value::name(&cfg.to)),
+    ];
+    answer(&mut runtime, Stmt::new(Command::Balance, args))
+}
+
+/// Prints every transfer, optionally only those of one entity or tag.
+pub fn history(cfg: config::History, horizon: Option<Date>) -> Result<()> {
+    let source = cfg.source.get().wrap_err("while loading source")?;
+    let mut runtime = evaluate(&source, horizon)?;
+
+    let args = [("of", &cfg.entity), ("tag", &cfg.tag)]
+        .into_iter()
+        .filter_map(|(key, value)| Some(Arg::named(key, Value::name(value.as_deref()?))))
+        .collect();
+    answer(&mut runtime, Stmt::new(Command::History, args))
+}
+
+/// Prints the payments suggested by [`State::settle`][crate::runtime::State::settle].
+pub fn settle(cfg: config::Settle, horizon: Option<Date>) -> Result<()> {
+    let source = cfg.source.get().wrap_err("while loading source")?;
+    let runtime = evaluate(&source, horizon)?;
+
+    let debits = runtime.state().settle();
+    if debits.is_empty() {
+        println!("everyone is settled");
+    }
+    for debit in debits {
+        println!("{debit}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::cmd;
+
+    use super::*;
+
+    const LEDGER: &str = "
+        create entity A
+        create entity B
+        pay 5€ from=A to=B
+        balance from=A to=B
+        history
+    ";
+
+    #[test]
+    fn quiet() {
+        let mut runtime = evaluate(LEDGER, None).unwrap();
+
+        // but they still have to make sense
+        assert!(evaluate("balance from=A to=B", None).is_err());
+        assert!(
+            evaluate(
+                "create entity A\ncreate entity B\nassert balance from=A to=B = 1€",
+                None
+            )
+            .is_err()
+        );
+
+        let cmd = runtime
+            .repr(Stmt::new(
+                Command::History,
+                vec![Arg::named("of", Value::name("A"))],
+            ))
+            .unwrap();
+        assert!(matches!(
+            cmd,
+            cmd::Command::History(cmd::History { of: Some(_), .. })
+        ));
+        assert!(answer(&mut runtime, Stmt::new(Command::Balance, vec![])).is_err());
+    }
+}
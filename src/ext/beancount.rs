@@ -0,0 +1,334 @@
+//! Export the ledger as [Beancount](https://beancount.github.io/) transactions,
+//! e.g. for archiving or taxes.
+//!
+//! # Accounts
+//!
+//! For every pair of entities `a` and `b` with `a < b`, there are 2 accounts:
+//!
+//! - `Assets:Receivable:<b>:<a>`: How much `a` owes `b`.
+//! - `Liabilities:Payable:<a>:<b>`: The same, but from the perspective of `a`,
+//!   hence with opposite sign.
+//!
+//! Each transfer moves the amount it changed the balance by between them.
+//! Voided transfers are left out.
+//! Memos are kept as metadata of the transaction.
+//! If `b` owes `a` instead, the receivable is negative.
+//! Names are changed to be valid account name components,
+//! e.g. `kim` becomes `Kim`.
+//! If that makes several entities end up with the same component,
+//! all but the first in order of their names get a number appended,
+//! e.g. `Kim` and `kim` become `Kim` and `Kim-2`.
+//!
+//! # Dates
+//!
+//! Transfers without a date get the one of the previous transfer.
+//! If there is none, they get the first date in the ledger,
+//! or the fallback date given to [`to_writer`] if no transfer has a date at all.
+//! All accounts are opened on the earliest date,
+//! since the dates don't have to be in order.
+//!
+//! At the end, there's a balance assertion for every account
+//! according to [`State::balances`] on the day after the latest date,
+//! so checking the file also checks it was exported correctly.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use crate::{
+    ext::{Balance, Date, Integer},
+    runtime::{
+        State,
+        cmd::Name,
+        model::{Dir, Entity, Pair, Product, Transfer},
+    },
+};
+
+/// Writes all transfers in `state` as Beancount file.
+///
+/// `undated` is used as date if no transfer has one.
+pub fn to_writer(state: &State, undated: Date, mut writer: impl Write) -> io::Result<()> {
+    writer.write_all(to_string(state, undated).as_bytes())
+}
+
+/// [`to_writer`], but into a string.
+pub fn to_string(state: &State, undated: Date) -> String {
+    let first = state.ledger.iter().find_map(date).unwrap_or(undated);
+    let accounts = Accounts::new(state);
+
+    let mut date = first;
+    let (mut min, mut max) = (first, first);
+    let mut out = String::new();
+    let mut pairs = BTreeSet::new();
+
+    for transfer in &state.ledger {
+        date = self::date(transfer).unwrap_or(date);
+        min = min.min(date);
+        max = max.max(date);
+
+        let who = transfer.who();
+        let narration = match transfer {
+            Transfer::Pay(_) => "Payment".to_owned(),
+            Transfer::Deliver(deliver) => narration(&deliver.product),
+        };
+
+        // same as the runtime does it, so the sign matches the stored balance
+        let mut delta: Balance = transfer.shifted().into();
+        let pair = delta.take_order(Dir::clone(who));
+        let Balance(delta) = delta;
+
+        // its effect on the balances was undone, but the accounts still exist
+        pairs.insert(pair.clone());
+        if transfer.is_voided() {
+            continue;
+        }
+
+        writeln!(out, "{date} * {}", quote(&narration)).unwrap();
+        writeln!(out, "  from: {}", quote(who.source().name())).unwrap();
+        writeln!(out, "  to: {}", quote(who.target().name())).unwrap();
+        if let Some(memo) = transfer.memo() {
+            writeln!(out, "  memo: {}", quote(memo)).unwrap();
+        }
+        let receivable = accounts.receivable(&pair);
+        writeln!(out, "  {receivable}  {} EUR", amount(&delta)).unwrap();
+        writeln!(
+            out,
+            "  {}  {} EUR",
+            accounts.payable(&pair),
+            amount(&-delta)
+        )
+        .unwrap();
+        writeln!(out).unwrap();
+    }
+
+    // balance assertions are checked at the start of the day,
+    // so they go after the last transfer, wherever it is in the ledger
+    let end = max.0.next_day().map_or(max, Date);
+    for (pair, Balance(balance)) in &state.balances {
+        writeln!(
+            out,
+            "{end} balance {}  {} EUR",
+            accounts.receivable(pair),
+            amount(balance)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{end} balance {}  {} EUR",
+            accounts.payable(pair),
+            amount(&-balance)
+        )
+        .unwrap();
+    }
+
+    // accounts have to be open before the earliest transfer
+    let mut header = String::from("option \"operating_currency\" \"EUR\"\n\n");
+    for pair in &pairs {
+        writeln!(header, "{min} open {} EUR", accounts.receivable(pair)).unwrap();
+        writeln!(header, "{min} open {} EUR", accounts.payable(pair)).unwrap();
+    }
+    if !pairs.is_empty() {
+        header.push('\n');
+    }
+
+    header + &out
+}
+
+fn date(transfer: &Transfer) -> Option<Date> {
+    match transfer {
+        Transfer::Pay(pay) => pay.date,
+        Transfer::Deliver(deliver) => deliver.date,
+    }
+}
+
+/// Name of the concept the product is an instance of, if any.
+fn narration(product: &Product) -> String {
+    match product {
+        Product::Concept(concept) => concept.name().to_owned(),
+        Product::Object(object) => match (object.parent(), object.name()) {
+            (Some(parent), _) => parent.name().to_owned(),
+            (None, Some(name)) => name.to_owned(),
+            (None, None) => unreachable!("objects without parent are always named"),
+        },
+    }
+}
+
+/// Account name component of every entity, unique among all of them.
+struct Accounts(HashMap<Name, String>);
+
+impl Accounts {
+    /// Assigns components in order of the names,
+    /// so e.g. `Kim` gets `Kim` and `kim` gets `Kim-2`.
+    fn new(state: &State) -> Self {
+        let transferred = state.ledger.iter().flat_map(|transfer| {
+            let who = transfer.who();
+            [who.source(), who.target()]
+        });
+        let names: BTreeSet<_> = state
+            .balances
+            .keys()
+            .flat_map(|pair| [pair.a(), pair.b()])
+            .chain(transferred)
+            .map(|entity| entity.name())
+            .collect();
+
+        let mut used = HashSet::new();
+        let mut components = HashMap::new();
+        for name in names {
+            let base = component(name);
+            let unique = (1..)
+                .map(|n| match n {
+                    1 => base.clone(),
+                    n => format!("{base}-{n}"),
+                })
+                .find(|candidate| !used.contains(candidate))
+                .unwrap();
+            used.insert(unique.clone());
+            components.insert(name.to_owned(), unique);
+        }
+
+        Self(components)
+    }
+
+    fn component(&self, entity: &Entity) -> &str {
+        &self.0[entity.name()]
+    }
+
+    fn receivable(&self, pair: &Pair) -> String {
+        format!(
+            "Assets:Receivable:{}:{}",
+            self.component(pair.b()),
+            self.component(pair.a())
+        )
+    }
+
+    fn payable(&self, pair: &Pair) -> String {
+        format!(
+            "Liabilities:Payable:{}:{}",
+            self.component(pair.a()),
+            self.component(pair.b())
+        )
+    }
+}
+
+/// Makes `name` a valid account name component:
+/// starting with an uppercase letter or digit,
+/// followed by letters, digits or dashes.
+fn component(name: &str) -> String {
+    let mut chars = name.chars();
+    let first = chars.next().into_iter().flat_map(char::to_uppercase);
+    let rest = chars.map(|c| if c.is_alphanumeric() { c } else { '-' });
+    first.chain(rest).collect()
+}
+
+/// Cents as decimal number of euros, e.g. `-1.50`.
+fn amount(cents: &Integer) -> String {
+    let sign = if cents.sign() == num_bigint::Sign::Minus {
+        "-"
+    } else {
+        ""
+    };
+    let abs = cents.magnitude();
+    format!("{sign}{}.{:02}", abs / 100u8, abs % 100u8)
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval;
+
+    use super::*;
+
+    #[test]
+    fn export() {
+        let rt = eval(
+            "
+            create entity { Store; kim; Lou }
+            create concept Mate price=1.50€
+            create object Keyboard parent=Mate
+
+            deliver Mate from=Store to=kim
+            pay 5€ from=Lou to=kim date=2024-03-01
+            deliver Keyboard price=20€ from=kim to=Lou split=1:1
+            ",
+        )
+        .unwrap();
+        let out = to_string(rt.state(), "2000-01-01".parse().unwrap());
+
+        assert_eq!(
+            out,
+            r#"option "operating_currency" "EUR"
+
+2024-03-01 open Assets:Receivable:Kim:Lou EUR
+2024-03-01 open Liabilities:Payable:Lou:Kim EUR
+2024-03-01 open Assets:Receivable:Kim:Store EUR
+2024-03-01 open Liabilities:Payable:Store:Kim EUR
+
+2024-03-01 * "Mate"
+  from: "Store"
+  to: "kim"
+  Assets:Receivable:Kim:Store  -1.50 EUR
+  Liabilities:Payable:Store:Kim  1.50 EUR
+
+2024-03-01 * "Payment"
+  from: "Lou"
+  to: "kim"
+  Assets:Receivable:Kim:Lou  -5.00 EUR
+  Liabilities:Payable:Lou:Kim  5.00 EUR
+
+2024-03-01 * "Mate"
+  from: "kim"
+  to: "Lou"
+  Assets:Receivable:Kim:Lou  10.00 EUR
+  Liabilities:Payable:Lou:Kim  -10.00 EUR
+
+2024-03-02 balance Assets:Receivable:Kim:Lou  5.00 EUR
+2024-03-02 balance Liabilities:Payable:Lou:Kim  -5.00 EUR
+2024-03-02 balance Assets:Receivable:Kim:Store  -1.50 EUR
+2024-03-02 balance Liabilities:Payable:Store:Kim  1.50 EUR
+"#
+        );
+    }
+
+    #[test]
+    fn unsorted_and_colliding() {
+        let rt = eval(
+            "
+            create entity { kim; Kim; Kim_2 }
+
+            pay 1€ from=kim to=Kim date=2024-03-05
+            pay 2€ from=Kim to=Kim_2 date=2024-03-01
+            pay 3€ from=Kim_2 to=kim date=2024-03-03
+            ",
+        )
+        .unwrap();
+        let out = to_string(rt.state(), "2000-01-01".parse().unwrap());
+
+        let lines = |prefix: &str| {
+            out.lines()
+                .filter(|line| line.starts_with(prefix))
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        // opened before the earliest transfer, checked after the latest
+        assert_eq!(lines("2024-03-01 open").len(), 6);
+        assert_eq!(lines("2024-03-06 balance").len(), 6);
+        assert!(!out.contains("2024-03-05 open") && !out.contains("2024-03-04 balance"));
+
+        // in order of names: Kim, Kim_2, kim
+        assert_eq!(
+            lines("2024-03-01 open Assets"),
+            [
+                "2024-03-01 open Assets:Receivable:Kim-2:Kim EUR",
+                "2024-03-01 open Assets:Receivable:Kim-3:Kim EUR",
+                "2024-03-01 open Assets:Receivable:Kim-3:Kim-2 EUR",
+            ]
+        );
+    }
+}
@@ -1,15 +1,263 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 
-use clap::{Args, Parser};
-use eyre::{Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use eyre::{Context, Result, bail};
+
+use super::{Date, locale::Locale};
 
 #[derive(Parser, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[command(version, about, author)]
+#[command(
+    version,
+    about,
+    author,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Config {
+    #[command(subcommand)]
+    pub cmd: Option<Cmd>,
+
+    /// What to do without subcommand, the same as `run`.
+    #[command(flatten)]
+    pub run: Run,
+
+    /// How money is written in source code and printed:
+    /// `plain` (1234.50 €), `en` (€1,234.50) or `de` (1.234,50 €).
+    #[arg(long, global = true, default_value = "plain")]
+    pub locale: Locale,
+
+    /// Separate euros and cents by this instead of as `--locale` does:
+    /// `.`, `,` or `'`.
+    #[arg(long, global = true, value_name = "CHAR", value_parser = [".", ",", "'"])]
+    pub decimal_separator: Option<String>,
+
+    /// Group the euros by 3 digits with this instead of as `--locale` does:
+    /// `.`, `,`, `'` or `none` to not group them.
+    #[arg(long, global = true, value_name = "CHAR", value_parser = [".", ",", "'", "none"])]
+    pub thousands_separator: Option<String>,
+
+    /// Print the euro sign before or after the amount instead of as `--locale` does.
+    /// Either is always accepted in source code.
+    #[arg(long, global = true)]
+    pub symbol_position: Option<SymbolPosition>,
+
+    /// Also accept one digit after the decimal separator, e.g. `1.5€`.
+    #[arg(long, global = true)]
+    pub short_fractions: bool,
+
+    /// Evaluate the ledger as of this day, formatted as `YYYY-MM-DD`:
+    /// recurring transfers from `every` only happen up to and including it.
+    /// Without it, each `every` needs an `until`.
+    /// Not with `--snapshot`, which would keep what was repeated up to an earlier day.
+    #[arg(long, global = true, value_name = "DATE")]
+    pub until: Option<Date>,
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Run {
+    #[command(flatten)]
+    pub source: Source,
+
+    /// After running, write the final state as JSON to this file.
+    /// See the `ext::json` module docs for the schema.
+    #[arg(long, value_name = "PATH")]
+    pub emit: Option<PathBuf>,
+
+    /// Resume from the snapshot in this file if it exists,
+    /// only running what was appended to the source since,
+    /// and save a new snapshot there afterwards.
+    // the conflict is declared here since subcommands only have `until`
+    #[arg(long, value_name = "PATH", conflicts_with = "until")]
+    pub snapshot: Option<PathBuf>,
+
+    /// After running, write all transfers as Beancount file to this path.
+    /// Transfers are dated today if none in the ledger has a date.
+    #[arg(long, value_name = "PATH")]
+    pub beancount: Option<PathBuf>,
+
+    /// Also write the state into this SQLite database
+    /// for querying with other tools.
+    /// It is cleared first.
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "PATH", conflicts_with = "snapshot")]
+    pub sqlite: Option<PathBuf>,
+}
+
+/// Where the euro sign is printed.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SymbolPosition {
+    /// As in `€1.50`.
+    Before,
+    /// As in `1.50 €`.
+    After,
+}
+
+/// Something else to do than just running source code.
+#[derive(Subcommand, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cmd {
+    /// Run the source, printing what its queries answer.
+    /// The same as without subcommand.
+    Run(Run),
+    /// Read GTINs line by line from stdin, e.g. typed by a barcode scanner,
+    /// and record each of them as delivery.
+    Scan(Scan),
+    /// Turn rows of a CSV file, e.g. exported from a spreadsheet or Splitwise,
+    /// into receipts.
+    ImportCsv(ImportCsv),
+    /// Run the source and print who owes whom as Graphviz DOT digraph,
+    /// e.g. to pipe into `dot -Tsvg`.
+    Graph(Graph),
+    /// Run the source, warning about statements that are likely mistakes
+    /// and reporting all failing statements instead of just the first one.
+    /// Lints can be configured per file with comments like `#lint allow unused`.
+    /// Queries are only checked, not answered, and nothing is written.
+    Check(Check),
+    /// Run the source and print how much one entity owes another.
+    /// Queries in the source are only checked, and nothing is written.
+    Balance(Balance),
+    /// Run the source and print every transfer in the order they happened,
+    /// with labels, memos and whether they were voided.
+    /// Queries in the source are only checked, and nothing is written.
+    History(History),
+    /// Run the source and print payments that would settle all debts.
+    /// Queries in the source are only checked, and nothing is written.
+    Settle(Settle),
+    /// Print the syntax, parameters, defaults and examples of a command,
+    /// like `help` in source code does, or list all commands.
+    Explain(Explain),
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Scan {
+    /// Entity delivering the scanned products, usually a store.
+    #[arg(long)]
+    pub from: String,
+
+    /// Entity receiving the scanned products.
+    #[arg(long)]
+    pub to: String,
+
+    /// Ledger to run before scanning and to append the session to afterwards.
+    /// If not specified, the session is printed to stdout instead.
+    pub ledger: Option<PathBuf>,
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ImportCsv {
+    /// CSV file to import, with a header row naming the columns.
+    pub csv: PathBuf,
+
+    /// Ledger to run before importing and to append the statements to afterwards.
+    /// If not specified, the statements are printed to stdout instead.
+    #[arg(long)]
+    pub ledger: Option<PathBuf>,
+
+    /// Create entities that don't exist yet instead of rejecting their rows.
+    #[arg(long)]
+    pub create_entities: bool,
+
+    /// Concept to record each expense as.
+    #[arg(long, default_value = "Expense")]
+    pub concept: String,
+
+    /// Column with the date, given to the receipt as `date`
+    /// or put into a comment if it isn't in the format `YYYY-MM-DD`.
+    #[arg(long, value_name = "COLUMN", default_value = "date")]
+    pub date: String,
+
+    /// Column with the entity who paid.
+    #[arg(long, value_name = "COLUMN", default_value = "payer")]
+    pub payer: String,
+
+    /// Column with the amount paid.
+    #[arg(long, value_name = "COLUMN", default_value = "amount")]
+    pub amount: String,
+
+    /// Column listing all entities taking part in the expense.
+    #[arg(long, value_name = "COLUMN", default_value = "participants")]
+    pub participants: String,
+
+    /// What separates the names in the participants column.
+    #[arg(long, default_value_t = ',')]
+    pub separator: char,
+
+    /// Instead of one participants column,
+    /// one column per entity, named like it.
+    /// Like in Splitwise exports, each cell is the negated share the entity owes,
+    /// except for the payer, whose cell is what the others owe them.
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    pub participant_columns: Vec<String>,
+
+    /// Column with a description, put into a comment.
+    #[arg(long, value_name = "COLUMN", default_value = "description")]
+    pub description: String,
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Graph {
+    #[command(flatten)]
+    pub source: Source,
+
+    /// Draw the payments that would settle all debts
+    /// instead of the balances themselves.
+    #[arg(long)]
+    pub settle: bool,
+
+    /// Draw these entities in a box, written as `NAME=A,B,...`.
+    /// Can be given multiple times.
+    #[arg(long, value_name = "NAME=ENTITIES")]
+    pub group: Vec<String>,
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Check {
+    #[command(flatten)]
+    pub source: Source,
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Balance {
+    /// Entity who might be owed.
+    pub from: String,
+
+    /// Entity who might owe.
+    pub to: String,
+
+    #[command(flatten)]
+    pub source: Source,
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct History {
+    /// Only transfers from or to this entity.
+    #[arg(long)]
+    pub entity: Option<String>,
+
+    /// Only transfers with this tag.
+    #[arg(long)]
+    pub tag: Option<String>,
+
+    #[command(flatten)]
+    pub source: Source,
+}
+
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Settle {
     #[command(flatten)]
     pub source: Source,
 }
 
+#[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Explain {
+    /// Command to explain, optionally followed by its form,
+    /// as in `explain create concept`.
+    #[arg(value_name = "COMMAND")]
+    pub topic: Vec<String>,
+}
+
 /// The source code to run.
 #[derive(Args, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[group(required = true)] // multiple is false by default
@@ -23,10 +271,15 @@ pub struct Source {
 }
 
 impl Source {
+    /// The file the source code is read from, if any.
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+
     /// Returns the source code to run, reading if necessary.
-    pub fn get(self) -> Result<String> {
-        if let Some(code) = self.code {
-            return Ok(code);
+    pub fn get(&self) -> Result<String> {
+        if let Some(code) = &self.code {
+            return Ok(code.clone());
         }
         if let Some(file) = &self.file {
             return fs::read_to_string(file)
@@ -45,3 +298,89 @@ impl Source {
 pub fn cli() -> Config {
     Parser::parse()
 }
+
+impl Config {
+    /// The locale with everything configured applied.
+    ///
+    /// # Errors
+    ///
+    /// If the decimal and thousands separator end up the same.
+    pub fn locale(&self) -> Result<Locale> {
+        // clap only lets through single characters or `none`
+        let char = |sep: &str| sep.chars().next().unwrap();
+
+        let mut locale = Locale {
+            short_fraction: self.short_fractions,
+            ..self.locale
+        };
+        if let Some(sep) = &self.decimal_separator {
+            locale.decimal = char(sep);
+        }
+        if let Some(sep) = &self.thousands_separator {
+            locale.thousands = (sep != "none").then(|| char(sep));
+        }
+        if let Some(position) = self.symbol_position {
+            locale.symbol_first = position == SymbolPosition::Before;
+        }
+
+        if locale.thousands == Some(locale.decimal) {
+            bail!(
+                "the decimal and thousands separator need to differ, but both are `{}`",
+                locale.decimal
+            );
+        }
+        Ok(locale)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn consistent() {
+        Config::command().debug_assert();
+    }
+
+    #[test]
+    fn locale() {
+        let locale = |args: &[&str]| {
+            let args = ["nyandere", "--code", ""].iter().chain(args);
+            Config::try_parse_from(args).unwrap().locale()
+        };
+
+        assert_eq!(locale(&[]).unwrap(), Locale::PLAIN);
+        let swiss = locale(&[
+            "--locale",
+            "de",
+            "--decimal-separator",
+            ".",
+            "--thousands-separator",
+            "'",
+            "--symbol-position",
+            "before",
+        ])
+        .unwrap();
+        assert_eq!(
+            swiss,
+            Locale {
+                decimal: '.',
+                thousands: Some('\''),
+                symbol_first: true,
+                short_fraction: false,
+            }
+        );
+        assert_eq!(
+            locale(&["--locale", "en", "--thousands-separator", "none"])
+                .unwrap()
+                .thousands,
+            None
+        );
+
+        assert!(locale(&["--locale", "de", "--decimal-separator", "."]).is_err());
+        let args = ["nyandere", "--code", "", "--decimal-separator", ";"];
+        assert!(Config::try_parse_from(args).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+//! Render who owes whom as [Graphviz](https://graphviz.org/) DOT digraph.
+//!
+//! Every entity is a node.
+//! Every non-zero balance is an edge from debtor to creditor,
+//! labelled with the amount.
+//! Pipe it into e.g. `dot -Tsvg` to get an image.
+
+use std::fmt::Write as _;
+
+use eyre::{Context, Result, bail};
+
+use crate::{
+    eval,
+    ext::{Debit, config},
+    runtime::State,
+};
+
+/// What to draw besides the plain balances.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    /// Draw the payments suggested by [`State::settle`]
+    /// instead of the balances.
+    pub settle: bool,
+    /// Named groups of entities to draw in a box each.
+    /// Entities in no group are drawn outside of any box.
+    pub groups: Vec<Group>,
+}
+
+/// Entities to draw close together, in a box labelled `name`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+/// Runs the configured source and prints its debt graph to stdout.
+pub fn cli(cfg: config::Graph) -> Result<()> {
+    let groups = cfg
+        .group
+        .iter()
+        .map(|group| group.parse())
+        .collect::<Result<_>>()?;
+    let script = cfg.source.get().wrap_err("while loading source")?;
+    let runtime = eval(script)?;
+
+    let options = Options {
+        settle: cfg.settle,
+        groups,
+    };
+    print!("{}", to_dot(runtime.state(), &options));
+    Ok(())
+}
+
+impl std::str::FromStr for Group {
+    type Err = eyre::Report;
+
+    /// Parses `NAME=A,B,...`.
+    fn from_str(source: &str) -> Result<Self> {
+        let Some((name, members)) = source.split_once('=') else {
+            bail!("group `{source}` needs to be written as `NAME=A,B,...`");
+        };
+        Ok(Self {
+            name: name.trim().to_owned(),
+            members: members
+                .split(',')
+                .map(str::trim)
+                .filter(|member| !member.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        })
+    }
+}
+
+/// Renders the debt graph of `state` as DOT source.
+pub fn to_dot(state: &State, options: &Options) -> String {
+    let mut out = String::from("digraph debts {\n");
+
+    for (i, group) in options.groups.iter().enumerate() {
+        writeln!(out, "  subgraph cluster_{i} {{").unwrap();
+        writeln!(out, "    label={};", quote(&group.name)).unwrap();
+        for member in &group.members {
+            writeln!(out, "    {};", quote(member)).unwrap();
+        }
+        writeln!(out, "  }}").unwrap();
+    }
+
+    for entity in state.entities.values() {
+        writeln!(out, "  {};", quote(entity.name())).unwrap();
+    }
+
+    let edges = if options.settle {
+        state.settle()
+    } else {
+        state.debts()
+    };
+    for Debit { between, amount } in edges {
+        writeln!(
+            out,
+            "  {} -> {} [label={}];",
+            quote(between.source().name()),
+            quote(between.target().name()),
+            quote(&amount.to_string()),
+        )
+        .unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval;
+
+    use super::*;
+
+    const CHAIN: &str = "
+        create entity { A; B; C; D }
+        pay 5€ from=B to=A
+        pay 5€ from=C to=B
+        pay 2€ from=D to=C
+    ";
+
+    #[test]
+    fn balances() {
+        let rt = eval(CHAIN).unwrap();
+        let dot = to_dot(rt.state(), &Options::default());
+
+        assert_eq!(
+            dot,
+            r#"digraph debts {
+  "A";
+  "B";
+  "C";
+  "D";
+  "A" -> "B" [label="5.00 €"];
+  "B" -> "C" [label="5.00 €"];
+  "C" -> "D" [label="2.00 €"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn settle() {
+        let rt = eval(CHAIN).unwrap();
+        let dot = to_dot(
+            rt.state(),
+            &Options {
+                settle: true,
+                groups: vec![Group {
+                    name: "Flat".to_owned(),
+                    members: vec!["A".to_owned(), "B".to_owned()],
+                }],
+            },
+        );
+
+        assert_eq!(
+            dot,
+            r#"digraph debts {
+  subgraph cluster_0 {
+    label="Flat";
+    "A";
+    "B";
+  }
+  "A";
+  "B";
+  "C";
+  "D";
+  "A" -> "C" [label="3.00 €"];
+  "A" -> "D" [label="2.00 €"];
+}
+"#
+        );
+    }
+}
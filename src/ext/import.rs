@@ -0,0 +1,590 @@
+//! Turn rows of a CSV file into receipts.
+//!
+//! Useful for migrating from a spreadsheet or Splitwise.
+//! Each row is one expense:
+//! someone paid an amount for a group of participants,
+//! each of them owing a part of it.
+//! This becomes a [`receipt`][crate::runtime::cmd::Receipt]
+//! with one line of a generic concept (`Expense` by default):
+//!
+//! ```text
+//! receipt from=Kim {
+//!     Expense 12.50€ for Kim Lou
+//! }
+//! ```
+//!
+//! If the date is in the format `YYYY-MM-DD`, it is given to the receipt,
+//! otherwise it's put in a comment above along with the description.
+//!
+//! Which columns hold what is configured by [`Columns`],
+//! looking them up by name in the header row.
+//! Participants are either listed in one column,
+//! splitting the amount equally,
+//! or each have their own column with the negated share they owe
+//! (which is what Splitwise exports look like).
+//! Then the receipt has one line per distinct share.
+
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+};
+
+use eyre::{Context, Result, bail, format_err};
+use thiserror::Error;
+
+use crate::{
+    Runtime, eval, eval_in,
+    syntax::ast::{Arg, Args, Block, Command, Stmt, Value},
+};
+
+use super::{Date, Money, Natural, config};
+
+/// Imports a CSV file as configured on the command line.
+pub fn cli(cfg: config::ImportCsv) -> Result<()> {
+    let runtime = match &cfg.ledger {
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .wrap_err_with(|| format!("tried to read `{}`", path.display()))?;
+            eval(source)?
+        }
+        None => Runtime::new(),
+    };
+
+    let columns = Columns {
+        date: Some(cfg.date),
+        payer: cfg.payer,
+        amount: cfg.amount,
+        participants: if cfg.participant_columns.is_empty() {
+            Participants::List {
+                column: cfg.participants,
+                separator: cfg.separator,
+            }
+        } else {
+            Participants::Columns(cfg.participant_columns)
+        },
+        description: Some(cfg.description),
+    };
+
+    let file = fs::File::open(&cfg.csv)
+        .wrap_err_with(|| format!("tried to open `{}`", cfg.csv.display()))?;
+    let mut import = Import::new(runtime, columns);
+    import.create_entities = cfg.create_entities;
+    import.concept = cfg.concept;
+    import.read(file)?;
+
+    let out = import.to_source();
+    match &cfg.ledger {
+        // nothing imported, so leave the ledger as-is
+        Some(_) if out.is_empty() => {}
+        Some(path) => {
+            let mut file = fs::OpenOptions::new()
+                .append(true)
+                .open(path)
+                .wrap_err_with(|| format!("tried to open `{}` for appending", path.display()))?;
+            write!(file, "\n{out}")?;
+        }
+        None => io::stdout().write_all(out.as_bytes())?,
+    }
+
+    Ok(())
+}
+
+/// Names of the columns in the header row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Columns {
+    /// Given to the receipt if it's a valid date,
+    /// otherwise put into a comment.
+    /// Ignored if the column doesn't exist.
+    pub date: Option<String>,
+    /// Entity who paid.
+    pub payer: String,
+    /// Amount paid, e.g. `12.50`, `12,5` or `12.50€`.
+    pub amount: String,
+    pub participants: Participants,
+    /// Put into a comment, ignored if the column doesn't exist.
+    pub description: Option<String>,
+}
+
+/// Where to find who took part in an expense.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Participants {
+    /// One column with all names, separated by `separator`.
+    List { column: String, separator: char },
+    /// One column per entity, named like it.
+    /// Each cell is the negated share the entity owes,
+    /// or empty or zero if it didn't take part.
+    /// The payer's cell is what all others owe them, if not empty.
+    Columns(Vec<String>),
+}
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self {
+            date: Some("date".to_owned()),
+            payer: "payer".to_owned(),
+            amount: "amount".to_owned(),
+            participants: Participants::List {
+                column: "participants".to_owned(),
+                separator: ',',
+            },
+            description: Some("description".to_owned()),
+        }
+    }
+}
+
+/// Rows that couldn't be imported.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub struct Malformed(pub Vec<Row>);
+
+/// Why the row with the given number couldn't be imported.
+///
+/// Rows are counted like in a spreadsheet,
+/// the header being row 1.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+#[error("row {row}: {reason}")]
+pub struct Row {
+    pub row: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for Malformed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} malformed rows, nothing was imported", self.0.len())?;
+        for row in &self.0 {
+            write!(f, "\n{row}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Rows imported into a runtime.
+#[derive(Debug)]
+pub struct Import {
+    runtime: Runtime,
+    columns: Columns,
+    /// Create entities that don't exist yet instead of rejecting the row.
+    pub create_entities: bool,
+    /// Concept each expense is delivered as,
+    /// created if it doesn't exist yet.
+    pub concept: String,
+    /// Statements each row produced, in order.
+    stmts: Vec<String>,
+}
+
+impl Import {
+    pub fn new(runtime: Runtime, columns: Columns) -> Self {
+        Self {
+            runtime,
+            columns,
+            create_entities: false,
+            concept: "Expense".to_owned(),
+            stmts: Vec::new(),
+        }
+    }
+
+    /// The runtime with all rows so far run in it.
+    pub fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
+    /// All statements the rows made, as source code.
+    pub fn to_source(&self) -> String {
+        self.stmts.iter().map(|s| format!("{s}\n")).collect()
+    }
+
+    /// Reads all rows of the CSV in `input` and runs them.
+    ///
+    /// # Errors
+    ///
+    /// If any row is malformed, none are imported
+    /// and [`Malformed`] lists all of them.
+    /// Also fails if the header is missing a column.
+    pub fn read(&mut self, input: impl io::Read) -> Result<()> {
+        let mut reader = csv::Reader::from_reader(input);
+        let header = Header::new(reader.headers()?, &self.columns)?;
+
+        let mut stmts = Vec::new();
+        let mut malformed = Vec::new();
+
+        // so nothing is kept if any row is malformed
+        self.runtime.begin()?;
+
+        for (idx, record) in reader.records().enumerate() {
+            // header is row 1
+            let row = idx + 2;
+            let outcome = record
+                .map_err(Into::into)
+                .and_then(|record| self.row(&self.runtime, &header, &record))
+                .and_then(|row_stmts| {
+                    eval_in(&mut self.runtime, &row_stmts.join("\n"), 0)?;
+                    Ok(row_stmts)
+                });
+
+            match outcome {
+                Ok(row_stmts) => stmts.extend(row_stmts),
+                Err(err) => malformed.push(Row {
+                    row,
+                    reason: format!("{err:#}"),
+                }),
+            }
+        }
+
+        if !malformed.is_empty() {
+            self.runtime.abort()?;
+            return Err(Malformed(malformed).into());
+        }
+
+        self.runtime.commit()?;
+        self.stmts.extend(stmts);
+        Ok(())
+    }
+
+    /// Statements for one row.
+    fn row(
+        &self,
+        runtime: &Runtime,
+        header: &Header,
+        record: &csv::StringRecord,
+    ) -> Result<Vec<String>> {
+        let get = |idx: usize| record.get(idx).unwrap_or_default().trim();
+
+        let payer = get(header.payer);
+        let amount = parse_amount(get(header.amount))?;
+        // who owes which part of the amount, as (part, participants) each
+        let parts = match &header.participants {
+            HeaderParticipants::List { column, separator } => {
+                let participants = get(*column)
+                    .split(*separator)
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .collect::<Vec<_>>();
+                if participants.is_empty() {
+                    bail!("no participants");
+                }
+                vec![(amount, participants)]
+            }
+            HeaderParticipants::Columns(columns) => {
+                let cells = columns.iter().map(|(name, idx)| (name.as_str(), get(*idx)));
+                column_parts(payer, amount, cells)?
+            }
+        };
+
+        let mut stmts = Vec::new();
+
+        // dates the runtime understands go on the receipt, all others into the comment
+        let cell = header.date.map(get).unwrap_or_default();
+        let date = cell.parse::<Date>().ok();
+        let comment = [date.is_none().then_some(cell), header.description.map(get)]
+            .into_iter()
+            .flatten()
+            .filter(|cell| !cell.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace(['\r', '\n'], " ");
+        if !comment.is_empty() {
+            stmts.push(format!("# {comment}"));
+        }
+
+        if runtime.get_concept(&self.concept).is_err() {
+            let args = vec![
+                Arg::Pos(Value::name("concept")),
+                Arg::Pos(Value::name(&self.concept)),
+            ];
+            stmts.push(Stmt::new(Command::Create, args).to_string());
+        }
+
+        let mut names = vec![payer];
+        names.extend(parts.iter().flat_map(|(_, participants)| participants));
+        names.dedup();
+        for name in names {
+            if runtime.get_entity(name).is_ok() {
+                continue;
+            }
+            if !self.create_entities {
+                bail!("unknown entity `{name}` (allow creating it if that's not a typo)");
+            }
+
+            let args = vec![Arg::Pos(Value::name("entity")), Arg::Pos(Value::name(name))];
+            let create = Stmt::new(Command::Create, args).to_string();
+            if !stmts.contains(&create) {
+                stmts.push(create);
+            }
+        }
+
+        let lines = parts
+            .into_iter()
+            .map(|(part, participants)| {
+                let mut line = vec![
+                    Arg::Pos(Value::name(&self.concept)),
+                    Arg::Pos(Value::Money(part)),
+                    Arg::Pos(Value::name("for")),
+                ];
+                line.extend(participants.into_iter().map(|p| Arg::Pos(Value::name(p))));
+                Args(line)
+            })
+            .collect();
+
+        let mut receipt_args = vec![Arg::named("from", Value::name(payer))];
+        if let Some(date) = date {
+            receipt_args.push(Arg::named("date", Value::Date(date)));
+        }
+        let receipt = Stmt {
+            block: Some(Block(lines)),
+            ..Stmt::new(Command::Receipt, receipt_args)
+        };
+        stmts.push(receipt.to_string());
+
+        Ok(stmts)
+    }
+}
+
+/// [`Columns`] resolved to indices.
+struct Header {
+    date: Option<usize>,
+    payer: usize,
+    amount: usize,
+    participants: HeaderParticipants,
+    description: Option<usize>,
+}
+
+enum HeaderParticipants {
+    List { column: usize, separator: char },
+    Columns(Vec<(String, usize)>),
+}
+
+impl Header {
+    fn new(record: &csv::StringRecord, columns: &Columns) -> Result<Self> {
+        let indices: HashMap<_, _> = record
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| (name.trim(), idx))
+            .collect();
+        let find = |name: &str| indices.get(name).copied();
+        let req = |name: &str| find(name).ok_or_else(|| format_err!("missing column `{name}`"));
+
+        Ok(Self {
+            date: columns.date.as_deref().and_then(find),
+            payer: req(&columns.payer)?,
+            amount: req(&columns.amount)?,
+            participants: match &columns.participants {
+                Participants::List { column, separator } => HeaderParticipants::List {
+                    column: req(column)?,
+                    separator: *separator,
+                },
+                Participants::Columns(names) => HeaderParticipants::Columns(
+                    names
+                        .iter()
+                        .map(|name| Ok((name.clone(), req(name)?)))
+                        .collect::<Result<_>>()?,
+                ),
+            },
+            description: columns.description.as_deref().and_then(find),
+        })
+    }
+}
+
+/// Parses amounts as typically found in spreadsheets,
+/// like `12`, `12.5`, `12,50` or `12.50 €`.
+fn parse_amount(src: &str) -> Result<Money> {
+    let digits = src
+        .trim_end_matches(['€', ' '])
+        .trim_end_matches("EUR")
+        .trim();
+
+    let (whole, frac) = digits.split_once(['.', ',']).unwrap_or((digits, ""));
+    let numeric = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !numeric(whole) || !numeric(frac) || frac.len() > 2 {
+        bail!("`{src}` is not a positive amount of money with at most 2 decimal places");
+    }
+
+    let whole: Natural = whole.parse()?;
+    let frac: Natural = format!("{frac:0<2}").parse()?;
+    Ok(Money(whole * 100u8 + frac))
+}
+
+/// Splits `amount` paid by `payer` into parts
+/// as given by the participant columns.
+///
+/// Like in Splitwise exports,
+/// each participant who owes something has the negated share in their cell
+/// and the payer what all others owe them together.
+/// Whatever is left of `amount` is what the payer owes themselves.
+/// Participants owing the same share are grouped into one part.
+fn column_parts<'a>(
+    payer: &'a str,
+    amount: Money,
+    cells: impl Iterator<Item = (&'a str, &'a str)>,
+) -> Result<Vec<(Money, Vec<&'a str>)>> {
+    let mut shares = Vec::new();
+    let mut owed = Money(Natural::ZERO);
+    let mut lent = None;
+
+    for (name, cell) in cells {
+        if is_zero(cell) {
+            continue;
+        }
+        match cell.strip_prefix('-') {
+            Some(share) => {
+                let share = parse_amount(share)?;
+                owed += share.clone();
+                shares.push((name, share));
+            }
+            None if name == payer => lent = Some(parse_amount(cell)?),
+            None => bail!("`{name}` is owed `{cell}`, but only `{payer}` paid"),
+        }
+    }
+
+    if shares.is_empty() {
+        bail!("no participants");
+    }
+    if owed > amount {
+        bail!("participants owe {owed} together, more than the {amount} paid");
+    }
+    if let Some(lent) = lent.filter(|lent| *lent != owed) {
+        bail!("`{payer}` is owed {lent}, but the participants owe {owed} together");
+    }
+
+    let own = Money(amount.0 - owed.0);
+    if own.0 != Natural::ZERO {
+        shares.push((payer, own));
+    }
+
+    let mut groups: Vec<(Money, Vec<&str>)> = Vec::new();
+    for (name, share) in shares {
+        match groups.iter_mut().find(|(other, _)| *other == share) {
+            Some((_, names)) => names.push(name),
+            None => groups.push((share, vec![name])),
+        }
+    }
+
+    let parts = groups
+        .into_iter()
+        .map(|(share, names)| (Money(share.0 * names.len()), names))
+        .collect();
+    Ok(parts)
+}
+
+/// Whether a participant column cell means "not involved".
+fn is_zero(cell: &str) -> bool {
+    cell.is_empty() || parse_amount(cell.trim_start_matches('-')).is_ok_and(|m| m.0 == 0u8.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::Integer;
+
+    use super::*;
+
+    #[test]
+    fn list() {
+        let csv = "\
+date,payer,amount,participants,description
+2023-01-05,Kim,12.50,\"Kim, Lou\",Pizza night
+2023-01-06,Lou,3,Kim,
+";
+        let mut import = Import::new(Runtime::new(), Columns::default());
+        import.create_entities = true;
+        import.read(csv.as_bytes()).unwrap();
+
+        let source = import.to_source();
+        assert!(source.starts_with("# Pizza night\ncreate concept Expense\n"));
+        assert!(source.contains("receipt from=Kim date=2023-01-05 {"));
+        assert!(source.contains("Expense 12.50€ for Kim Lou"));
+
+        // the generated source does the same when ran again
+        let rt = eval(&source).unwrap();
+        let dir = rt.get_dir("Lou", "Kim").unwrap();
+        assert_eq!(rt.state().balance(dir).0, Integer::from(300 - 625));
+        assert_eq!(rt.state(), import.runtime().state());
+    }
+
+    #[test]
+    fn splitwise() {
+        // Sam paid 30€ for Kim and Lou,
+        // then Kim 10€ for all three,
+        // then Lou 10€ for Kim (7€) and themselves (3€)
+        let csv = "\
+Date,Description,Category,Cost,Currency,Paid by,Kim,Lou,Sam
+2023-01-05,Groceries,General,30.00,EUR,Sam,-15.00,-15.00,30.00
+2023-01-06,Cinema,General,10.00,EUR,Kim,6.67,-3.33,-3.34
+2023-01-07,Pizza,General,10.00,EUR,Lou,-7.00,7.00,0.00
+";
+        let columns = Columns {
+            date: Some("Date".to_owned()),
+            payer: "Paid by".to_owned(),
+            amount: "Cost".to_owned(),
+            participants: Participants::Columns(["Kim", "Lou", "Sam"].map(str::to_owned).to_vec()),
+            description: Some("Description".to_owned()),
+        };
+        let runtime = eval("create entity { Kim; Lou; Sam }").unwrap();
+        let mut import = Import::new(runtime, columns);
+        import.read(csv.as_bytes()).unwrap();
+
+        let source = import.to_source();
+        assert!(source.starts_with("# Groceries\n"));
+        assert!(source.contains("receipt from=Sam date=2023-01-05 {"));
+        assert!(source.contains("Expense 30€ for Kim Lou"));
+        // Kim owes themselves the 3.33€ left over, just like Lou
+        assert!(source.contains("Expense 6.66€ for Lou Kim"));
+        assert!(source.contains("Expense 3.34€ for Sam"));
+        assert!(source.contains("Expense 7€ for Kim"));
+        assert!(source.contains("Expense 3€ for Lou"));
+
+        let rt = import.runtime();
+        let net = |name| rt.state().net(rt.get_entity(name).unwrap()).0;
+        assert_eq!(net("Kim"), Integer::from(1500 - 667 + 700));
+        assert_eq!(net("Lou"), Integer::from(1500 + 333 - 700));
+        assert_eq!(net("Sam"), Integer::from(-3000 + 334));
+    }
+
+    #[test]
+    fn splitwise_mismatch() {
+        let csv = "\
+Cost,Paid by,Kim,Lou
+30.00,Kim,20.00,-15.00
+30.00,Kim,15.00,15.00
+30.00,Kim,-20.00,-15.00
+";
+        let columns = Columns {
+            date: None,
+            payer: "Paid by".to_owned(),
+            amount: "Cost".to_owned(),
+            participants: Participants::Columns(["Kim", "Lou"].map(str::to_owned).to_vec()),
+            description: None,
+        };
+        let runtime = eval("create entity { Kim; Lou }").unwrap();
+        let mut import = Import::new(runtime, columns);
+        let err = import.read(csv.as_bytes()).unwrap_err();
+
+        let Malformed(rows) = err.downcast().unwrap();
+        assert_eq!(
+            rows.iter().map(|row| row.row).collect::<Vec<_>>(),
+            [2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let csv = "\
+payer,amount,participants
+Kim,1.50,Kim
+Kim,one euro,Kim
+Kim,1,
+Nobody,1,Kim
+";
+        let runtime = eval("create entity Kim").unwrap();
+        let mut import = Import::new(runtime, Columns::default());
+        let err = import.read(csv.as_bytes()).unwrap_err();
+
+        let Malformed(rows) = err.downcast().unwrap();
+        assert_eq!(
+            rows.iter().map(|row| row.row).collect::<Vec<_>>(),
+            [3, 4, 5]
+        );
+        assert!(rows[2].reason.contains("unknown entity `Nobody`"));
+
+        // nothing imported
+        assert_eq!(import.to_source(), "");
+    }
+}
@@ -0,0 +1,353 @@
+//! Export the [`State`] as JSON, for tools that don't speak Rust.
+//!
+//! # Schema
+//!
+//! The top level is an object with:
+//!
+//! - `version`: Version of this schema, currently `1`.
+//!   Bumped whenever anything below changes incompatibly.
+//! - `state`: The actual state, an object with:
+//!     - `entities`: Object from name to [entity](#entity).
+//!     - `concepts`: Object from name to [concept](#concept).
+//!     - `concepts_gtin`: Object from GTIN (as string) to [concept](#concept).
+//!     - `objects`: Object from name to [object](#object).
+//!     - `aliases`: Object with `entities`, `concepts` and `objects`,
+//!       each an object from alias to the actor as above.
+//!     - `balances`: Array of objects with
+//!       `a` and `b` as [entity](#entity) and
+//!       `balance` as [balance](#balance) of how much `a` owes `b`.
+//!       Pairs that never interacted are left out.
+//!     - `ledger`: Array of [transfers](#transfer), in the order they happened.
+//!     - `budgets`: Array of objects with
+//!       `of` as [entity](#entity),
+//!       `category` (string),
+//!       `limit` as [money](#money) and
+//!       `per` as `"day"`, `"week"`, `"month"` or `"year"`.
+//!
+//! Names of actors that have been shadowed don't show up in `entities`,
+//! `concepts` or `objects` anymore,
+//! but they still do where they were referenced by something else.
+//! Renamed actors show up with their new name everywhere.
+//!
+//! ## Basic values
+//!
+//! Amounts can be arbitrarily large,
+//! so they're strings with a decimal number to not lose precision.
+//!
+//! - <a name="money"></a> Money: String, count of cents, e.g. `"150"` for 1.50€.
+//! - <a name="balance"></a> Balance: Like money, but may be negative, e.g. `"-150"`.
+//! - <a name="gtin"></a> GTIN: Number, e.g. `4002846034504`.
+//! - <a name="remainder"></a> Remainder: String, an exact fraction of cents, e.g. `"-1/3"`.
+//! - <a name="date"></a> Date: String formatted as `YYYY-MM-DD`, e.g. `"2024-12-31"`.
+//!
+//! ## Actors
+//!
+//! - <a name="entity"></a> Entity: String, its name.
+//! - <a name="concept"></a> Concept: Object with
+//!   `name` (string),
+//!   `default_price` ([money](#money) or `null`),
+//!   `gtin` ([GTIN](#gtin) or `null`),
+//!   `category` (string or `null`) and
+//!   `tags` (array of strings).
+//! - <a name="object"></a> Object: Object with
+//!   `name` (string or `null`) and
+//!   `parent` ([concept](#concept) or `null`).
+//! - <a name="product"></a> Product: A [concept](#concept) or [object](#object)
+//!   with an additional field `kind` being `"concept"` or `"object"`.
+//!
+//! ## Transfers
+//!
+//! <a name="transfer"></a>
+//! Each has `type`, `who`, `date`, `label`, `memo`, `tags` and `voided`,
+//! `who` being an object with `source` and `target` as [entities](#entity),
+//! `date` being a [date](#date) or `null` if it wasn't specified,
+//! `label` being a string or `null` if it wasn't given with `as`,
+//! `memo` being a string or `null` if it wasn't given,
+//! `tags` being an array of strings given with `tags`
+//! (without those of a delivered concept)
+//! and `voided` being `true` if it was voided later on
+//! (then it doesn't count for `balances` anymore).
+//!
+//! - `type` is `"pay"`: `amount` as [money](#money) paid.
+//! - `type` is `"deliver"`:
+//!   `product` as [product](#product),
+//!   `price` as [money](#money) and
+//!   `split` as object with `source` and `target`,
+//!   the parts of the price paid by either (as strings),
+//!   `rounding` as `"nearest"`, `"payer"`, `"receiver"` or
+//!   an object with `carry` as the [remainder](#remainder) carried into it,
+//!   and `qty`, how many units were delivered (as string, `"1"` if missing).
+//!
+//! # Example
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "state": {
+//!     "entities": { "A": "A", "B": "B" },
+//!     "concepts": {},
+//!     "concepts_gtin": {},
+//!     "objects": {},
+//!     "aliases": { "entities": {}, "concepts": {}, "objects": {} },
+//!     "balances": [{ "a": "A", "b": "B", "balance": "-100" }],
+//!     "ledger": [
+//!       {
+//!         "type": "pay",
+//!         "amount": "100",
+//!         "who": { "source": "A", "target": "B" },
+//!         "date": "2024-12-31",
+//!         "label": "rent",
+//!         "memo": "for December",
+//!         "tags": ["rent"],
+//!         "voided": false
+//!       }
+//!     ],
+//!     "budgets": []
+//!   }
+//! }
+//! ```
+
+use std::io;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+use crate::runtime::{State, model::Remainder};
+
+use super::{Balance, Date, Gtin, Money, Natural};
+
+/// Version of the schema described in the [module docs][self].
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct Export<'a> {
+    version: u32,
+    state: &'a State,
+}
+
+/// Writes `state` as pretty-printed JSON
+/// as described in the [module docs][self].
+pub fn to_writer(state: &State, writer: impl io::Write) -> serde_json::Result<()> {
+    serde_json::to_writer_pretty(
+        writer,
+        &Export {
+            version: VERSION,
+            state,
+        },
+    )
+}
+
+/// [`to_writer`], but into a string.
+pub fn to_string(state: &State) -> String {
+    let mut out = Vec::new();
+    to_writer(state, &mut out).expect("serializing into memory to not fail");
+    String::from_utf8(out).expect("serde_json to emit valid UTF-8")
+}
+
+/// (De)serializes a [`Natural`] as decimal string.
+pub(crate) mod natural {
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    use crate::ext::Natural;
+
+    pub fn serialize<S: Serializer>(n: &Natural, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(n)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Natural, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        src.parse().map_err(D::Error::custom)
+    }
+}
+
+/// (De)serializes the balances as list of `{a, b, balance}` objects,
+/// since JSON only allows strings as object keys.
+pub(crate) mod balances {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+    use crate::{
+        Map,
+        ext::Balance,
+        runtime::model::{Entity, Pair},
+    };
+
+    #[derive(Serialize, Deserialize)]
+    struct Row<E, B> {
+        a: E,
+        b: E,
+        balance: B,
+    }
+
+    pub fn serialize<S: Serializer>(
+        balances: &Map<Pair, Balance>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(balances.iter().map(|(pair, balance)| Row {
+            a: pair.a(),
+            b: pair.b(),
+            balance,
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Map<Pair, Balance>, D::Error> {
+        let rows = Vec::<Row<Entity, Balance>>::deserialize(deserializer)?;
+        rows.into_iter()
+            .map(|Row { a, b, mut balance }| {
+                let pair = Pair::new(a.clone(), b).map_err(D::Error::custom)?;
+                // the balance is from `a` to `b`,
+                // which the pair might have swapped
+                if *pair.a() != a {
+                    balance.flip();
+                }
+                Ok((pair, balance))
+            })
+            .collect()
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        natural::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        natural::deserialize(deserializer).map(Money)
+    }
+}
+
+impl Serialize for Balance {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Balance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        src.parse().map(Balance).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Remainder {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{}/{}", self.numerator(), self.denominator()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Remainder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        let (numerator, denominator) = src
+            .split_once('/')
+            .ok_or_else(|| D::Error::custom(format!("expected a fraction, found `{src}`")))?;
+        let numerator = numerator.parse().map_err(D::Error::custom)?;
+        let denominator: Natural = denominator.parse().map_err(D::Error::custom)?;
+        if denominator == Natural::ZERO {
+            return Err(D::Error::custom("remainder over 0 parts"));
+        }
+        Ok(Remainder::new(numerator, denominator))
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Date {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let src = String::deserialize(deserializer)?;
+        src.parse().map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Gtin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.get())
+    }
+}
+
+impl<'de> Deserialize<'de> for Gtin {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Gtin::new(u64::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::eval;
+
+    #[test]
+    fn schema() {
+        let rt = eval(
+            "
+            create entity { A; B }
+            create concept Mate price=1.50€ gtin=4002846034504 category=drinks tags=bio
+            budget A drinks 10€ per month
+            pay 1€ from=A to=B date=2024-12-31 as=rent memo=\"for \\\"December\\\"\" tags=flat
+            deliver Mate from=A to=B split=1:2
+            void 2
+            ",
+        )
+        .unwrap();
+
+        let exported: Value = serde_json::from_str(&super::to_string(rt.state())).unwrap();
+        let mate = json!({
+            "name": "Mate",
+            "default_price": "150",
+            "gtin": 4002846034504u64,
+            "category": "drinks",
+            "tags": ["bio"],
+        });
+
+        assert_eq!(
+            exported,
+            json!({
+                "version": 1,
+                "state": {
+                    "entities": { "A": "A", "B": "B" },
+                    "concepts": { "Mate": mate },
+                    "concepts_gtin": { "4002846034504": mate },
+                    "objects": {},
+                    "aliases": { "entities": {}, "concepts": {}, "objects": {} },
+                    "balances": [{ "a": "A", "b": "B", "balance": "-100" }],
+                    "ledger": [
+                        {
+                            "type": "pay",
+                            "amount": "100",
+                            "who": { "source": "A", "target": "B" },
+                            "date": "2024-12-31",
+                            "label": "rent",
+                            "memo": "for \"December\"",
+                            "tags": ["flat"],
+                            "voided": false,
+                        },
+                        {
+                            "type": "deliver",
+                            "who": { "source": "A", "target": "B" },
+                            "product": { "kind": "concept", "name": "Mate", "default_price": "150", "gtin": 4002846034504u64, "category": "drinks", "tags": ["bio"] },
+                            "price": "150",
+                            "split": { "source": "1", "target": "2" },
+                            "rounding": "nearest",
+                            "qty": "1",
+                            "date": null,
+                            "label": null,
+                            "memo": null,
+                            "tags": [],
+                            "voided": true,
+                        },
+                    ],
+                    "budgets": [
+                        { "of": "A", "category": "drinks", "limit": "1000", "per": "month" },
+                    ],
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,576 @@
+//! Warn about statements that are valid, but likely not what was meant.
+//!
+//! Unlike running, checking doesn't stop at the first failing statement:
+//! it reports the error, skips the statement and goes on.
+//!
+//! # Lints
+//!
+//! - `shadowed`: An actor is created with a name or GTIN
+//!   that already refers to another one of the same kind.
+//!   The previous one can't be referred to by it anymore.
+//! - `unused`: An actor is created, but never takes part in any transfer.
+//! - `free-delivery`: A delivery costs the target nothing,
+//!   even though that wasn't written down explicitly,
+//!   e.g. because the default price of the concept is 0.
+//!   Giving `price=0€` or a split like `1:0` marks it as a gift.
+//! - `cancelling`: A transfer is immediately followed by one of the same amount
+//!   in the opposite direction, so together they do nothing.
+//! - `duplicate`: A transfer is exactly the same as an earlier one on the same day.
+//!
+//! # Configuration
+//!
+//! Each file can change the level of lints by comment lines like
+//!
+//! ```text
+//! #lint allow shadowed unused
+//! #lint deny duplicate
+//! ```
+//!
+//! Allowed lints aren't reported at all,
+//! denied ones make the check fail just like errors.
+//! All others are warnings.
+
+use std::str::FromStr;
+
+use eyre::{Context, Result, bail, format_err};
+
+use crate::{
+    Map, Runtime,
+    aux::Stack,
+    ext::{Date, Natural, config},
+    runtime::{
+        cmd::{self, Command, Create},
+        model::{Actor, Product, Transfer},
+    },
+    syntax::ast::{self, Script, Span, Stmt, Value},
+};
+
+/// Something likely unintended, see the [module docs][self].
+#[derive(Stack!)]
+pub enum Lint {
+    Shadowed,
+    Unused,
+    FreeDelivery,
+    Cancelling,
+    Duplicate,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Lint::Shadowed,
+        Lint::Unused,
+        Lint::FreeDelivery,
+        Lint::Cancelling,
+        Lint::Duplicate,
+    ];
+
+    /// How it is referred to in `#lint` comments.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::Shadowed => "shadowed",
+            Lint::Unused => "unused",
+            Lint::FreeDelivery => "free-delivery",
+            Lint::Cancelling => "cancelling",
+            Lint::Duplicate => "duplicate",
+        }
+    }
+}
+
+impl FromStr for Lint {
+    type Err = eyre::Report;
+
+    fn from_str(name: &str) -> Result<Self> {
+        Lint::ALL
+            .into_iter()
+            .find(|lint| lint.name() == name)
+            .ok_or_else(|| {
+                let names = Lint::ALL.map(|lint| lint.name()).join(", ");
+                format_err!("unknown lint `{name}`, expected one of {names}")
+            })
+    }
+}
+
+/// How much a [`Lint`] matters.
+#[derive(Stack!, Default)]
+pub enum Level {
+    /// Not reported.
+    Allow,
+    /// Reported, but the check still passes.
+    #[default]
+    Warn,
+    /// Reported and fails the check.
+    Deny,
+}
+
+/// What a `#lint` comment set the level of each lint to.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Levels(Map<Lint, Level>);
+
+impl Levels {
+    /// Collects all `#lint <level> <lint>...` comment lines in `source`,
+    /// later ones overriding earlier ones.
+    pub fn from_source(source: &str) -> Result<Self> {
+        let mut levels = Self::default();
+
+        for (idx, line) in source.lines().enumerate() {
+            let Some(directive) = line.trim().strip_prefix("#lint") else {
+                continue;
+            };
+            let mut words = directive.split_whitespace();
+            let level = match words.next() {
+                Some("allow") => Level::Allow,
+                Some("warn") => Level::Warn,
+                Some("deny") => Level::Deny,
+                _ => bail!(
+                    "in line {}: expected `#lint allow|warn|deny <lint>...`",
+                    idx + 1
+                ),
+            };
+            for lint in words {
+                let lint = lint
+                    .parse()
+                    .wrap_err_with(|| format!("in line {}", idx + 1))?;
+                levels.0.insert(lint, level);
+            }
+        }
+
+        Ok(levels)
+    }
+
+    pub fn get(&self, lint: Lint) -> Level {
+        self.0.get(&lint).copied().unwrap_or_default()
+    }
+}
+
+/// One lint that fired, and where.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    pub lint: Lint,
+    pub level: Level,
+    /// The statement it is about.
+    pub span: Span,
+    pub message: String,
+}
+
+/// Everything a check found.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// Not including allowed lints, in order of the statements.
+    pub warnings: Vec<Warning>,
+    /// Statements that failed and were skipped.
+    pub errors: Vec<(Span, String)>,
+}
+
+impl Report {
+    /// Whether there's an error or a denied lint.
+    pub fn failed(&self) -> bool {
+        !self.errors.is_empty() || self.warnings.iter().any(|w| w.level == Level::Deny)
+    }
+}
+
+/// Checks the configured source and prints what it found to stderr.
+pub fn cli(cfg: config::Check, horizon: Option<Date>) -> Result<()> {
+    let source = cfg.source.get().wrap_err("while loading source")?;
+    let report = check(&source, horizon)?;
+
+    let line = |span: Span| source[..span.start].matches('\n').count() + 1;
+    for warning in &report.warnings {
+        let kind = match warning.level {
+            Level::Deny => "error",
+            _ => "warning",
+        };
+        eprintln!(
+            "{kind}[{}]: in line {}: {}",
+            warning.lint.name(),
+            line(warning.span),
+            warning.message
+        );
+    }
+    for (span, err) in &report.errors {
+        eprintln!("error: in line {}: {err}", line(*span));
+    }
+
+    if report.failed() {
+        bail!("check failed");
+    }
+    Ok(())
+}
+
+/// Runs `source` statement by statement, collecting lints and errors.
+///
+/// # Errors
+///
+/// Only if it can't be parsed or a `#lint` comment is invalid.
+pub fn check(source: &str, horizon: Option<Date>) -> Result<Report> {
+    let levels = Levels::from_source(source)?;
+    let script = Script::parse(source)
+        .into_result()
+        .map_err(|orig| format_err!("while parsing source code: {orig:?}"))?;
+
+    let mut runtime = Runtime::new();
+    runtime.set_horizon(horizon);
+    let mut linter = Linter {
+        runtime,
+        levels,
+        created: Vec::new(),
+        report: Report::default(),
+    };
+    for stmt in script.0 {
+        for line in stmt.node.unblock() {
+            linter.stmt(line, stmt.span);
+        }
+    }
+    linter.unused();
+
+    Ok(linter.report)
+}
+
+struct Linter {
+    runtime: Runtime,
+    levels: Levels,
+    /// Every created actor, with where.
+    created: Vec<(Actor, Span)>,
+    report: Report,
+}
+
+impl Linter {
+    fn warn(&mut self, lint: Lint, span: Span, message: String) {
+        let level = self.levels.get(lint);
+        if level != Level::Allow {
+            self.report.warnings.push(Warning {
+                lint,
+                level,
+                span,
+                message,
+            });
+        }
+    }
+
+    fn stmt(&mut self, stmt: Stmt, span: Span) {
+        // doesn't change anything and would print
+        if stmt.cmd == ast::Command::Whatif {
+            return;
+        }
+        if stmt.cmd == ast::Command::Every {
+            match self.runtime.unroll(stmt) {
+                Ok(script) => {
+                    for stmt in script.0 {
+                        for line in stmt.node.unblock() {
+                            self.stmt(line, stmt.span);
+                        }
+                    }
+                }
+                Err(err) => self.error(span, &err),
+            }
+            return;
+        }
+
+        let explicitly_free = is_explicitly_free(&stmt);
+        let cmd = match self.runtime.repr(stmt) {
+            Ok(cmd) => cmd,
+            Err(err) => return self.error(span, &err),
+        };
+        // valid, but would print
+        if cmd.is_query() {
+            return;
+        }
+        if let Command::Create(create) = &cmd {
+            self.shadowed(create, span);
+        }
+
+        let before = self.runtime.ledger.len();
+        if let Err(err) = self.runtime.fulfil(cmd.clone()) {
+            return self.error(span, &err);
+        }
+        if let Command::Create(create) = cmd {
+            self.created(create, span);
+        }
+
+        for idx in before..self.runtime.ledger.len() {
+            self.transfer(idx, before, explicitly_free, span);
+        }
+    }
+
+    fn error(&mut self, span: Span, err: &dyn std::error::Error) {
+        // the sources are where the details are
+        let mut message = err.to_string();
+        let mut source = err.source();
+        while let Some(err) = source {
+            message = format!("{message}: {err}");
+            source = err.source();
+        }
+        self.report.errors.push((span, message));
+    }
+
+    fn shadowed(&mut self, create: &Create, span: Span) {
+        let rt = &self.runtime;
+        let (kind, name, exists) = match create {
+            Create::Entity(cmd::Entity { name }) => ("entity", name, rt.get_entity(name).is_ok()),
+            Create::Concept(concept) => {
+                if let Some(gtin) = concept.gtin
+                    && rt.get_concept_by_gtin(&gtin).is_ok()
+                {
+                    self.warn(
+                        Lint::Shadowed,
+                        span,
+                        format!("GTIN {gtin} already refers to another concept"),
+                    );
+                }
+                let rt = &self.runtime;
+                (
+                    "concept",
+                    &concept.name,
+                    rt.get_concept(&concept.name).is_ok(),
+                )
+            }
+            Create::Object(object) => ("object", &object.name, rt.get_object(&object.name).is_ok()),
+        };
+
+        if exists {
+            self.warn(
+                Lint::Shadowed,
+                span,
+                format!("{name} already refers to another {kind}, which can't be referred to by it anymore"),
+            );
+        }
+    }
+
+    fn created(&mut self, create: Create, span: Span) {
+        let rt = &self.runtime;
+        let actor = match create {
+            Create::Entity(entity) => rt.get_entity(&entity.name).ok().cloned().map(Actor::Entity),
+            Create::Concept(concept) => rt
+                .get_concept(&concept.name)
+                .ok()
+                .cloned()
+                .map(Actor::Concept),
+            Create::Object(object) => rt.get_object(&object.name).ok().cloned().map(Actor::Object),
+        };
+        let actor = actor.expect("created actor to exist");
+        self.created.push((actor, span));
+    }
+
+    /// Lints the transfer at `idx` in the ledger, which was made by the current statement.
+    /// The ones before `start` were made by earlier ones.
+    fn transfer(&mut self, idx: usize, start: usize, explicitly_free: bool, span: Span) {
+        let ledger = &self.runtime.ledger;
+        let transfer = &ledger[idx];
+        let mut warnings = Vec::new();
+
+        if let Transfer::Deliver(deliver) = transfer
+            && transfer.shifted().0 == Natural::ZERO
+            && !explicitly_free
+        {
+            warnings.push((
+                Lint::FreeDelivery,
+                format!(
+                    "delivering {} costs {} nothing, give `price=0€` if it's a gift",
+                    deliver.product,
+                    deliver.who.target()
+                ),
+            ));
+        }
+
+        if let Some(prev) = idx.checked_sub(1).map(|prev| &ledger[prev])
+            && !prev.is_voided()
+            && prev.shifted() == transfer.shifted()
+            && prev.shifted().0 != Natural::ZERO
+            && prev.who().source() == transfer.who().target()
+            && prev.who().target() == transfer.who().source()
+        {
+            warnings.push((
+                Lint::Cancelling,
+                format!(
+                    "cancels out the transfer right before, {} between {} and {}",
+                    transfer.shifted(),
+                    transfer.who().source(),
+                    transfer.who().target()
+                ),
+            ));
+        }
+
+        let unlabelled = |transfer: &Transfer| {
+            let mut transfer = transfer.clone();
+            match &mut transfer {
+                Transfer::Pay(pay) => pay.label = None,
+                Transfer::Deliver(deliver) => deliver.label = None,
+            }
+            transfer
+        };
+        let date = match transfer {
+            Transfer::Pay(pay) => pay.date,
+            Transfer::Deliver(deliver) => deliver.date,
+        };
+        if let Some(date) = date
+            && let Some(earlier) = ledger[..start]
+                .iter()
+                .position(|earlier| unlabelled(earlier) == unlabelled(transfer))
+        {
+            warnings.push((
+                Lint::Duplicate,
+                format!(
+                    "is the same as transfer {} on {date}, void one of them if it was recorded twice",
+                    earlier + 1
+                ),
+            ));
+        }
+
+        for (lint, message) in warnings {
+            self.warn(lint, span, message);
+        }
+    }
+
+    /// Lints all created actors that never took part in a transfer.
+    fn unused(&mut self) {
+        let ledger = &self.runtime.ledger;
+        let mut unused = Vec::new();
+
+        for (actor, span) in &self.created {
+            let used = ledger.iter().any(|transfer| {
+                let product = match transfer {
+                    Transfer::Deliver(deliver) => Some(&deliver.product),
+                    Transfer::Pay(_) => None,
+                };
+                match actor {
+                    Actor::Entity(entity) => {
+                        [transfer.who().source(), transfer.who().target()].contains(&entity)
+                    }
+                    Actor::Concept(concept) => match product {
+                        Some(Product::Concept(delivered)) => delivered == concept,
+                        Some(Product::Object(delivered)) => delivered.parent() == Some(concept),
+                        None => false,
+                    },
+                    Actor::Object(object) => product == Some(&Product::Object(object.clone())),
+                }
+            });
+            // concepts can also be used just as parents
+            let parent = match actor {
+                Actor::Concept(concept) => self.created.iter().any(
+                    |(other, _)| matches!(other, Actor::Object(o) if o.parent() == Some(concept)),
+                ),
+                _ => false,
+            };
+
+            if !used && !parent {
+                let name = actor.name().unwrap_or_default();
+                unused.push((*span, format!("{} {name} is never used", actor.kind())));
+            }
+        }
+
+        for (span, message) in unused {
+            self.warn(Lint::Unused, span, message);
+        }
+    }
+}
+
+/// Whether the statement states a price of 0 or a split leaving nothing for the target,
+/// in its arguments or any line of its block.
+fn is_explicitly_free(stmt: &Stmt) -> bool {
+    let lines = stmt.block.iter().flat_map(|ast::Block(lines)| lines);
+    std::iter::once(&stmt.args)
+        .chain(lines)
+        .flat_map(|ast::Args(args)| args)
+        .any(|arg| match arg {
+            ast::Arg::Named { value, .. } | ast::Arg::Pos(value) => match value {
+                Value::Money(money) => money.0 == Natural::ZERO,
+                Value::Split(split) => split.to == Natural::ZERO,
+                _ => false,
+            },
+            ast::Arg::Equals => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fired(source: &str) -> Vec<(Lint, Level)> {
+        check(source, None)
+            .unwrap()
+            .warnings
+            .into_iter()
+            .map(|warning| (warning.lint, warning.level))
+            .collect()
+    }
+
+    #[test]
+    fn lints() {
+        let source = "
+            create entity { A; B; C }
+            create concept Mate price=1.50€ gtin=4002846034504
+            create concept Mate price=1.70€
+            create concept Club-Mate price=1.50€ gtin=4002846034504
+            create concept Sticker price=0€
+
+            deliver Mate from=A to=B
+            deliver Sticker from=A to=B
+            deliver Sticker price=0€ from=A to=B
+            pay 3€ from=A to=B date=2024-12-31
+            pay 3€ from=B to=A
+            pay 3€ from=A to=B date=2024-12-31
+        ";
+        assert_eq!(
+            fired(source)
+                .into_iter()
+                .map(|(lint, _)| lint)
+                .collect::<Vec<_>>(),
+            [
+                Lint::Shadowed,
+                Lint::Shadowed,
+                Lint::FreeDelivery,
+                Lint::Cancelling,
+                Lint::Cancelling,
+                Lint::Duplicate,
+                Lint::Unused,
+                Lint::Unused,
+                Lint::Unused,
+            ],
+        );
+
+        let report = check(source, None).unwrap();
+        let unused = report.warnings.iter().filter(|w| w.lint == Lint::Unused);
+        assert_eq!(
+            unused.map(|w| w.message.as_str()).collect::<Vec<_>>(),
+            [
+                "entity C is never used",
+                // the shadowed one
+                "concept Mate is never used",
+                "concept Club-Mate is never used",
+            ],
+        );
+        assert!(!report.failed());
+    }
+
+    #[test]
+    fn configured() {
+        let source = "
+            #lint allow unused
+            #lint deny shadowed
+            create entity { A; B; A }
+            pay 1€ from=A to=B
+        ";
+        assert_eq!(fired(source), [(Lint::Shadowed, Level::Deny)]);
+        assert!(check(source, None).unwrap().failed());
+
+        assert!(check("#lint allow typo\n", None).is_err());
+    }
+
+    #[test]
+    fn continues() {
+        let report = check(
+            "
+            create entity { A; B }
+            pay 1€ from=A to=C
+            pay 1€ from=A to=B
+            every month from=2025-01-01 { pay 1€ from=A to=B }
+            ",
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].1.contains("unknown entity C"));
+        assert!(report.errors[1].1.contains("never ends"));
+        assert!(report.warnings.is_empty());
+        assert!(report.failed());
+    }
+}
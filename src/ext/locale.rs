@@ -0,0 +1,242 @@
+//! How amounts of money are written, e.g. `1.234,56 €` in Germany.
+//!
+//! The [current][Locale::current] locale is used both
+//! when [parsing][crate::syntax::parse::money] source code
+//! and when printing money, for humans or as source code.
+//! It is set per thread, so e.g. tests don't interfere with each other.
+//! Machine-readable exports like JSON or Beancount don't use it.
+//!
+//! Amounts are only ever money together with a currency sign,
+//! so separators never make them ambiguous with ratios like `1:2` or GTINs.
+
+use std::{cell::Cell, fmt, str::FromStr};
+
+use eyre::{Result, bail};
+
+use crate::aux::Stack;
+
+use super::{Money, Natural};
+
+/// Separators and symbol placement for money.
+#[derive(Stack!)]
+pub struct Locale {
+    /// Between euros and cents.
+    pub decimal: char,
+    /// Between each group of 3 digits of the euros, if they're grouped.
+    /// Never the same as [`Locale::decimal`].
+    pub thousands: Option<char>,
+    /// Whether it's `€1.50` instead of `1.50 €` when printed.
+    /// Either is accepted when parsing.
+    pub symbol_first: bool,
+    /// Whether `1.5€` is accepted as 1.50 € when parsing.
+    pub short_fraction: bool,
+}
+
+thread_local! {
+    static CURRENT: Cell<Locale> = const { Cell::new(Locale::PLAIN) };
+}
+
+impl Locale {
+    /// `1234.50 €`, what nyandere always used.
+    pub const PLAIN: Self = Self {
+        decimal: '.',
+        thousands: None,
+        symbol_first: false,
+        short_fraction: false,
+    };
+
+    /// `€1,234.50`
+    pub const EN: Self = Self {
+        decimal: '.',
+        thousands: Some(','),
+        symbol_first: true,
+        short_fraction: false,
+    };
+
+    /// `1.234,50 €`
+    pub const DE: Self = Self {
+        decimal: ',',
+        thousands: Some('.'),
+        symbol_first: false,
+        short_fraction: false,
+    };
+
+    /// The locale used on this thread, [`Locale::PLAIN`] unless [set][Locale::set].
+    pub fn current() -> Self {
+        CURRENT.get()
+    }
+
+    /// Uses this locale on this thread from now on.
+    pub fn set(self) {
+        CURRENT.set(self);
+    }
+
+    /// Reads an amount of euros like `1.234,5` into cents.
+    ///
+    /// `src` consists of digits and separators only,
+    /// the currency sign is already stripped.
+    pub fn parse(&self, src: &str) -> Result<Natural, String> {
+        let numeric = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+        let (whole, fraction) = match src.rsplit_once(self.decimal) {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (src, "00"),
+        };
+        let fraction = match fraction.len() {
+            2 => fraction.to_owned(),
+            1 if self.short_fraction => format!("{fraction}0"),
+            _ => {
+                return Err(format!(
+                    "`{src}` needs exactly 2 digits after the decimal separator `{}`",
+                    self.decimal
+                ));
+            }
+        };
+        if !numeric(&fraction) {
+            return Err(format!("`{src}` isn't an amount of money"));
+        }
+
+        let groups: Vec<_> = match self.thousands {
+            Some(sep) => whole.split(sep).collect(),
+            None => vec![whole],
+        };
+        let grouped = groups.len() > 1;
+        for (idx, group) in groups.iter().enumerate() {
+            let fits =
+                numeric(group) && (!grouped || group.len() == 3 || idx == 0 && group.len() <= 3);
+            if !fits {
+                let thousands = match self.thousands {
+                    Some(sep) => format!(", thousands by `{sep}`"),
+                    None => String::new(),
+                };
+                return Err(format!(
+                    "`{src}` isn't an amount of money with euros and cents separated by `{}`{thousands}",
+                    self.decimal,
+                ));
+            }
+        }
+
+        let whole: Natural = groups.concat().parse().map_err(|err| format!("{err}"))?;
+        let fraction: Natural = fraction.parse().map_err(|err| format!("{err}"))?;
+        Ok(whole * 100u8 + fraction)
+    }
+
+    /// Writes `money` in this locale.
+    /// `spaced` puts a space between amount and symbol if it comes after.
+    pub fn write(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        money: &Money,
+        fraction: Fraction,
+        spaced: bool,
+    ) -> fmt::Result {
+        let whole = (&money.0 / 100u8).to_string();
+        let frac = &money.0 % 100u8;
+
+        let mut amount = String::new();
+        for (idx, digit) in whole.chars().enumerate() {
+            let left = whole.len() - idx;
+            if idx > 0
+                && left.is_multiple_of(3)
+                && let Some(sep) = self.thousands
+            {
+                amount.push(sep);
+            }
+            amount.push(digit);
+        }
+        if fraction == Fraction::Always || frac != Natural::ZERO {
+            amount = format!("{amount}{}{frac:02}", self.decimal);
+        }
+
+        match (self.symbol_first, spaced) {
+            (true, _) => write!(f, "€{amount}"),
+            (false, true) => write!(f, "{amount} €"),
+            (false, false) => write!(f, "{amount}€"),
+        }
+    }
+}
+
+/// Whether [`Locale::write`] writes cents that are 0.
+#[derive(Stack!)]
+pub enum Fraction {
+    Always,
+    /// Like `2€` instead of `2.00€`.
+    IfAny,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::PLAIN
+    }
+}
+
+impl FromStr for Locale {
+    type Err = eyre::Report;
+
+    /// One of the presets `plain`, `en` or `de`.
+    fn from_str(name: &str) -> Result<Self> {
+        Ok(match name {
+            "plain" => Self::PLAIN,
+            "en" => Self::EN,
+            "de" => Self::DE,
+            _ => bail!("unknown locale `{name}`, expected one of `plain`, `en` or `de`"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Shown(Locale, Money, Fraction, bool);
+
+    impl fmt::Display for Shown {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            self.0.write(f, &self.1, self.2, self.3)
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let cents = |locale: Locale, src| locale.parse(src).map(|n| n.to_string());
+
+        assert_eq!(cents(Locale::PLAIN, "1.50"), Ok("150".into()));
+        assert_eq!(cents(Locale::DE, "1.234,56"), Ok("123456".into()));
+        assert_eq!(cents(Locale::DE, "1234,56"), Ok("123456".into()));
+        assert_eq!(cents(Locale::EN, "12,345,678.90"), Ok("1234567890".into()));
+        assert_eq!(cents(Locale::EN, "1,234"), Ok("123400".into()));
+
+        // misread separators fail instead of being off by a lot
+        for (locale, src) in [
+            (Locale::DE, "1.50"),
+            (Locale::EN, "1,50"),
+            (Locale::PLAIN, "1,50"),
+            (Locale::DE, "1,5"),
+            (Locale::DE, "12.34.567"),
+            (Locale::EN, "1,2345.00"),
+        ] {
+            assert!(locale.parse(src).is_err(), "{src}");
+        }
+
+        let short = Locale {
+            short_fraction: true,
+            ..Locale::DE
+        };
+        assert_eq!(cents(short, "1,5"), Ok("150".into()));
+    }
+
+    #[test]
+    fn write() {
+        let money = Money(123456u32.into());
+        let shown =
+            |locale, fraction, spaced| Shown(locale, money.clone(), fraction, spaced).to_string();
+
+        assert_eq!(shown(Locale::PLAIN, Fraction::Always, true), "1234.56 €");
+        assert_eq!(shown(Locale::DE, Fraction::Always, true), "1.234,56 €");
+        assert_eq!(shown(Locale::EN, Fraction::Always, true), "€1,234.56");
+        assert_eq!(
+            Shown(Locale::DE, Money(100_000u32.into()), Fraction::IfAny, false).to_string(),
+            "1.000€"
+        );
+    }
+}
@@ -1,9 +1,22 @@
 //! Interact and construct the outside world.
 
+pub mod ask;
+pub mod beancount;
 pub mod config;
+pub mod graph;
+pub mod import;
+pub mod json;
+pub mod lint;
+pub mod locale;
+pub mod scan;
+pub mod snapshot;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod table;
 pub mod ui;
 
 use std::{
+    fmt,
     num::ParseIntError,
     ops::{Add, AddAssign, RangeInclusive, Sub, SubAssign},
     str::FromStr,
@@ -125,6 +138,29 @@ pub struct Debit {
     pub amount: Money,
 }
 
+/// Calendar day something happened on, written as `YYYY-MM-DD`.
+#[derive(Stack!)]
+pub struct Date(pub time::Date);
+
+impl Date {
+    const FORMAT: &[time::format_description::BorrowedFormatItem<'static>] =
+        time::macros::format_description!("[year]-[month]-[day]");
+}
+
+impl FromStr for Date {
+    type Err = time::error::Parse;
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        time::Date::parse(source, Self::FORMAT).map(Self)
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let formatted = self.0.format(Self::FORMAT).map_err(|_| fmt::Error)?;
+        write!(f, "{formatted}")
+    }
+}
+
 /// Global trade item number. The number behind the barcode you find in stores.
 ///
 /// Internationally standardized.
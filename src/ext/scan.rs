@@ -0,0 +1,342 @@
+//! Turn barcode scans into deliveries.
+//!
+//! Cheap USB barcode scanners act like a keyboard:
+//! they type the digits of the scanned GTIN, followed by Enter.
+//! So all this needs to do is to read lines.
+//!
+//! Between scans, one can also type:
+//!
+//! - `qty <n>` to deliver the next scanned product `n` times
+//! - `undo` to take back the last scan
+//! - `done` to end the session (same as end of input)
+//!
+//! If a GTIN isn't known yet,
+//! the session asks for a name and price
+//! and creates a concept for it.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, BufRead, Write},
+};
+
+use chumsky::prelude::*;
+use eyre::{Context, Result, bail, format_err};
+
+use crate::{
+    Runtime, eval,
+    runtime::cmd::Name,
+    syntax::{
+        ast::{Arg, Command, Script, Stmt, Value},
+        parse::{self, lex},
+        print::SourceMoney,
+    },
+};
+
+use super::{Gtin, Money, config};
+
+/// Runs a scanning session on stdin as configured on the command line.
+pub fn cli(cfg: config::Scan) -> Result<()> {
+    let runtime = match &cfg.ledger {
+        Some(path) => {
+            let source = fs::read_to_string(path)
+                .wrap_err_with(|| format!("tried to read `{}`", path.display()))?;
+            eval(source)?
+        }
+        None => Runtime::new(),
+    };
+
+    let mut session = Session::new(runtime, cfg.from, cfg.to)?;
+    session.run(io::stdin().lock(), io::stderr())?;
+    let out = session.to_source();
+    crate::warn_overruns(&mut session.finish()?);
+
+    match &cfg.ledger {
+        // nothing scanned, so leave the ledger as-is
+        Some(_) if out.is_empty() => {}
+        Some(path) => {
+            let mut file = OpenOptions::new()
+                .append(true)
+                .open(path)
+                .wrap_err_with(|| format!("tried to open `{}` for appending", path.display()))?;
+            write!(file, "\n{out}")?;
+        }
+        None => print!("{out}"),
+    }
+
+    Ok(())
+}
+
+/// Scans delivered from one entity to another.
+///
+/// Each scan is kept in its own open transaction until the session is
+/// [finished][Session::finish], so it can be undone.
+#[derive(Debug)]
+pub struct Session {
+    /// What the session started with, all scans applied.
+    runtime: Runtime,
+    from: Name,
+    to: Name,
+    /// Statements each scan produced, in order.
+    scans: Vec<Vec<String>>,
+    /// How often the next scan will be delivered.
+    qty: usize,
+}
+
+impl Session {
+    /// Prepares scanning deliveries from `from` to `to`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if one of the entities doesn't exist
+    /// or they're the same.
+    pub fn new(runtime: Runtime, from: Name, to: Name) -> Result<Self> {
+        runtime.get_dir(&from, &to)?;
+
+        Ok(Self {
+            runtime,
+            from,
+            to,
+            scans: Vec::new(),
+            qty: 1,
+        })
+    }
+
+    /// The state with all scans so far applied.
+    pub fn runtime(&self) -> &Runtime {
+        &self.runtime
+    }
+
+    /// Reads scans and commands from `input` until it ends or `done` is typed,
+    /// reporting progress and asking for missing info on `prompt`.
+    ///
+    /// Invalid input is reported and then ignored,
+    /// only I/O errors cause this to return early.
+    pub fn run(&mut self, input: impl BufRead, mut prompt: impl Write) -> Result<()> {
+        let mut lines = input.lines();
+
+        while let Some(line) = lines.next() {
+            let line = line?;
+            let line = line.trim();
+
+            let outcome = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [] => continue,
+                ["done"] => break,
+                ["undo"] => self.undo(),
+                ["qty", n] => self.set_qty(n),
+                [gtin] if gtin.bytes().all(|b| b.is_ascii_digit()) => {
+                    self.scan(gtin, &mut lines, &mut prompt)
+                }
+                _ => Err(format_err!(
+                    "expected a GTIN, `qty <n>`, `undo` or `done`, found `{line}`"
+                )),
+            };
+
+            match outcome {
+                Ok(msg) => writeln!(prompt, "{msg}")?,
+                Err(err) => writeln!(prompt, "error: {err:#}")?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ends the session, keeping all scans not undone.
+    pub fn finish(mut self) -> Result<Runtime> {
+        for _ in &self.scans {
+            self.runtime.commit()?;
+        }
+        Ok(self.runtime)
+    }
+
+    /// All statements the scans made, as source code.
+    pub fn to_source(&self) -> String {
+        self.scans
+            .iter()
+            .flatten()
+            .map(|s| format!("{s}\n"))
+            .collect()
+    }
+
+    fn scan(
+        &mut self,
+        gtin: &str,
+        lines: &mut impl Iterator<Item = io::Result<String>>,
+        prompt: &mut impl Write,
+    ) -> Result<String> {
+        let gtin: Gtin = gtin.parse()?;
+        let mut stmts = Vec::new();
+
+        let (name, price) = match self.runtime.get_concept_by_gtin(&gtin) {
+            Ok(concept) => (concept.name().to_owned(), concept.default_price().cloned()),
+            Err(_) => {
+                writeln!(prompt, "unknown {gtin}")?;
+                let name = ask(lines, prompt, "name", |src| {
+                    if src.is_empty() {
+                        bail!("the name can't be empty");
+                    }
+                    Ok(src.to_owned())
+                })?;
+                let price = ask(lines, prompt, &price_prompt(), parse_money)?;
+
+                stmts.push(create_concept(&name, &price, gtin));
+                (name, Some(price))
+            }
+        };
+
+        // no default price means it has to be specified on every delivery
+        let price = match price {
+            Some(_) => None,
+            None => Some(ask(lines, prompt, &price_prompt(), parse_money)?),
+        };
+
+        let deliver = deliver(gtin, price.as_ref(), &self.from, &self.to);
+        stmts.extend((0..self.qty).map(|_| deliver.clone()));
+
+        self.apply(&stmts)?;
+        self.scans.push(stmts);
+
+        let qty = std::mem::replace(&mut self.qty, 1);
+        Ok(format!("scanned {name} ×{qty}"))
+    }
+
+    fn undo(&mut self) -> Result<String> {
+        let Some(undone) = self.scans.pop() else {
+            bail!("nothing to undo");
+        };
+
+        self.runtime.abort()?;
+
+        Ok(format!("undid {}", undone.join("; ")))
+    }
+
+    fn set_qty(&mut self, n: &str) -> Result<String> {
+        let qty =
+            n.parse().ok().filter(|qty| *qty > 0).ok_or_else(|| {
+                format_err!("quantity needs to be a positive integer, found `{n}`")
+            })?;
+        self.qty = qty;
+        Ok(format!("next scan is delivered {qty} times"))
+    }
+
+    /// Runs the statements on the runtime in a new transaction,
+    /// only keeping them if all succeed.
+    fn apply(&mut self, stmts: &[String]) -> Result<()> {
+        self.runtime.begin()?;
+
+        let outcome = stmts.iter().try_for_each(|src| {
+            let script = Script::parse(src)
+                .into_result()
+                .map_err(|orig| format_err!("generated invalid source code `{src}`: {orig:?}"))?;
+            self.runtime.run(script)?;
+            Ok(())
+        });
+        if outcome.is_err() {
+            self.runtime.abort()?;
+        }
+        outcome
+    }
+}
+
+/// Asks on `prompt` until the answer in `lines` is accepted by `parse`.
+fn ask<T>(
+    lines: &mut impl Iterator<Item = io::Result<String>>,
+    prompt: &mut impl Write,
+    what: &str,
+    parse: impl Fn(&str) -> Result<T>,
+) -> Result<T> {
+    loop {
+        write!(prompt, "{what}? ")?;
+        prompt.flush()?;
+
+        let Some(line) = lines.next() else {
+            bail!("input ended while waiting for {what}");
+        };
+
+        match parse(line?.trim()) {
+            Ok(answer) => return Ok(answer),
+            Err(err) => writeln!(prompt, "invalid {what}: {err:#}")?,
+        }
+    }
+}
+
+/// Asks for a price with an example in the current locale.
+fn price_prompt() -> String {
+    format!("price (e.g. {})", SourceMoney(&Money(150u8.into())))
+}
+
+fn parse_money(src: &str) -> Result<Money> {
+    let money = parse::money()
+        .then_ignore(end())
+        .parse(lex(src))
+        .into_result()
+        .map_err(|orig| format_err!("{orig:?}"))?;
+    Ok(money)
+}
+
+fn create_concept(name: &str, price: &Money, gtin: Gtin) -> String {
+    let args = vec![
+        Arg::Pos(Value::name("concept")),
+        Arg::Pos(Value::name(name)),
+        Arg::named("price", Value::Money(price.clone())),
+        Arg::named("gtin", Value::Gtin(gtin)),
+    ];
+    Stmt::new(Command::Create, args).to_string()
+}
+
+fn deliver(gtin: Gtin, price: Option<&Money>, from: &str, to: &str) -> String {
+    let mut args = vec![Arg::Pos(Value::Gtin(gtin))];
+    if let Some(price) = price {
+        args.push(Arg::named("price", Value::Money(price.clone())));
+    }
+    args.push(Arg::named("from", Value::name(from)));
+    args.push(Arg::named("to", Value::name(to)));
+    Stmt::new(Command::Deliver, args).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval, ext::Integer};
+
+    use super::*;
+
+    #[test]
+    fn session() {
+        let rt = eval(
+            "
+            create entity { Store; A }
+            create concept Mate price=1.50€ gtin=4002846034504
+            ",
+        )
+        .unwrap();
+
+        let mut session = Session::new(rt, "Store".into(), "A".into()).unwrap();
+        let input = "
+            4002846034504
+            qty 3
+            12345678
+            Chips
+            2€
+            4002846034504
+            undo
+            not-a-gtin
+            12345678
+        ";
+        session.run(input.as_bytes(), io::sink()).unwrap();
+
+        assert_eq!(
+            session.to_source(),
+            concat!(
+                "deliver 4002846034504 from=Store to=A\n",
+                "create concept Chips price=2€ gtin=12345678\n",
+                "deliver 12345678 from=Store to=A\n",
+                "deliver 12345678 from=Store to=A\n",
+                "deliver 12345678 from=Store to=A\n",
+                "deliver 12345678 from=Store to=A\n",
+            ),
+        );
+
+        let rt = session.runtime();
+        let dir = rt.get_dir("A", "Store").unwrap();
+        assert_eq!(rt.state().balance(dir).0, -Integer::from(150 + 4 * 200));
+    }
+}
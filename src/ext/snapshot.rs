@@ -0,0 +1,293 @@
+//! Save the state after a run and pick up from there next time.
+//!
+//! Ledgers only ever grow at the end,
+//! so there's no point in running years of statements again and again.
+//! A [`Snapshot`] remembers how much of which source it covered,
+//! and [`Snapshot::resume`] only runs what has been appended since.
+//!
+//! To make sure old parts don't drift silently,
+//! the snapshot also stores a hash of the covered part.
+//! If that changed, resuming fails with [`Stale`]
+//! and the whole source needs to be run again.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{Runtime, eval_from, runtime::State};
+
+/// Version of the snapshot format.
+/// Snapshots of other versions can't be resumed.
+pub const VERSION: u32 = 1;
+
+/// Runs `source` using the snapshot at `path`,
+/// resuming from it if it exists,
+/// and saves the new snapshot there afterwards.
+///
+/// `file` is where `source` was read from, if anywhere.
+pub fn cli(source: &str, file: Option<&Path>, path: &Path) -> Result<Runtime> {
+    let runtime = if path.exists() {
+        Snapshot::load(path)?
+            .resume(source, file)
+            .wrap_err_with(|| format!("while resuming from `{}`", path.display()))?
+    } else {
+        eval_from(Runtime::new(), source, 0)?
+    };
+
+    Snapshot::new(&runtime, source, file).save(path)?;
+    Ok(runtime)
+}
+
+/// The state after running some source,
+/// with enough info to continue from there.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Snapshot {
+    version: u32,
+    covered: Covered,
+    state: State,
+}
+
+/// Which part of what source a [`Snapshot`] already ran.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Covered {
+    /// Where the source was read from, if it was a file.
+    pub file: Option<PathBuf>,
+    /// Count of bytes at the start of the source.
+    pub len: usize,
+    /// SHA-256 of these bytes, in hex.
+    pub sha256: String,
+}
+
+/// Why a snapshot can't be resumed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Stale {
+    #[error("snapshot has version {found}, but only version {VERSION} is supported")]
+    Version { found: u64 },
+    #[error("snapshot was taken of {snapshot}, not of {given}")]
+    OtherFile { snapshot: String, given: String },
+    #[error(
+        "source is only {actual} bytes long, \
+        but the snapshot covers {covered} bytes already. \
+        was something removed?"
+    )]
+    Truncated { covered: usize, actual: usize },
+    #[error("the first {covered} bytes of the source changed since the snapshot was taken")]
+    Changed { covered: usize },
+    #[error(
+        "appended source continues the last statement the snapshot covers, \
+        start with a newline or `;`"
+    )]
+    Continued,
+}
+
+impl Snapshot {
+    /// Captures the state of `runtime`
+    /// after having run all of `source`.
+    pub fn new(runtime: &Runtime, source: &str, file: Option<&Path>) -> Self {
+        Self {
+            version: VERSION,
+            covered: Covered::new(source, file),
+            state: runtime.state().clone(),
+        }
+    }
+
+    /// What part of which source this snapshot ran.
+    pub fn covered(&self) -> &Covered {
+        &self.covered
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    /// Reads a previously [saved][Snapshot::save] snapshot.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read,
+    /// has another [`VERSION`] or isn't a snapshot at all.
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = fs::read_to_string(path)
+            .wrap_err_with(|| format!("tried to read snapshot `{}`", path.display()))?;
+        Self::from_json(&raw).wrap_err_with(|| format!("invalid snapshot `{}`", path.display()))
+    }
+
+    /// Writes this snapshot to `path`, overwriting what was there.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_json())
+            .wrap_err_with(|| format!("tried to write snapshot `{}`", path.display()))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("serializing into memory to not fail")
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self> {
+        // check the version first,
+        // other versions might not even deserialize
+        let value: serde_json::Value = serde_json::from_str(raw)?;
+        let found = value["version"].as_u64().unwrap_or_default();
+        if found != u64::from(VERSION) {
+            return Err(Stale::Version { found }.into());
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Checks that `source` starts with what this snapshot covered
+    /// and runs only the rest.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Stale`] if the covered part changed
+    /// or the rest fails to parse or run.
+    pub fn resume(self, source: &str, file: Option<&Path>) -> Result<Runtime> {
+        let start = self.covered.check(source, file)?;
+        eval_from(Runtime::from(self.state), source, start)
+    }
+}
+
+impl Covered {
+    pub fn new(source: &str, file: Option<&Path>) -> Self {
+        Self {
+            file: file.map(Path::to_path_buf),
+            len: source.len(),
+            sha256: sha256(source.as_bytes()),
+        }
+    }
+
+    /// Returns where the not yet covered part of `source` starts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `source` doesn't start with the covered part.
+    pub fn check(&self, source: &str, file: Option<&Path>) -> Result<usize, Stale> {
+        if self.file.as_deref() != file {
+            let show = |file: Option<&Path>| match file {
+                Some(file) => format!("`{}`", file.display()),
+                None => "inline code".to_owned(),
+            };
+            return Err(Stale::OtherFile {
+                snapshot: show(self.file.as_deref()),
+                given: show(file),
+            });
+        }
+
+        let Some(prefix) = source.as_bytes().get(..self.len) else {
+            return Err(Stale::Truncated {
+                covered: self.len,
+                actual: source.len(),
+            });
+        };
+        if sha256(prefix) != self.sha256 {
+            return Err(Stale::Changed { covered: self.len });
+        }
+
+        // `pay 1€ from=A to=B` + ` split=1:2` would silently change the last statement
+        let delimits = |b: Option<&u8>| matches!(b, None | Some(b'\n' | b';'));
+        let rest = &source.as_bytes()[self.len..];
+        if !delimits(prefix.last()) && !delimits(rest.first()) {
+            return Err(Stale::Continued);
+        }
+
+        Ok(self.len)
+    }
+}
+
+fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval, ext::Integer};
+
+    use super::*;
+
+    const LEDGER: &str = "
+        create entity { A; B }
+        pay 1€ from=A to=B
+    ";
+
+    fn roundtrip(source: &str) -> Snapshot {
+        let snapshot = Snapshot::new(&eval(source).unwrap(), source, None);
+        Snapshot::from_json(&snapshot.to_json()).unwrap()
+    }
+
+    #[test]
+    fn resume() {
+        let snapshot = roundtrip(LEDGER);
+
+        // would fail if the creations ran again
+        let source = format!("{LEDGER}\npay 2€ from=B to=A\n");
+        let rt = snapshot.resume(&source, None).unwrap();
+
+        let dir = rt.get_dir("A", "B").unwrap();
+        assert_eq!(rt.state().balance(dir).0, Integer::from(-100));
+        assert_eq!(rt.state().ledger.len(), 2);
+    }
+
+    #[test]
+    fn stale() {
+        let snapshot = roundtrip(LEDGER);
+        let check = |source: &str, file: Option<&Path>| snapshot.covered().check(source, file);
+
+        assert_eq!(check(LEDGER, None), Ok(LEDGER.len()));
+        assert!(matches!(
+            check(LEDGER, Some(Path::new("ledger.nyan"))),
+            Err(Stale::OtherFile { .. }),
+        ));
+        assert!(matches!(
+            check(&LEDGER[1..], None),
+            Err(Stale::Truncated { .. }),
+        ));
+        assert_eq!(
+            check(&LEDGER.replace("1€", "2€"), None),
+            Err(Stale::Changed {
+                covered: LEDGER.len()
+            }),
+        );
+
+        let source = "create entity A";
+        let snapshot = roundtrip(source);
+        assert_eq!(
+            snapshot.covered().check("create entity AB", None),
+            Err(Stale::Continued),
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        let json = roundtrip(
+            "
+            create entity { A; B }
+            create concept Mate price=1€
+            deliver Mate from=A to=B
+        ",
+        )
+        .to_json();
+
+        let zero = json.replace(
+            r#""split":{"source":"0","target":"1"}"#,
+            r#""split":{"source":"0","target":"0"}"#,
+        );
+        let same = json.replace(
+            r#""who":{"source":"A","target":"B"}"#,
+            r#""who":{"source":"A","target":"A"}"#,
+        );
+        assert_ne!(zero, json);
+        assert_ne!(same, json);
+
+        assert!(Snapshot::from_json(&zero).is_err());
+        assert!(Snapshot::from_json(&same).is_err());
+    }
+}
@@ -0,0 +1,752 @@
+//! Mirror the runtime state into an SQLite database.
+//!
+//! Only available with the `sqlite` feature.
+//! Other tools can then query it read-only using [`open_read_only`]
+//! or the `sqlite3` CLI directly.
+//!
+//! # Schema
+//!
+//! Amounts of money are integers counting cents.
+//!
+//! - `entity(name)`
+//! - `concept(id, name, default_price, gtin, category)`:
+//!   Every created concept, including shadowed ones.
+//!   The one with the highest `id` for a name is the current one.
+//!   `category` is what was given with `category`, if anything.
+//! - `object(id, name, parent)`:
+//!   Every created object, `parent` referring to `concept(id)`.
+//! - `transfer(id, kind, source, target, amount, concept, object, split_source, split_target, date, qty, label, voided, memo, rounding)`:
+//!   Every transfer in the order they happened.
+//!   - `kind` is `'pay'` or `'deliver'`.
+//!   - `amount` is the amount paid or the price of the delivered product.
+//!   - For deliveries, exactly one of `concept` and `object` is set,
+//!     referring to the delivered product.
+//!     `split_source` and `split_target` are the parts of the price paid by either.
+//!   - `date` is formatted as `YYYY-MM-DD`, if specified.
+//!   - `qty` is how many units were delivered, `NULL` for payments.
+//!   - `label` is what was given with `as`, if anything.
+//!   - `voided` is `1` if the transfer was voided later on, otherwise `0`.
+//!     Voided transfers don't count for `balance` anymore.
+//!   - `memo` is what was given with `memo`, if anything.
+//!   - `rounding` is `'largest-remainder'`, `'payer'`, `'receiver'` or `'carry'`
+//!     for deliveries, `NULL` for payments.
+//! - `balance(a, b, balance)`: How much `a` owes `b`, with `a < b`.
+//! - `alias(id, name, entity, concept, object)`:
+//!   Every alias given with `alias`,
+//!   exactly one of `entity`, `concept` and `object` referring to the actor.
+//! - `tag(tag, concept, transfer)`:
+//!   Every tag given with `tags` or `tag`,
+//!   exactly one of `concept` and `transfer` referring to what has it.
+//!   Deliveries also have the tags of their concept,
+//!   which aren't repeated here.
+//! - `budget(entity, category, amount, per)`:
+//!   The current budget for each entity and category,
+//!   `per` being `'day'`, `'week'`, `'month'` or `'year'`.
+//!
+//! Renamed actors are updated in place,
+//! including all rows referring to them by name.
+//!
+//! `PRAGMA user_version` is the count of applied [migrations](MIGRATIONS).
+
+use std::path::Path;
+
+use rusqlite::{Connection, OpenFlags, params, types::Value};
+
+use crate::{
+    ext::{Balance, Money},
+    runtime::{
+        cmd::{Budget, NameRef},
+        error,
+        model::{Actor, Concept, Entity, Object, Pair, Product, Transfer},
+        storage::Storage,
+    },
+};
+
+/// Schema changes, in order.
+/// Each is applied once on [`Sqlite::open`],
+/// so only ever append here.
+pub const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE entity (
+        name TEXT PRIMARY KEY NOT NULL
+    );
+    CREATE TABLE concept (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        default_price INTEGER,
+        gtin INTEGER
+    );
+    CREATE TABLE object (
+        id INTEGER PRIMARY KEY,
+        name TEXT,
+        parent INTEGER REFERENCES concept(id)
+    );
+    CREATE TABLE transfer (
+        id INTEGER PRIMARY KEY,
+        kind TEXT NOT NULL CHECK (kind IN ('pay', 'deliver')),
+        source TEXT NOT NULL REFERENCES entity(name),
+        target TEXT NOT NULL REFERENCES entity(name),
+        amount INTEGER NOT NULL,
+        concept INTEGER REFERENCES concept(id),
+        object INTEGER REFERENCES object(id),
+        split_source INTEGER,
+        split_target INTEGER
+    );
+    CREATE TABLE balance (
+        a TEXT NOT NULL REFERENCES entity(name),
+        b TEXT NOT NULL REFERENCES entity(name),
+        balance INTEGER NOT NULL,
+        PRIMARY KEY (a, b)
+    );
+",
+    "
+    ALTER TABLE transfer ADD COLUMN date TEXT;
+",
+    "
+    ALTER TABLE transfer ADD COLUMN qty INTEGER;
+",
+    "
+    ALTER TABLE transfer ADD COLUMN label TEXT;
+    ALTER TABLE transfer ADD COLUMN voided INTEGER NOT NULL DEFAULT 0;
+",
+    "
+    ALTER TABLE transfer ADD COLUMN memo TEXT;
+",
+    "
+    CREATE TABLE alias (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        entity TEXT REFERENCES entity(name),
+        concept INTEGER REFERENCES concept(id),
+        object INTEGER REFERENCES object(id)
+    );
+",
+    "
+    ALTER TABLE transfer ADD COLUMN rounding TEXT;
+",
+    "
+    ALTER TABLE concept ADD COLUMN category TEXT;
+    CREATE TABLE budget (
+        entity TEXT NOT NULL REFERENCES entity(name),
+        category TEXT NOT NULL,
+        amount INTEGER NOT NULL,
+        per TEXT NOT NULL,
+        PRIMARY KEY (entity, category)
+    );
+",
+    "
+    CREATE TABLE tag (
+        tag TEXT NOT NULL,
+        concept INTEGER REFERENCES concept(id),
+        transfer INTEGER REFERENCES transfer(id)
+    );
+",
+];
+
+/// [`Storage`] writing into an SQLite database.
+#[derive(Debug)]
+pub struct Sqlite {
+    conn: Connection,
+    /// How many savepoints are open.
+    depth: usize,
+    /// How many savepoints were open when the tables were cleared,
+    /// [`None`] if they weren't yet.
+    cleared: Option<usize>,
+}
+
+/// Opens the database at `path` for querying without being able to modify it.
+pub fn open_read_only(path: impl AsRef<Path>) -> rusqlite::Result<Connection> {
+    Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+}
+
+impl Sqlite {
+    /// Opens or creates the database at `path`
+    /// and migrates it to the latest schema.
+    ///
+    /// Since the runtime always starts from scratch,
+    /// it is cleared on the first write or transaction.
+    /// So if that transaction is aborted, e.g. because the script failed,
+    /// the previous contents are kept.
+    pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Like [`Sqlite::open`], but only in memory.
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(mut conn: Connection) -> rusqlite::Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn,
+            depth: 0,
+            cleared: None,
+        })
+    }
+
+    /// Clears all tables if that didn't happen yet.
+    fn clear(&mut self) -> Result<(), error::Storage> {
+        if self.cleared.is_some() {
+            return Ok(());
+        }
+
+        self.conn
+            .execute_batch(
+                "
+                DELETE FROM tag;
+                DELETE FROM budget;
+                DELETE FROM alias;
+                DELETE FROM balance;
+                DELETE FROM transfer;
+                DELETE FROM object;
+                DELETE FROM concept;
+                DELETE FROM entity;
+                ",
+            )
+            .map_err(wrap)?;
+        self.cleared = Some(self.depth);
+        Ok(())
+    }
+
+    /// The underlying connection, e.g. for querying.
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Looks up the `id` of the last row in `concept` matching `concept`.
+    fn concept_id(&self, concept: &Concept) -> rusqlite::Result<i64> {
+        self.conn.query_row(
+            "
+            SELECT id FROM concept
+            WHERE name = ?1 AND default_price IS ?2 AND gtin IS ?3
+            ORDER BY id DESC LIMIT 1
+            ",
+            params![
+                concept.name(),
+                concept.default_price().map(cents).transpose()?,
+                concept.gtin().map(|gtin| gtin.get()),
+            ],
+            |row| row.get(0),
+        )
+    }
+
+    /// Looks up the `id` of the last row in `object` matching `object`.
+    fn object_id(&self, object: &Object) -> rusqlite::Result<i64> {
+        let parent = object
+            .parent()
+            .map(|parent| self.concept_id(parent))
+            .transpose()?;
+        self.conn.query_row(
+            "
+            SELECT id FROM object
+            WHERE name IS ?1 AND parent IS ?2
+            ORDER BY id DESC LIMIT 1
+            ",
+            params![object.name(), parent],
+            |row| row.get(0),
+        )
+    }
+
+    /// The column in `alias` referring to `actor` and the value to refer to it by.
+    fn actor_ref(&self, actor: &Actor) -> rusqlite::Result<(&'static str, Value)> {
+        Ok(match actor {
+            Actor::Entity(entity) => ("entity", Value::Text(entity.name().to_owned())),
+            Actor::Concept(concept) => ("concept", Value::Integer(self.concept_id(concept)?)),
+            Actor::Object(object) => ("object", Value::Integer(self.object_id(object)?)),
+        })
+    }
+
+    fn insert_transfer(&self, transfer: &Transfer) -> rusqlite::Result<()> {
+        let (kind, amount, date) = match transfer {
+            Transfer::Pay(pay) => ("pay", &pay.amount, pay.date),
+            Transfer::Deliver(deliver) => ("deliver", &deliver.price, deliver.date),
+        };
+        let who = transfer.who();
+        let (mut concept, mut object, mut split, mut qty, mut rounding) =
+            (None, None, None, None, None);
+
+        if let Transfer::Deliver(deliver) = transfer {
+            match &deliver.product {
+                Product::Concept(c) => concept = Some(self.concept_id(c)?),
+                Product::Object(o) => object = Some(self.object_id(o)?),
+            }
+            split = Some((int(deliver.split.source())?, int(deliver.split.target())?));
+            qty = Some(int(&deliver.qty)?);
+            rounding = Some(deliver.rounding.name());
+        }
+
+        self.conn.execute(
+            "
+            INSERT INTO transfer
+            (kind, source, target, amount, concept, object, split_source, split_target, date, qty,
+             label, voided, memo, rounding)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+            ",
+            params![
+                kind,
+                who.source().name(),
+                who.target().name(),
+                cents(amount)?,
+                concept,
+                object,
+                split.map(|(source, _)| source),
+                split.map(|(_, target)| target),
+                date.map(|date| date.to_string()),
+                qty,
+                transfer.label(),
+                transfer.is_voided(),
+                transfer.memo(),
+                rounding,
+            ],
+        )?;
+
+        let own = match transfer {
+            Transfer::Pay(pay) => &pay.tags,
+            Transfer::Deliver(deliver) => &deliver.tags,
+        };
+        let id = self.conn.last_insert_rowid();
+        for tag in own {
+            self.conn.execute(
+                "INSERT INTO tag (tag, transfer) VALUES (?1, ?2)",
+                params![tag, id],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn insert_concept_tag(&self, concept: &Concept, tag: NameRef) -> rusqlite::Result<()> {
+        self.conn.execute(
+            "INSERT INTO tag (tag, concept) VALUES (?1, ?2)",
+            params![tag, self.concept_id(concept)?],
+        )?;
+        Ok(())
+    }
+}
+
+/// Applies all [`MIGRATIONS`] not applied yet.
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    let applied: usize = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    for migration in MIGRATIONS.iter().skip(applied) {
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+
+    tx.commit()
+}
+
+fn cents(money: &Money) -> rusqlite::Result<i64> {
+    int(&money.0)
+}
+
+fn int<T>(n: &T) -> rusqlite::Result<i64>
+where
+    for<'a> i64: TryFrom<&'a T>,
+{
+    i64::try_from(n).map_err(|_| {
+        rusqlite::Error::ToSqlConversionFailure("number too large for SQLite integer".into())
+    })
+}
+
+fn wrap(err: rusqlite::Error) -> error::Storage {
+    error::Storage(err.to_string())
+}
+
+impl Storage for Sqlite {
+    fn entity(&mut self, entity: &Entity) -> Result<(), error::Storage> {
+        self.clear()?;
+        self.conn
+            .execute(
+                "INSERT OR IGNORE INTO entity (name) VALUES (?1)",
+                [entity.name()],
+            )
+            .map_err(wrap)?;
+        Ok(())
+    }
+
+    fn concept(&mut self, concept: &Concept) -> Result<(), error::Storage> {
+        self.clear()?;
+        let price = concept
+            .default_price()
+            .map(cents)
+            .transpose()
+            .map_err(wrap)?;
+        self.conn
+            .execute(
+                "INSERT INTO concept (name, default_price, gtin, category) VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    concept.name(),
+                    price,
+                    concept.gtin().map(|gtin| gtin.get()),
+                    concept.category(),
+                ],
+            )
+            .map_err(wrap)?;
+        for tag in concept.tags() {
+            self.insert_concept_tag(concept, tag).map_err(wrap)?;
+        }
+        Ok(())
+    }
+
+    fn object(&mut self, object: &Object) -> Result<(), error::Storage> {
+        self.clear()?;
+        let parent = object
+            .parent()
+            .map(|parent| self.concept_id(parent))
+            .transpose()
+            .map_err(wrap)?;
+        self.conn
+            .execute(
+                "INSERT INTO object (name, parent) VALUES (?1, ?2)",
+                params![object.name(), parent],
+            )
+            .map_err(wrap)?;
+        Ok(())
+    }
+
+    fn transfer(&mut self, transfer: &Transfer) -> Result<(), error::Storage> {
+        self.clear()?;
+        self.insert_transfer(transfer).map_err(wrap)
+    }
+
+    fn tag(&mut self, concept: &Concept, tag: NameRef) -> Result<(), error::Storage> {
+        self.clear()?;
+        self.insert_concept_tag(concept, tag).map_err(wrap)
+    }
+
+    fn budget(&mut self, budget: &Budget) -> Result<(), error::Storage> {
+        self.clear()?;
+        self.conn
+            .execute(
+                "
+                INSERT INTO budget (entity, category, amount, per) VALUES (?1, ?2, ?3, ?4)
+                ON CONFLICT (entity, category) DO UPDATE
+                SET amount = excluded.amount, per = excluded.per
+                ",
+                params![
+                    budget.of.name(),
+                    budget.category,
+                    cents(&budget.limit).map_err(wrap)?,
+                    budget.per.name(),
+                ],
+            )
+            .map_err(wrap)?;
+        Ok(())
+    }
+
+    fn void(&mut self, idx: usize) -> Result<(), error::Storage> {
+        self.clear()?;
+        self.conn
+            .execute(
+                "
+                UPDATE transfer SET voided = 1
+                WHERE id = (SELECT id FROM transfer ORDER BY id LIMIT 1 OFFSET ?1)
+                ",
+                [idx],
+            )
+            .map_err(wrap)?;
+        Ok(())
+    }
+
+    fn alias(&mut self, actor: &Actor, name: NameRef) -> Result<(), error::Storage> {
+        self.clear()?;
+        let (column, id) = self.actor_ref(actor).map_err(wrap)?;
+        self.conn
+            .execute(
+                &format!("INSERT INTO alias (name, {column}) VALUES (?1, ?2)"),
+                params![name, id],
+            )
+            .map_err(wrap)?;
+        Ok(())
+    }
+
+    fn rename(&mut self, actor: &Actor, from: NameRef, to: NameRef) -> Result<(), error::Storage> {
+        self.clear()?;
+        let (column, id) = self.actor_ref(actor).map_err(wrap)?;
+
+        if actor.name() != Some(from) {
+            self.conn
+                .execute(
+                    &format!(
+                        "
+                        UPDATE alias SET name = ?3
+                        WHERE id = (
+                            SELECT id FROM alias WHERE name = ?1 AND {column} IS ?2
+                            ORDER BY id DESC LIMIT 1
+                        )
+                        "
+                    ),
+                    params![from, id, to],
+                )
+                .map_err(wrap)?;
+            return Ok(());
+        }
+
+        let updates: &[&str] = match actor {
+            Actor::Entity(_) => &[
+                "UPDATE entity SET name = ?2 WHERE name = ?1",
+                "UPDATE transfer SET source = ?2 WHERE source = ?1",
+                "UPDATE transfer SET target = ?2 WHERE target = ?1",
+                "UPDATE alias SET entity = ?2 WHERE entity = ?1",
+                "UPDATE budget SET entity = ?2 WHERE entity = ?1",
+                "UPDATE balance SET a = ?2 WHERE a = ?1",
+                "UPDATE balance SET b = ?2 WHERE b = ?1",
+            ],
+            Actor::Concept(_) => &["UPDATE concept SET name = ?2 WHERE id = ?1"],
+            Actor::Object(_) => &["UPDATE object SET name = ?2 WHERE id = ?1"],
+        };
+        for sql in updates {
+            self.conn.execute(sql, params![id, to]).map_err(wrap)?;
+        }
+        // pairs might be ordered the other way around now
+        self.conn
+            .execute_batch("UPDATE balance SET a = b, b = a, balance = -balance WHERE a > b")
+            .map_err(wrap)
+    }
+
+    // savepoints nest, unlike `BEGIN`
+    fn begin(&mut self) -> Result<(), error::Storage> {
+        self.conn.execute_batch("SAVEPOINT tx").map_err(wrap)?;
+        self.depth += 1;
+        self.clear()
+    }
+
+    fn commit(&mut self) -> Result<(), error::Storage> {
+        self.conn.execute_batch("RELEASE tx").map_err(wrap)?;
+        self.depth -= 1;
+        // the clearing is now part of the outer transaction
+        self.cleared = self.cleared.map(|depth| depth.min(self.depth));
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), error::Storage> {
+        self.conn
+            .execute_batch("ROLLBACK TO tx; RELEASE tx")
+            .map_err(wrap)?;
+        self.depth -= 1;
+        // the clearing might've been undone
+        self.cleared = self.cleared.filter(|depth| *depth <= self.depth);
+        Ok(())
+    }
+
+    fn balance(&mut self, pair: &Pair, balance: &Balance) -> Result<(), error::Storage> {
+        self.clear()?;
+        let Balance(balance) = balance;
+        self.conn
+            .execute(
+                "
+                INSERT INTO balance (a, b, balance) VALUES (?1, ?2, ?3)
+                ON CONFLICT (a, b) DO UPDATE SET balance = excluded.balance
+                ",
+                params![
+                    pair.a().name(),
+                    pair.b().name(),
+                    int(balance).map_err(wrap)?
+                ],
+            )
+            .map_err(wrap)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use rusqlite::OptionalExtension;
+
+    use crate::{Runtime, eval_from};
+
+    use super::*;
+
+    const LEDGER: &str = "
+        create entity { Store; A; B }
+        create concept Mate price=1.50€ gtin=4002846034504 tags=drinks
+        create object Keyboard parent=Mate
+
+        deliver Mate from=Store to=A date=2024-12-31
+        deliver Keyboard price=20€ from=A to=B split=1:1
+        pay 5€ from=B to=A tags=flat,cash
+        tag Mate bio
+        pay 2€ from=A to=B as=oops memo=\"sent twice\"
+        void oops
+    ";
+
+    #[test]
+    fn mirror() {
+        let path = env::temp_dir().join(format!("nyandere-test-{}.sqlite", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let rt = eval_from(
+            Runtime::with_storage(Sqlite::open(&path).unwrap()),
+            LEDGER,
+            0,
+        )
+        .unwrap();
+
+        let db = open_read_only(&path).unwrap();
+        let query = |sql: &str| -> Vec<(String, String, i64)> {
+            db.prepare(sql)
+                .unwrap()
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            query("SELECT kind, target, amount FROM transfer ORDER BY id"),
+            [
+                ("deliver".into(), "A".into(), 150),
+                ("deliver".into(), "B".into(), 2000),
+                ("pay".into(), "A".into(), 500),
+                ("pay".into(), "B".into(), 200),
+            ],
+        );
+        assert_eq!(
+            query("SELECT a, b, balance FROM balance ORDER BY a, b"),
+            [
+                ("A".into(), "B".into(), -1000 + 500),
+                ("A".into(), "Store".into(), 150),
+            ],
+        );
+
+        let dates: Vec<Option<String>> = db
+            .prepare("SELECT date FROM transfer ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(dates, [Some("2024-12-31".into()), None, None, None]);
+
+        // voided, but still there
+        assert_eq!(
+            query("SELECT label, memo, voided FROM transfer WHERE label IS NOT NULL"),
+            [("oops".into(), "sent twice".into(), 1)],
+        );
+
+        let tags: Vec<(String, Option<i64>, Option<i64>)> = db
+            .prepare("SELECT tag, concept, transfer FROM tag ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            tags,
+            [
+                ("drinks".into(), Some(1), None),
+                ("flat".into(), None, Some(3)),
+                ("cash".into(), None, Some(3)),
+                ("bio".into(), Some(1), None),
+            ]
+        );
+
+        // read-only really means read-only
+        assert!(db.execute("DELETE FROM transfer", []).is_err());
+
+        // and it agrees with the in-memory state
+        let dir = rt.get_dir("A", "B").unwrap();
+        assert_eq!(rt.state().balance(dir).0, 500.into());
+
+        // opening again starts from scratch once used, without migrating twice
+        let mut sqlite = Sqlite::open(&path).unwrap();
+        let mate = rt.get_concept("Mate").unwrap();
+        assert!(sqlite.concept_id(mate).optional().unwrap().is_some());
+        sqlite.begin().unwrap();
+        sqlite.commit().unwrap();
+        assert_eq!(sqlite.concept_id(mate).optional().unwrap(), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rename() {
+        let path = env::temp_dir().join(format!(
+            "nyandere-test-rename-{}.sqlite",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        eval_from(
+            Runtime::with_storage(Sqlite::open(&path).unwrap()),
+            "
+            create entity { Store; A }
+            create concept Mate price=1.50€
+            alias Späti = Store
+            alias concept Tschunk = Mate
+            deliver Mate from=Store to=A
+            rename A to Zed
+            rename Tschunk to Cocktail
+            rename Mate to Club-Mate
+            ",
+            0,
+        )
+        .unwrap();
+
+        let db = open_read_only(&path).unwrap();
+        let row = |sql: &str| -> (String, String, i64) {
+            db.query_row(sql, [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .unwrap()
+        };
+        assert_eq!(
+            row("SELECT a, b, balance FROM balance"),
+            ("Store".into(), "Zed".into(), -150),
+        );
+        assert_eq!(
+            row("SELECT source, target, amount FROM transfer"),
+            ("Store".into(), "Zed".into(), 150),
+        );
+        assert_eq!(
+            row("
+                SELECT alias.name, concept.name, concept.default_price
+                FROM alias JOIN concept ON alias.concept = concept.id
+            "),
+            ("Cocktail".into(), "Club-Mate".into(), 150),
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn rollback() {
+        let path = env::temp_dir().join(format!(
+            "nyandere-test-rollback-{}.sqlite",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let count = |table: &str| -> i64 {
+            let db = open_read_only(&path).unwrap();
+            db.query_row(&format!("SELECT count(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+        };
+        const TABLES: [&str; 5] = ["entity", "concept", "object", "transfer", "balance"];
+
+        eval_from(
+            Runtime::with_storage(Sqlite::open(&path).unwrap()),
+            LEDGER,
+            0,
+        )
+        .unwrap();
+        let before = TABLES.map(count);
+        assert!(before.iter().all(|n| *n > 0));
+
+        // a failing run leaves what was there before, not an empty database
+        let failing = "create entity C\nbegin; pay 1€ from=A to=C; commit\npay 1€ from=A to=D";
+        let result = eval_from(
+            Runtime::with_storage(Sqlite::open(&path).unwrap()),
+            failing,
+            0,
+        );
+        assert!(result.is_err());
+        assert_eq!(TABLES.map(count), before);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
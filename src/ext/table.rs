@@ -0,0 +1,114 @@
+//! Line up rows of text in columns, for reports meant for humans,
+//! or write them as CSV for spreadsheets.
+
+use std::fmt;
+
+use crate::aux::Owned;
+
+/// Rows of cells under a header,
+/// [shown][fmt::Display] with every column as wide as its widest cell.
+#[derive(Owned!)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<S: ToString>(header: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            header: header.into_iter().map(|cell| cell.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Appends a row with as many cells as the header.
+    pub fn push<S: ToString>(&mut self, row: impl IntoIterator<Item = S>) {
+        let row: Vec<_> = row.into_iter().map(|cell| cell.to_string()).collect();
+        debug_assert_eq!(row.len(), self.header.len(), "row to fit the header");
+        self.rows.push(row);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// As CSV with the header as first line,
+    /// quoting cells as needed per RFC 4180.
+    pub fn to_csv(&self) -> String {
+        let quoted = |cell: &str| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_owned()
+            }
+        };
+
+        std::iter::once(&self.header)
+            .chain(&self.rows)
+            .map(|line| {
+                let line: Vec<_> = line.iter().map(|cell| quoted(cell)).collect();
+                format!("{}\r\n", line.join(","))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Table {
+    /// Columns are separated by 2 spaces,
+    /// without any trailing whitespace.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = || std::iter::once(&self.header).chain(&self.rows);
+        let mut widths = vec![0; self.header.len()];
+        for line in lines() {
+            for (width, cell) in widths.iter_mut().zip(line) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        for line in lines() {
+            let mut out = String::new();
+            for (cell, width) in line.iter().zip(&widths) {
+                out.push_str(&format!("{cell:width$}  "));
+            }
+            writeln!(f, "{}", out.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligned() {
+        let mut table = Table::new(["tag", "entity", "spent"]);
+        table.push(["drinks", "A", "3.00 €"]);
+        table.push(["groceries", "Bär", "12.40 €"]);
+
+        assert_eq!(
+            table.to_string(),
+            concat!(
+                "tag        entity  spent\n",
+                "drinks     A       3.00 €\n",
+                "groceries  Bär     12.40 €\n",
+            )
+        );
+    }
+
+    #[test]
+    fn csv() {
+        let mut table = Table::new(["period", "paid"]);
+        table.push(["2025-01", "1.234,50 €"]);
+        table.push(["\"best\" week", "0,00 €"]);
+
+        assert_eq!(
+            table.to_csv(),
+            concat!(
+                "period,paid\r\n",
+                "2025-01,\"1.234,50 €\"\r\n",
+                "\"\"\"best\"\" week\",\"0,00 €\"\r\n",
+            )
+        );
+    }
+}
@@ -2,9 +2,20 @@ use std::fmt;
 
 use num_bigint::Sign;
 
-use crate::runtime::model::{Concept, Entity, Object, Product};
+use crate::{
+    runtime::{
+        cmd::{Change, Help, history::Entry},
+        model::{Concept, Entity, Object, Product, Transfer},
+        query::Cell,
+        schema::{SCHEMAS, Schema},
+    },
+    syntax::print::SourceGtin,
+};
 
-use super::{Balance, Debit, Gtin, Money};
+use super::{
+    Balance, Debit, Gtin, Money,
+    locale::{Fraction, Locale},
+};
 
 impl fmt::Display for Gtin {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -14,10 +25,7 @@ impl fmt::Display for Gtin {
 
 impl fmt::Display for Money {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let whole = &self.0 / 100u8;
-        let frac = &self.0 % 100u8;
-
-        write!(f, "{whole}.{frac:02} €")
+        Locale::current().write(f, self, Fraction::Always, true)
     }
 }
 
@@ -45,6 +53,76 @@ impl fmt::Display for Debit {
     }
 }
 
+impl fmt::Display for Change {
+    /// Oriented so the debtor comes first,
+    /// preferring the one after the change.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mut debtor, mut creditor) = (self.between.a(), self.between.b());
+        let (mut before, mut after) = (self.before.clone(), self.after.clone());
+
+        let sign = match after.0.sign() {
+            Sign::NoSign => before.0.sign(),
+            sign => sign,
+        };
+        if sign == Sign::Minus {
+            (debtor, creditor) = (creditor, debtor);
+            before.flip();
+            after.flip();
+        }
+
+        write!(f, "{debtor} owes {creditor}: {before} → {after}")
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. {}", self.number, self.transfer)
+    }
+}
+
+impl fmt::Display for Transfer {
+    /// Everything about it on one line, e.g.
+    /// `2025-03-02 entity B delivered concept Mate to entity C for 1.50 € as mate`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(date) = self.date() {
+            write!(f, "{date} ")?;
+        }
+
+        let who = self.who();
+        match self {
+            Transfer::Pay(pay) => write!(
+                f,
+                "{} paid {} to {}",
+                who.source(),
+                pay.amount,
+                who.target()
+            )?,
+            Transfer::Deliver(deliver) => {
+                write!(f, "{} delivered {}", who.source(), deliver.product)?;
+                if deliver.qty != 1u8.into() {
+                    write!(f, " ×{}", deliver.qty)?;
+                }
+                write!(f, " to {} for {}", who.target(), deliver.price)?;
+            }
+        }
+
+        if let Some(label) = self.label() {
+            write!(f, " as {label}")?;
+        }
+        let tags = self.tags();
+        if !tags.is_empty() {
+            write!(f, ", tagged {}", tags.join(", "))?;
+        }
+        if let Some(memo) = self.memo() {
+            write!(f, " ({memo})")?;
+        }
+        if self.is_voided() {
+            write!(f, " [voided]")?;
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Display for Entity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "entity {}", self.name())
@@ -82,3 +160,86 @@ impl fmt::Display for Object {
         Ok(())
     }
 }
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Empty => Ok(()),
+            Cell::Text(text) => write!(f, "{text}"),
+            Cell::Money(money) => write!(f, "{money}"),
+            Cell::Natural(n) => write!(f, "{n}"),
+            Cell::Date(date) => write!(f, "{date}"),
+            Cell::Gtin(gtin) => write!(f, "{}", SourceGtin(*gtin)),
+            Cell::Tags(tags) => write!(f, "{}", tags.join(", ")),
+        }
+    }
+}
+
+impl fmt::Display for Help {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Help::Overview => {
+                let names: Vec<_> = SCHEMAS
+                    .iter()
+                    .filter(|schema| !schema.line)
+                    .map(|schema| match schema.form {
+                        Some(form) => (format!("{} {form}", schema.command), schema.about),
+                        None => (schema.command.to_string(), schema.about),
+                    })
+                    .collect();
+                let width = names.iter().map(|(name, _)| name.len()).max();
+                for (name, about) in &names {
+                    writeln!(f, "{name:width$}  {about}", width = width.unwrap_or(0))?;
+                }
+                writeln!(f)?;
+                writeln!(f, "`help <command>` explains one in detail.")
+            }
+            Help::Forms(forms) => {
+                for (idx, schema) in forms.iter().enumerate() {
+                    if idx > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", Explain(schema))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Usage, parameters and examples of one schema.
+struct Explain<'a>(&'a Schema);
+
+impl fmt::Display for Explain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Explain(schema) = self;
+        match schema.line {
+            true => writeln!(f, "<line>: {schema}")?,
+            false => writeln!(f, "{schema}")?,
+        }
+        writeln!(f, "{}", schema.about)?;
+
+        let params: Vec<_> = schema.params.iter().chain(schema.expected).collect();
+        let shown: Vec<_> = params.iter().map(ToString::to_string).collect();
+        let width = shown.iter().map(|param| param.chars().count()).max();
+        if !params.is_empty() {
+            writeln!(f)?;
+        }
+        for (param, shown) in params.iter().zip(&shown) {
+            let width = width.unwrap_or(0);
+            write!(f, "  {shown:width$}  {}", param.about)?;
+            match param.default {
+                Some(default) => writeln!(f, " (default: {default})")?,
+                None => writeln!(f)?,
+            }
+        }
+
+        if !schema.examples.is_empty() {
+            writeln!(f)?;
+        }
+        for example in schema.examples {
+            writeln!(f, "  {example}")?;
+        }
+        Ok(())
+    }
+}
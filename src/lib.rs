@@ -40,30 +40,124 @@ pub mod syntax;
 use ext::config;
 pub use runtime::Runtime;
 
+use std::{fs, io, path::Path};
+
 use eyre::{Result, WrapErr, format_err};
-use syntax::ast::Script;
+use syntax::ast::{Arg, Command, Script, Stmt, Value};
 
 pub fn run() -> Result<()> {
     let cfg = config::cli();
+    cfg.locale()?.set();
+
+    match cfg.cmd {
+        Some(config::Cmd::Scan(cfg)) => ext::scan::cli(cfg)?,
+        Some(config::Cmd::ImportCsv(cfg)) => ext::import::cli(cfg)?,
+        Some(config::Cmd::Graph(cfg)) => ext::graph::cli(cfg)?,
+        Some(config::Cmd::Run(run)) => run_ledger(&run, cfg.until)?,
+        Some(config::Cmd::Check(check)) => ext::lint::cli(check, cfg.until)?,
+        Some(config::Cmd::Balance(balance)) => ext::ask::balance(balance, cfg.until)?,
+        Some(config::Cmd::History(history)) => ext::ask::history(history, cfg.until)?,
+        Some(config::Cmd::Settle(settle)) => ext::ask::settle(settle, cfg.until)?,
+        Some(config::Cmd::Explain(explain)) => {
+            let topic = explain.topic.iter().map(|word| Arg::Pos(Value::name(word)));
+            let stmt = Stmt::new(Command::Help, topic.collect());
+            let help = Runtime::new()
+                .repr(stmt)
+                .wrap_err("while looking up the command")?;
+            Runtime::new().fulfil(help)?;
+        }
+        None => run_ledger(&cfg.run, cfg.until)?,
+    }
+
+    Ok(())
+}
+
+/// Runs the configured source, printing what its queries answer,
+/// and writes the results where configured.
+fn run_ledger(cfg: &config::Run, until: Option<ext::Date>) -> Result<()> {
+    let file = cfg.source.file().map(Path::to_path_buf);
     let script = cfg.source.get().wrap_err("while loading source")?;
-    eval(script)?;
+    let mut runtime = match &cfg.snapshot {
+        Some(path) => ext::snapshot::cli(&script, file.as_deref(), path)?,
+        None => eval_from(runtime(cfg, until)?, &script, 0)?,
+    };
+    warn_overruns(&mut runtime);
+
+    if let Some(path) = &cfg.emit {
+        let file = fs::File::create(path)
+            .wrap_err_with(|| format!("tried to create `{}`", path.display()))?;
+        ext::json::to_writer(runtime.state(), io::BufWriter::new(file))
+            .wrap_err("while emitting state")?;
+    }
+
+    if let Some(path) = &cfg.beancount {
+        let today = ext::Date(time::OffsetDateTime::now_utc().date());
+        let file = fs::File::create(path)
+            .wrap_err_with(|| format!("tried to create `{}`", path.display()))?;
+        ext::beancount::to_writer(runtime.state(), today, file)
+            .wrap_err("while exporting to beancount")?;
+    }
 
     Ok(())
 }
 
+/// Warns about every budget that was gone over.
+pub(crate) fn warn_overruns(runtime: &mut Runtime) {
+    for usage in runtime.take_overruns() {
+        eprintln!("warning: {usage}");
+    }
+}
+
+/// The runtime to start with as configured.
+fn runtime(
+    #[cfg_attr(not(feature = "sqlite"), allow(unused_variables))] cfg: &config::Run,
+    until: Option<ext::Date>,
+) -> Result<Runtime> {
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &cfg.sqlite {
+        let db = ext::sqlite::Sqlite::open(path)
+            .wrap_err_with(|| format!("tried to open database `{}`", path.display()))?;
+        let mut runtime = Runtime::with_storage(db);
+        runtime.set_horizon(until);
+        return Ok(runtime);
+    }
+
+    let mut runtime = Runtime::new();
+    runtime.set_horizon(until);
+    Ok(runtime)
+}
+
 /// Parses and runs the given script,
 /// returning the final runtime state.
 pub fn eval(script: impl AsRef<str>) -> Result<Runtime> {
-    let script = Script::parse(script.as_ref())
+    eval_from(Runtime::new(), script.as_ref(), 0)
+}
+
+/// Parses and [atomically][Runtime::run_atomic] runs `source` on `runtime`,
+/// skipping the first `start` bytes.
+///
+/// Errors still report lines relative to all of `source`.
+pub(crate) fn eval_from(mut runtime: Runtime, source: &str, start: usize) -> Result<Runtime> {
+    eval_in(&mut runtime, source, start)?;
+    Ok(runtime)
+}
+
+/// Like [`eval_from`], but runs in place.
+///
+/// If it fails, `runtime` is left as it was before.
+pub(crate) fn eval_in(runtime: &mut Runtime, source: &str, start: usize) -> Result<()> {
+    let script = Script::parse(&source[start..])
         .into_result()
         .map_err(|orig| format_err!("while parsing source code: {orig:?}"))?;
 
-    dbg!(&script);
+    runtime.run_atomic(script).map_err(|err| {
+        let (start, end) = (start + err.span.start, start + err.span.end);
+        let line = source[..start].matches('\n').count() + 1;
+        let stmt = &source[start..end];
+        eyre::Report::new(err).wrap_err(format!("in line {line}: `{stmt}`"))
+    })?;
 
-    let mut runtime = Runtime::new();
-    runtime.run(script).unwrap();
-
-    Ok(runtime)
+    Ok(())
 }
 
 #[cfg(test)]
@@ -0,0 +1,132 @@
+use crate::{
+    Runtime,
+    aux::Owned,
+    runtime::{error, model::Actor},
+};
+
+use super::Name;
+
+/// Lets an existing actor also be referred to by another name,
+/// e.g. a nickname.
+///
+/// Unlike creating it again under that name,
+/// it stays the same actor with the same balances.
+#[derive(Owned!)]
+pub struct Alias {
+    pub actor: Actor,
+    /// Not naming any other actor of the same kind yet.
+    pub name: Name,
+}
+
+/// Changes a name of an existing actor,
+/// e.g. because a store was rebranded.
+///
+/// The actor keeps its identity and balances,
+/// and everything referring to it shows the new name from now on.
+#[derive(Owned!)]
+pub struct Rename {
+    pub actor: Actor,
+    /// Either what the actor is called or one of its aliases.
+    pub from: Name,
+    /// Not naming any other actor of the same kind yet.
+    pub to: Name,
+}
+
+impl Runtime {
+    pub fn alias(&mut self, cmd: Alias) -> Result<(), error::Storage> {
+        self.store(|s| s.alias(&cmd.actor, &cmd.name))
+    }
+
+    pub fn rename(&mut self, cmd: Rename) -> Result<(), error::Storage> {
+        self.store(|s| s.rename(&cmd.actor, &cmd.from, &cmd.to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        eval,
+        ext::Integer,
+        runtime::model::{Product, Transfer},
+    };
+
+    #[test]
+    fn alias() {
+        let rt = eval(
+            "
+            create entity { Robert; Kim }
+            create concept Mate price=1.50€
+            alias Bob = Robert
+            alias concept Tschunk = Mate
+            pay 5€ from=Bob to=Kim
+            deliver Tschunk from=Kim to=Robert
+            assert balance from=Robert to=Kim = 3.50€
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(rt.get_entity("Bob"), rt.get_entity("Robert"));
+        assert_eq!(rt.get_concept("Tschunk"), rt.get_concept("Mate"));
+        // still only one entity
+        assert_eq!(rt.entities.len(), 2);
+        assert_eq!(rt.ledger[0].who().source().name(), "Robert");
+    }
+
+    #[test]
+    fn rename() {
+        let rt = eval(
+            "
+            create entity { Späti; Lou; Z }
+            create concept Mate price=1.50€
+            create object crate parent=Mate
+            alias Corner = Späti
+            deliver Mate from=Späti to=Lou
+            deliver crate price=3€ from=Späti to=Z
+            rename Späti to Kiosk
+            rename Mate to=Club-Mate
+            rename Corner to Shop
+            assert balance from=Kiosk to=Lou = 1.50€
+            assert balance from=Shop to=Z = 3€
+            ",
+        )
+        .unwrap();
+
+        for gone in ["Späti", "Corner"] {
+            assert!(rt.get_entity(gone).is_err());
+        }
+        assert!(rt.get_concept("Mate").is_err());
+        assert_eq!(rt.get_entity("Shop").unwrap().name(), "Kiosk");
+
+        // history shows the current names
+        let Transfer::Deliver(deliver) = &rt.ledger[0] else {
+            panic!("expected a delivery");
+        };
+        assert_eq!(deliver.who.source().name(), "Kiosk");
+        let Product::Concept(mate) = &deliver.product else {
+            panic!("expected a concept");
+        };
+        assert_eq!(mate.name(), "Club-Mate");
+        let crate_ = rt.get_object("crate").unwrap();
+        assert_eq!(crate_.parent(), Some(mate));
+
+        // Lou sorts after Kiosk, but before Späti
+        let dir = rt.get_dir("Lou", "Kiosk").unwrap();
+        assert_eq!(rt.state().balance(dir).0, Integer::from(-150));
+    }
+
+    #[test]
+    fn invalid() {
+        let setup = "create entity { A; B }\ncreate concept B\n";
+        for (script, reason) in [
+            ("alias A = B", "more than one"),
+            ("alias A = C", "unknown"),
+            ("alias entity A = B", "taken"),
+            ("rename A to B", "taken"),
+            ("rename concept B to A\nrename A to C", "more than one"),
+            ("alias X A = B", "kind"),
+        ] {
+            let err = eval(format!("{setup}{script}")).unwrap_err();
+            assert!(format!("{err:?}").contains(reason), "{script}: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+use crate::{
+    Runtime,
+    aux::Owned,
+    ext::{Balance, Integer, Money},
+    runtime::{
+        error,
+        model::{Concept, Dir, Entity, Product},
+    },
+};
+
+/// Makes sure something is as expected,
+/// so a script can check itself.
+///
+/// For example, to catch accidental edits in old parts of a ledger.
+#[derive(Owned!)]
+pub enum Assert {
+    /// How much `between.target` owes `between.source`.
+    Balance { between: Dir, expected: Balance },
+    /// How much `of` owes all other entities together.
+    Net { of: Entity, expected: Balance },
+    /// How often `product` was delivered to `of`, minus how often `of` delivered it.
+    Inventory {
+        of: Entity,
+        product: Product,
+        expected: Integer,
+    },
+    /// The default price of `concept`.
+    Price { concept: Concept, expected: Money },
+}
+
+impl Runtime {
+    /// Checks that the assertion holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns the expected and actual value if it doesn't.
+    pub fn assert(&self, cmd: Assert) -> Result<(), error::AssertFailed> {
+        match cmd {
+            Assert::Balance { between, expected } => check(
+                format!(
+                    "balance from {} to {}",
+                    between.source().name(),
+                    between.target().name(),
+                ),
+                expected,
+                self.state.balance(between.clone()),
+            ),
+            Assert::Net { of, expected } => check(
+                format!("net of {}", of.name()),
+                expected,
+                self.state.net(&of),
+            ),
+            Assert::Inventory {
+                of,
+                product,
+                expected,
+            } => check(
+                format!("inventory of {} in {product}", of.name()),
+                expected,
+                self.state.inventory(&of, &product),
+            ),
+            Assert::Price { concept, expected } => {
+                let actual = concept
+                    .default_price()
+                    .map_or_else(|| "unset".to_owned(), Money::to_string);
+                check(format!("price of {concept}"), expected.to_string(), actual)
+            }
+        }
+    }
+}
+
+fn check<T: PartialEq + fmt::Display>(
+    what: String,
+    expected: T,
+    actual: T,
+) -> Result<(), error::AssertFailed> {
+    if expected == actual {
+        return Ok(());
+    }
+
+    Err(error::AssertFailed {
+        what,
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval;
+
+    const LEDGER: &str = "
+        create entity { Store; A; B }
+        create concept Mate price=1.50€ gtin=4002846034504
+        create concept Pizza
+
+        deliver Mate from=Store to=A
+        deliver Mate from=A to=B
+        deliver Mate from=Store to=A
+        deliver Pizza price=9€ from=B to=A split=1:2
+    ";
+
+    #[test]
+    fn holds() {
+        let script = format!(
+            "{LEDGER}
+            assert balance from=A to=B = -4.50€
+            assert balance from=B to=A = 4.50€
+            assert net A = 7.50€
+            assert net Store = -3€
+            assert inventory A Mate = 1
+            assert inventory B 4002846034504 = 1
+            assert inventory Store Mate = -2
+            assert price Mate = 1.50€
+            "
+        );
+        eval(script).unwrap();
+    }
+
+    #[test]
+    fn receipt_inventory() {
+        let script = format!(
+            "{LEDGER}
+            receipt from=A at=Store {{
+                Mate 3 for B Store
+            }}
+            assert inventory A Mate = 4
+            assert inventory B Mate = 1
+            assert inventory Store Mate = -5
+            "
+        );
+        eval(script).unwrap();
+    }
+
+    #[test]
+    fn fails() {
+        for assertion in [
+            "assert balance from=A to=B = 4€",
+            "assert net A = 0€",
+            "assert inventory A Mate = 2",
+            "assert price Mate = 2€",
+        ] {
+            let err = eval(format!("{LEDGER}\n{assertion}")).unwrap_err();
+            let msg = format!("{err:?}");
+            assert!(msg.contains(assertion), "{msg}");
+            assert!(msg.contains("expected"), "{msg}");
+        }
+    }
+}
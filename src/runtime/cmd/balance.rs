@@ -1,18 +1,29 @@
 use num_bigint::Sign;
 
-use crate::{Runtime, aux::Owned, ext::Debit, runtime::model::Dir};
-
-/// Evaluates how much `between.source` owes `between.target`.
+use crate::{
+    Runtime,
+    aux::Owned,
+    ext::{Date, Debit},
+    runtime::model::Dir,
+};
+
+/// Evaluates which of `between.source` and `between.target` owes the other how much.
 #[derive(Owned!)]
 pub struct Balance {
     pub between: Dir,
+    /// Only count transfers up to and including this day.
+    pub as_of: Option<Date>,
 }
 
 impl Runtime {
-    pub fn balance(&self, Balance { mut between }: Balance) -> Debit {
-        let bal = self.state.balance(between.clone());
-
-        if let Sign::Minus = bal.0.sign() {
+    pub fn balance(&self, Balance { mut between, as_of }: Balance) -> Debit {
+        let bal = match as_of {
+            Some(date) => self.state.balance_as_of(between.clone(), date),
+            None => self.state.balance(between.clone()),
+        };
+
+        // the state's balance is how much the target owes
+        if let Sign::Plus = bal.0.sign() {
             between.flip();
         }
 
@@ -43,6 +54,7 @@ mod tests {
 
         rt.balance(super::Balance {
             between: rt.get_dir("A", "B").unwrap(),
+            as_of: None,
         });
     }
 }
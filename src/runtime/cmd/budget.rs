@@ -0,0 +1,248 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Map, Runtime,
+    aux::Owned,
+    ext::{Date, Money, Natural},
+    runtime::{
+        error,
+        model::{Concept, Entity, State, Transfer},
+    },
+};
+
+use super::{Name, every::Period};
+
+/// Limits how much an entity may spend on a category in each period,
+/// e.g. 300€ on groceries per month.
+///
+/// What an entity spends is what it is expected to pay
+/// for the deliveries it receives
+/// with a concept (or object of one) in the category,
+/// or with the category among their [tags][Transfer::tags].
+/// Deliveries without a date don't belong to any period
+/// and are left out.
+#[derive(Owned!, Serialize, Deserialize)]
+pub struct Budget {
+    pub of: Entity,
+    /// As given with `category` on concepts,
+    /// or one of the tags of a delivery.
+    pub category: Name,
+    pub limit: Money,
+    pub per: Period,
+}
+
+/// How much of a [`Budget`] was spent in one period.
+#[derive(Owned!)]
+pub struct Usage {
+    pub budget: Budget,
+    /// Any day in the period.
+    pub during: Date,
+    pub spent: Money,
+}
+
+impl Usage {
+    pub fn is_over(&self) -> bool {
+        self.spent > self.budget.limit
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Budget {
+            of,
+            category,
+            limit,
+            per,
+        } = &self.budget;
+        write!(
+            f,
+            "{of} spent {} of {limit} on {category} in {}",
+            self.spent,
+            per.label(self.during),
+        )?;
+        if self.is_over() {
+            write!(f, ", over by {}", Money(&self.spent.0 - &limit.0))?;
+        }
+        Ok(())
+    }
+}
+
+impl Runtime {
+    pub fn budget(&mut self, cmd: Budget) -> Result<(), error::Storage> {
+        self.store(|s| s.budget(&cmd))
+    }
+
+    /// How much of each budget was spent in every period anything was.
+    pub fn budget_report(&self) -> Vec<Usage> {
+        let mut report = Vec::new();
+        for budget in &self.state.budgets {
+            let mut spent: Map<Date, Money> = Map::new();
+            for (date, amount) in self.state.spending(budget) {
+                *spent
+                    .entry(budget.per.start(date))
+                    .or_insert(Money(Natural::ZERO)) += amount;
+            }
+
+            report.extend(spent.into_iter().map(|(during, spent)| Usage {
+                budget: budget.clone(),
+                during,
+                spent,
+            }));
+        }
+        report
+    }
+
+    /// Budgets the last transfer in the ledger went over.
+    fn overrun(&self) -> Vec<Usage> {
+        let Some(transfer @ Transfer::Deliver(deliver)) = self.state.ledger.last() else {
+            return Vec::new();
+        };
+        let Some(date) = deliver.date else {
+            return Vec::new();
+        };
+
+        self.state
+            .budgets
+            .iter()
+            .filter(|budget| counts(transfer, budget))
+            .filter_map(|budget| {
+                let start = budget.per.start(date);
+                let mut spent = Money(Natural::ZERO);
+                for (on, amount) in self.state.spending(budget) {
+                    if budget.per.start(on) == start {
+                        spent += amount;
+                    }
+                }
+
+                let usage = Usage {
+                    budget: budget.clone(),
+                    during: date,
+                    spent,
+                };
+                // only warn when it goes over, not for everything after
+                let before = Money(&usage.spent.0 - transfer.shifted().0);
+                (usage.is_over() && before <= budget.limit).then_some(usage)
+            })
+            .collect()
+    }
+
+    /// Remembers every budget the last transfer went over
+    /// for [`Runtime::take_overruns`].
+    pub(super) fn note_overrun(&mut self) {
+        let overrun = self.overrun();
+        self.overruns.extend(overrun);
+    }
+}
+
+impl State {
+    /// When and how much was spent towards `budget`, in ledger order.
+    fn spending<'a>(&'a self, budget: &'a Budget) -> impl Iterator<Item = (Date, Money)> + 'a {
+        self.ledger.iter().filter_map(move |transfer| {
+            let Transfer::Deliver(deliver) = transfer else {
+                return None;
+            };
+            if deliver.voided || !counts(transfer, budget) {
+                return None;
+            }
+            Some((deliver.date?, transfer.shifted()))
+        })
+    }
+}
+
+/// Whether `transfer` is a delivery spending towards `budget`, regardless of when.
+fn counts(transfer: &Transfer, budget: &Budget) -> bool {
+    let Transfer::Deliver(deliver) = transfer else {
+        return false;
+    };
+    let category = deliver.product.concept().and_then(Concept::category);
+    *deliver.who.target() == budget.of
+        && (category == Some(&budget.category) || transfer.is_tagged(&budget.category))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Runtime, eval, eval_in};
+
+    #[test]
+    fn report() {
+        let rt = eval(
+            "
+            create entity { A; B; Store }
+            create concept Milk price=1.20€ category=groceries
+            create concept Mate price=1.50€ category=drinks
+            create concept Eggs price=3€
+            create object crate parent=Milk
+            budget A groceries 10€ per month
+            budget B groceries 1€ per week
+
+            deliver Milk from=Store to=A date=2025-01-02
+            deliver crate price=9€ from=Store to=A date=2025-01-20
+            deliver Mate from=Store to=A date=2025-01-21
+            deliver Milk from=Store to=A date=2025-02-01
+            deliver Eggs from=Store to=A date=2025-02-02 tags=groceries
+            # no date, so no period
+            deliver Milk from=Store to=A
+            deliver Milk from=Store to=B date=2025-01-20
+            ",
+        )
+        .unwrap();
+
+        let report: Vec<_> = rt.budget_report().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            report,
+            [
+                "entity A spent 10.20 € of 10.00 € on groceries in 2025-01, over by 0.20 €",
+                "entity A spent 4.20 € of 10.00 € on groceries in 2025-02",
+                "entity B spent 1.20 € of 1.00 € on groceries in week of 2025-01-20, over by 0.20 €",
+            ]
+        );
+    }
+
+    #[test]
+    fn overrun() {
+        let mut rt = eval(
+            "
+            create entity { A; Store }
+            create concept Milk price=1.20€ category=groceries
+            budget A groceries 2€ per month
+            deliver Milk from=Store to=A date=2025-01-02
+            ",
+        )
+        .unwrap();
+        assert!(rt.overrun().is_empty());
+
+        let deliver = |rt: &mut Runtime, date: &str| {
+            eval_in(rt, &format!("deliver Milk from=Store to=A date={date}"), 0).unwrap();
+        };
+        deliver(&mut rt, "2025-01-03");
+        assert_eq!(rt.overrun().len(), 1);
+        assert_eq!(rt.take_overruns().len(), 1);
+        assert!(rt.take_overruns().is_empty());
+        // only warned once per period
+        deliver(&mut rt, "2025-01-04");
+        assert!(rt.overrun().is_empty());
+        deliver(&mut rt, "2025-02-01");
+        assert!(rt.overrun().is_empty());
+    }
+
+    #[test]
+    fn overrun_undone() {
+        let mut rt = eval(
+            "
+            create entity { A; Store }
+            create concept Milk price=1.20€ category=groceries
+            budget A groceries 1€ per month
+            whatif { deliver Milk from=Store to=A date=2025-01-02 }
+            ",
+        )
+        .unwrap();
+        assert!(rt.take_overruns().is_empty());
+
+        rt.begin().unwrap();
+        eval_in(&mut rt, "deliver Milk from=Store to=A date=2025-01-02", 0).unwrap();
+        rt.abort().unwrap();
+        assert!(rt.take_overruns().is_empty());
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{Runtime, aux::Owned, runtime::model};
+use crate::{
+    Runtime,
+    aux::Owned,
+    runtime::{error, model},
+};
 
 use super::{Concept, Entity, Object};
 
@@ -37,37 +41,29 @@ pub enum Create {
 }
 
 impl Runtime {
-    pub fn create(&mut self, cmd: Create) {
+    pub fn create(&mut self, cmd: Create) -> Result<(), error::Storage> {
         use Create as C;
         match cmd {
             C::Entity(entity) => {
-                self.state
-                    .entities
-                    .insert(entity.name.clone(), model::Entity { name: entity.name });
+                let entity = model::Entity { name: entity.name };
+                self.store(|s| s.entity(&entity))
             }
             C::Concept(concept) => {
                 let concept = model::Concept {
                     name: concept.name,
                     default_price: concept.default_price,
                     gtin: concept.gtin,
+                    category: concept.category,
+                    tags: concept.tags,
                 };
-
-                // if it has a GTIN, we also need to remember it separately
-                // so it is still stored when it is shadowed by name
-                if let Some(gtin) = concept.gtin {
-                    self.state.concepts_gtin.insert(gtin, concept.clone());
-                }
-
-                self.state.concepts.insert(concept.name.clone(), concept);
+                self.store(|s| s.concept(&concept))
             }
             C::Object(object) => {
-                self.state.objects.insert(
-                    object.name.clone(),
-                    model::Object {
-                        name: Some(object.name),
-                        parent: object.parent,
-                    },
-                );
+                let object = model::Object {
+                    name: Some(object.name),
+                    parent: object.parent,
+                };
+                self.store(|s| s.object(&object))
             }
         }
     }
@@ -108,4 +104,21 @@ mod tests {
             Set::from([("O".into(), "E".into()), ("T".into(), "E".into())])
         );
     }
+
+    #[test]
+    fn quoted() {
+        let rt = eval(
+            r#"
+            create entity { "Mama's Pizza"; "Kim" }
+            create concept "Club Mate 0,5l" price=1€
+            deliver "Club Mate 0,5l" from="Mama's Pizza" to=Kim memo="cold, \"finally\""
+            "#,
+        )
+        .unwrap();
+
+        assert!(rt.get_entity("Mama's Pizza").is_ok());
+        // quoting an identifier doesn't change it
+        assert!(rt.get_entity("Kim").is_ok());
+        assert_eq!(rt.ledger[0].memo(), Some(r#"cold, "finally""#));
+    }
 }
@@ -1,29 +1,117 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{
     Runtime,
     aux::Owned,
-    ext::Money,
-    runtime::model::{Dir, Split},
+    ext::{Date, Money, Natural},
+    runtime::{
+        error,
+        model::{Dir, Product, Rounding, Split, Transfer},
+    },
 };
 
-#[derive(Owned!)]
+use super::Name;
+
+#[derive(Owned!, Serialize, Deserialize)]
 pub struct Deliver {
     pub who: Dir,
+    pub product: Product,
+    /// For all units together.
     pub price: Money,
     pub split: Split,
+    /// How the price is rounded if the split doesn't divide it evenly.
+    #[serde(default)]
+    pub rounding: Rounding,
+    /// How many units of `product` change hands.
+    /// Only counts for [inventory][crate::runtime::State::inventory].
+    #[serde(default = "one", with = "crate::ext::json::natural")]
+    pub qty: Natural,
+    #[serde(default)]
+    pub date: Option<Date>,
+    /// Given with `as`, unique among all transfers.
+    #[serde(default)]
+    pub label: Option<Name>,
+    /// Free-form note, e.g. what it was for.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Given with `tags`, e.g. to find it again in the history.
+    #[serde(default)]
+    pub tags: Vec<Name>,
+    /// Whether it was taken back by [`super::Void`].
+    #[serde(default)]
+    pub voided: bool,
+}
+
+fn one() -> Natural {
+    1u8.into()
 }
 
 impl Runtime {
-    pub fn deliver(&mut self, Deliver { who, price, split }: Deliver) {
-        // at the moment a delivery has no difference to a payment
+    pub fn deliver(&mut self, mut cmd: Deliver) -> Result<(), error::Storage> {
         // the logic of finding the price is handled in the repr
-        // since possession is not modelled nor deliveries/payments/purchases tracked
-        // TODO: track them all ^
+        if let Rounding::Carry(carried) = &mut cmd.rounding {
+            **carried = self.state.carried(&cmd.who);
+        }
+        let transfer = Transfer::Deliver(Box::new(cmd));
+        self.shift(transfer.shifted(), transfer.who().clone())?;
+        self.store(|s| s.transfer(&transfer))?;
+        self.note_overrun();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval, ext::Integer};
+
+    #[test]
+    fn rounding() {
+        let owed = |round: &str| {
+            let rt = eval(format!(
+                "
+                create entity {{ A; B }}
+                create concept Mate
+                deliver Mate price=1.01€ split=2:1 from=A to=B {round}
+                "
+            ))
+            .unwrap();
+            rt.state().balance(rt.get_dir("A", "B").unwrap()).0
+        };
+
+        // exactly 33.67ct
+        assert_eq!(owed(""), Integer::from(34));
+        assert_eq!(owed("round=largest-remainder"), Integer::from(34));
+        assert_eq!(owed("round=nearest"), Integer::from(34));
+        assert_eq!(owed("round=payer"), Integer::from(33));
+        assert_eq!(owed("round=receiver"), Integer::from(34));
+        assert_eq!(owed("round payer"), Integer::from(33));
+        assert!(eval("deliver Mate round=up").is_err());
+    }
 
-        let (_source_supply, target_supply) = split.split(price);
+    #[test]
+    fn carry() {
+        let rt = eval(
+            "
+            create entity { A; B }
+            create concept Mate price=1€
+            deliver Mate split=2:1 from=A to=B round=carry
+            assert balance from=A to=B = 33ct
+            deliver Mate split=2:1 from=A to=B round=carry
+            assert balance from=A to=B = 67ct
+            deliver Mate split=2:1 from=A to=B round=carry
+            assert balance from=A to=B = 1€
+            deliver Mate split=2:1 from=B to=A round=carry
+            assert balance from=A to=B = 67ct
+            ",
+        )
+        .unwrap();
 
-        self.pay(super::Pay {
-            amount: target_supply,
-            who,
-        });
+        // the last one rounded down what A owes B, so A catches up next time
+        let dir = rt.get_dir("A", "B").unwrap();
+        let carried = rt.state().carried(&dir);
+        assert_eq!(
+            (carried.numerator(), carried.denominator().clone()),
+            (&Integer::from(-1), 3u8.into())
+        );
     }
 }
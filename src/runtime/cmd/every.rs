@@ -0,0 +1,289 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use time::{Duration, Month};
+
+use crate::{
+    Runtime,
+    aux::Stack,
+    ext::Date,
+    runtime::error,
+    syntax::ast::{self, Arg, Args, Ident, Script, Stmt, Value},
+};
+
+/// How often an `every` statement repeats its body,
+/// or how long a [budget][super::Budget] lasts.
+#[derive(Stack!, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Day,
+    /// From Monday to Sunday.
+    Week,
+    Month,
+    Year,
+}
+
+impl Period {
+    /// How it's written in source code.
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Day => "day",
+            Period::Week => "week",
+            Period::Month => "month",
+            Period::Year => "year",
+        }
+    }
+
+    /// The first day of the calendar period `date` is in.
+    pub fn start(self, date: Date) -> Date {
+        let date = date.0;
+        let start = match self {
+            Period::Day => date,
+            Period::Week => date - Duration::days(date.weekday().number_days_from_monday().into()),
+            Period::Month => date.replace_day(1).expect("every month to have a 1st"),
+            Period::Year => date
+                .replace_ordinal(1)
+                .expect("every year to have a 1st day"),
+        };
+        Date(start)
+    }
+
+    /// Names the calendar period `date` is in, e.g. `2025-01` for a month.
+    pub fn label(self, date: Date) -> String {
+        let start = self.start(date);
+        match self {
+            Period::Day => start.to_string(),
+            Period::Week => format!("week of {start}"),
+            Period::Month => format!("{}-{:02}", start.0.year(), u8::from(start.0.month())),
+            Period::Year => start.0.year().to_string(),
+        }
+    }
+}
+
+impl FromStr for Period {
+    type Err = ();
+
+    /// `day`, `week`, `month` or `year`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "day" => Self::Day,
+            "week" => Self::Week,
+            "month" => Self::Month,
+            "year" => Self::Year,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// When the body of an `every` statement happens,
+/// e.g. rent on the first of every month.
+#[derive(Stack!)]
+pub struct Schedule {
+    pub period: Period,
+    /// The first occurrence.
+    pub from: Date,
+    /// Nothing happens after this, if given.
+    pub until: Option<Date>,
+}
+
+impl Schedule {
+    /// The `n`th occurrence, counting from 0.
+    ///
+    /// Monthly and yearly schedules stay on the day of month of [`Schedule::from`],
+    /// falling back to the last day of shorter months,
+    /// so the 31st is followed by the 28th of February and then the 31st of March.
+    pub fn nth(&self, n: u32) -> Option<Date> {
+        let from = self.from.0;
+        let months = match self.period {
+            Period::Day => return from.checked_add(Duration::days(n.into())).map(Date),
+            Period::Week => return from.checked_add(Duration::weeks(n.into())).map(Date),
+            Period::Month => i64::from(n),
+            Period::Year => i64::from(n) * 12,
+        };
+
+        let index = i64::from(from.year()) * 12 + i64::from(u8::from(from.month())) - 1 + months;
+        let year = i32::try_from(index.div_euclid(12)).ok()?;
+        let month = Month::try_from(u8::try_from(index.rem_euclid(12) + 1).ok()?).ok()?;
+        let day = from.day().min(month.length(year));
+        time::Date::from_calendar_date(year, month, day)
+            .ok()
+            .map(Date)
+    }
+
+    /// Every occurrence up to and including `end`, in order.
+    pub fn until(self, end: Date) -> impl Iterator<Item = Date> {
+        let end = self.until.map_or(end, |until| until.min(end));
+        (0..)
+            .map_while(move |n| self.nth(n))
+            .take_while(move |date| *date <= end)
+    }
+}
+
+impl Runtime {
+    /// Repeats the body of an `every` statement,
+    /// each statement dated to the occurrence.
+    ///
+    /// Occurrences go up to the `until` of the statement
+    /// or the [horizon][Runtime::set_horizon], whichever is earlier.
+    pub fn unroll<'tok>(&self, stmt: Stmt<'tok>) -> Result<Script<'tok>, error::Repr> {
+        let Stmt { args, body, .. } = stmt;
+        let schedule = self.repr_schedule(args)?;
+        let end = match (schedule.until, self.horizon) {
+            (Some(until), Some(horizon)) => until.min(horizon),
+            (until, horizon) => until.or(horizon).ok_or(error::Recurring::Endless)?,
+        };
+
+        let body = body.unwrap_or(Script(Vec::new())).0;
+        for stmt in &body {
+            let stmt = &stmt.node;
+            if !matches!(
+                stmt.cmd,
+                ast::Command::Pay | ast::Command::Deliver | ast::Command::Receipt
+            ) {
+                return Err(error::Recurring::Unrepeatable(stmt.cmd.clone()).into());
+            }
+
+            let lines = match (&stmt.block, &stmt.cmd) {
+                (Some(block), ast::Command::Pay | ast::Command::Deliver) => {
+                    block.0.iter().collect()
+                }
+                _ => Vec::new(),
+            };
+            if std::iter::once(&stmt.args).chain(lines).any(is_dated) {
+                return Err(error::Recurring::Dated.into());
+            }
+        }
+
+        let script = schedule
+            .until(end)
+            .flat_map(|date| {
+                body.iter().cloned().map(move |mut stmt| {
+                    stmt.node.args.0.push(Arg::named("date", Value::Date(date)));
+                    stmt
+                })
+            })
+            .collect();
+        Ok(Script(script))
+    }
+
+    /// Runs the body of an `every` statement once per occurrence.
+    pub(in crate::runtime) fn every(&mut self, stmt: Stmt) -> Result<(), error::Run> {
+        let script = self.unroll(stmt)?;
+        self.run(script)
+            .map_err(|err| error::Run::Every(Box::new(err)))
+    }
+}
+
+/// Whether `date` is already given, one way or another.
+fn is_dated(args: &Args) -> bool {
+    args.0.iter().any(|arg| match arg {
+        Arg::Named { key, .. } => key.0 == "date",
+        Arg::Pos(Value::Name(ast::Name(Ident(key)))) => *key == "date",
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Runtime, Script, eval,
+        ext::{Date, Integer},
+        runtime::cmd,
+    };
+
+    use super::*;
+
+    fn date(src: &str) -> Date {
+        src.parse().unwrap()
+    }
+
+    #[test]
+    fn schedule() {
+        let dates = |period, from, until| {
+            let schedule = Schedule {
+                period,
+                from: date(from),
+                until: None,
+            };
+            schedule
+                .until(date(until))
+                .map(|date| date.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            dates(Period::Month, "2024-01-31", "2024-04-30"),
+            ["2024-01-31", "2024-02-29", "2024-03-31", "2024-04-30"]
+        );
+        assert_eq!(
+            dates(Period::Week, "2024-12-25", "2025-01-08"),
+            ["2024-12-25", "2025-01-01", "2025-01-08"]
+        );
+        assert_eq!(
+            dates(Period::Year, "2024-02-29", "2026-12-31"),
+            ["2024-02-29", "2025-02-28", "2026-02-28"]
+        );
+        assert_eq!(dates(Period::Day, "2025-01-01", "2024-12-31"), [""; 0]);
+    }
+
+    #[test]
+    fn rent() {
+        let rt = eval(
+            "
+            create entity { A; Landlord }
+            every month from=2025-01-01 until=2025-12-31 {
+                pay 450€ from A to Landlord
+            }
+            assert balance from=A to=Landlord = 5400€
+            ",
+        )
+        .unwrap();
+        assert_eq!(rt.ledger.len(), 12);
+
+        let balance = |as_of: &str| {
+            let between = rt.get_dir("A", "Landlord").unwrap();
+            rt.balance(cmd::Balance {
+                between,
+                as_of: Some(date(as_of)),
+            })
+            .to_string()
+        };
+        assert_eq!(
+            balance("2024-12-31"),
+            "entity A owes 0.00 € to entity Landlord"
+        );
+        assert_eq!(
+            balance("2025-03-15"),
+            "entity Landlord owes 1350.00 € to entity A"
+        );
+    }
+
+    #[test]
+    fn horizon() {
+        let source = "
+            create entity { A; B }
+            every week from=2025-01-01 { pay 5€ from A to B }
+        ";
+
+        // would never end
+        assert!(eval(source).is_err());
+
+        let mut rt = Runtime::new();
+        rt.set_horizon(Some(date("2025-01-14")));
+        let script = Script::parse(source).unwrap();
+        rt.run(script).unwrap();
+        let dir = rt.get_dir("A", "B").unwrap();
+        assert_eq!(rt.state().balance(dir).0, Integer::from(1000));
+
+        for invalid in [
+            "every month from=2025-01-01 until=2025-02-01 { pay 1€ from A to B date=2025-01-01 }",
+            "every month from=2025-01-01 until=2025-02-01 { balance from A to B }",
+            "every fortnight from=2025-01-01 until=2025-02-01 { pay 1€ from A to B }",
+        ] {
+            assert!(
+                eval(format!("create entity {{ A; B }}\n{invalid}")).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    aux::Owned,
+    runtime::{
+        error,
+        schema::{self, SCHEMAS, Schema},
+    },
+    syntax::ast,
+};
+
+/// Explains commands from the same [schemas][Schema]
+/// their arguments are bound with.
+#[derive(Owned!)]
+pub enum Help {
+    /// Every command in every form, with what it does.
+    Overview,
+    /// Everything about these forms of one command,
+    /// including its [lines][Schema::line].
+    Forms(Vec<&'static Schema>),
+}
+
+impl Help {
+    /// On every form of `command`, or only on the one called `form`.
+    pub fn on(command: &ast::Command, form: Option<&str>) -> Result<Self, error::Arg> {
+        let all = SCHEMAS.iter().filter(|schema| schema.command == *command);
+        let forms: Vec<_> = all
+            .clone()
+            .filter(|schema| form.is_none() || schema.form == form || schema.line)
+            .collect();
+
+        if forms.iter().all(|schema| schema.line) {
+            let words: Vec<_> = all.filter_map(|schema| schema.form).collect();
+            return Err(match words[..] {
+                [] => error::Arg::Unexpected(form.unwrap_or_default().to_owned()),
+                _ => error::Arg::Type {
+                    key: "form".to_owned(),
+                    expected: schema::one_of(&words),
+                },
+            });
+        }
+        Ok(Help::Forms(forms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{runtime::cmd::Command, syntax::ast::Script};
+
+    use super::*;
+
+    #[test]
+    fn examples() {
+        // they're all as valid as the schema they're shown with says
+        for schema in SCHEMAS {
+            for example in schema.examples {
+                if schema.line {
+                    let src = format!("{} {{ {example} }}", schema.command);
+                    let script = Script::parse(&src).unwrap();
+                    let block = script.0[0].node.block.clone().unwrap();
+                    schema.bind(block.0[0].clone()).unwrap();
+                    continue;
+                }
+
+                let script = Script::parse(example)
+                    .into_result()
+                    .unwrap_or_else(|err| panic!("{example}: {err:?}"));
+                let stmt = script.0[0].node.clone();
+
+                assert_eq!(stmt.cmd, schema.command, "{example}");
+                for line in stmt.block.iter().flat_map(|block| &block.0) {
+                    if stmt.cmd == ast::Command::Receipt {
+                        schema::RECEIPT_LINE.bind(line.clone()).unwrap();
+                    }
+                }
+                for stmt in stmt.unblock() {
+                    let (bound, _) = schema::bind(&stmt.cmd, stmt.args)
+                        .unwrap_or_else(|err| panic!("{example}: {err}"));
+                    assert_eq!(bound, schema, "{example}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn topics() {
+        let rt = crate::Runtime::new();
+        let help = |src| {
+            let script = Script::parse(src).unwrap();
+            rt.repr(script.0[0].node.clone())
+        };
+
+        assert_eq!(help("help").unwrap(), Command::Help(Help::Overview));
+        let Command::Help(Help::Forms(forms)) = help("help receipt").unwrap() else {
+            panic!("expected forms");
+        };
+        assert_eq!(forms.len(), 2);
+        let Command::Help(Help::Forms(forms)) = help("help create concept").unwrap() else {
+            panic!("expected forms");
+        };
+        assert_eq!(forms[0].form, Some("concept"));
+
+        let Err(error::Repr::Usage(usage)) = help("help delivr") else {
+            panic!("expected a usage error");
+        };
+        assert_eq!(usage.suggestion.as_deref(), Some("deliver"));
+        assert!(help("help create spending").is_err());
+        assert!(help("help pay entity").is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    Runtime,
+    aux::Owned,
+    runtime::model::{Entity, Transfer},
+};
+
+use super::Name;
+
+/// Lists transfers in the ledger in the order they happened,
+/// including voided ones.
+#[derive(Owned!)]
+pub struct History {
+    /// Only transfers from or to this entity.
+    pub of: Option<Entity>,
+    /// Only transfers with this among their [tags][Transfer::tags].
+    pub tag: Option<Name>,
+}
+
+/// One transfer in the [`History`].
+#[derive(Owned!)]
+pub struct Entry {
+    /// Its position in the ledger counting from 1,
+    /// as understood by [`super::Void`].
+    pub number: usize,
+    pub transfer: Transfer,
+}
+
+impl Runtime {
+    pub fn history(&self, History { of, tag }: &History) -> Vec<Entry> {
+        self.ledger
+            .iter()
+            .enumerate()
+            .filter(|(_, transfer)| {
+                let who = transfer.who();
+                of.as_ref()
+                    .is_none_or(|of| who.source() == of || who.target() == of)
+            })
+            .filter(|(_, transfer)| tag.as_ref().is_none_or(|tag| transfer.is_tagged(tag)))
+            .map(|(idx, transfer)| Entry {
+                number: idx + 1,
+                transfer: transfer.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval;
+
+    use super::*;
+
+    #[test]
+    fn filter() {
+        let rt = eval(
+            "
+            create entity { A; B; C }
+            create concept Mate price=1.50€ tags=drinks
+            pay 5€ from=A to=B tags=rent,flat memo=\"for March\"
+            deliver Mate from=B to=C date=2025-03-02 as=mate
+            pay 1€ from=C to=A tags=flat
+            void mate
+            ",
+        )
+        .unwrap();
+
+        let history = |of: Option<&str>, tag: Option<&str>| {
+            rt.history(&History {
+                of: of.map(|of| rt.get_entity(of).unwrap().clone()),
+                tag: tag.map(str::to_owned),
+            })
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            history(None, None),
+            [
+                "1. entity A paid 5.00 € to entity B, tagged rent, flat (for March)",
+                "2. 2025-03-02 entity B delivered concept Mate to entity C for 1.50 € as mate, tagged drinks [voided]",
+                "3. entity C paid 1.00 € to entity A, tagged flat",
+            ]
+        );
+        assert_eq!(history(Some("B"), None).len(), 2);
+        assert_eq!(history(None, Some("drinks")).len(), 1);
+        assert_eq!(history(Some("C"), Some("flat")).len(), 1);
+    }
+}
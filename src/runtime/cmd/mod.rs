@@ -1,38 +1,93 @@
 //! Structurally interact with, modify and do things.
 
+pub mod alias;
+pub mod assert;
 pub mod balance;
+pub mod budget;
 pub mod create;
 pub mod deliver;
+pub mod every;
+pub mod help;
+pub mod history;
 pub mod pay;
+pub mod receipt;
+pub mod report;
+pub mod tag;
+pub mod void;
+pub mod whatif;
 
+pub use alias::{Alias, Rename};
+pub use assert::Assert;
 pub use balance::Balance;
+pub use budget::{Budget, Usage};
 pub use create::Create;
 pub use deliver::Deliver;
+pub use every::Schedule;
+pub use help::Help;
+pub use history::History;
 pub use pay::Pay;
+pub use receipt::Receipt;
+pub use report::Report;
+pub use tag::Tag;
+pub use void::Void;
+pub use whatif::Change;
 
 use crate::{
     aux::Owned,
     ext::{Gtin, Money},
 };
 
-use super::{Runtime, model};
+use super::{Runtime, error, model, query};
 
 // TODO: do this via dynamic dispatch so the cases don't have to be matched manually?
 
 impl Runtime {
     /// Performs one single command.
     ///
-    /// This can never fail, any [`Command`]
+    /// Any [`Command`]
     /// ***that is constructed from this instance***
     /// is valid to run at any point after construction!
-    pub fn fulfil(&mut self, cmd: Command) {
+    /// The only ways this can fail are an [`Assert`] not holding,
+    /// a [`Command::Commit`] without open transaction
+    /// or the [storage][super::storage] failing.
+    pub fn fulfil(&mut self, cmd: Command) -> Result<(), error::Fulfil> {
         use Command as C;
         match cmd {
-            C::Create(cmd) => self.create(cmd),
-            C::Pay(cmd) => self.pay(cmd),
-            C::Deliver(cmd) => self.deliver(cmd),
+            C::Create(cmd) => self.create(cmd)?,
+            C::Pay(cmd) => self.pay(cmd)?,
+            C::Deliver(cmd) => self.deliver(cmd)?,
+            C::Receipt(cmd) => self.receipt(cmd)?,
+            C::Void(cmd) => self.void(cmd)?,
+            C::Alias(cmd) => self.alias(cmd)?,
+            C::Rename(cmd) => self.rename(cmd)?,
+            C::Tag(cmd) => self.tag(cmd)?,
             C::Balance(cmd) => println!("{}", self.balance(cmd)),
+            C::Budget(cmd) => self.budget(cmd)?,
+            C::BudgetReport => {
+                for usage in self.budget_report() {
+                    println!("{usage}");
+                }
+            }
+            C::Report(cmd) => {
+                let table = self.report(cmd.spending);
+                match cmd.format {
+                    report::Format::Table => print!("{table}"),
+                    report::Format::Csv => print!("{}", table.to_csv()),
+                }
+            }
+            C::History(cmd) => {
+                for entry in self.history(&cmd) {
+                    println!("{entry}");
+                }
+            }
+            C::Select(plan) => print!("{}", self.select(&plan)),
+            C::Help(help) => print!("{help}"),
+            C::Assert(cmd) => self.assert(cmd)?,
+            C::Begin => self.begin()?,
+            C::Commit => self.commit()?,
         }
+
+        Ok(())
     }
 }
 
@@ -42,7 +97,41 @@ pub enum Command {
     Create(Create),
     Pay(Pay),
     Deliver(Deliver),
+    Receipt(Receipt),
+    Void(Void),
+    Alias(Alias),
+    Rename(Rename),
+    Tag(Tag),
     Balance(Balance),
+    Budget(Budget),
+    /// See [`Runtime::budget_report`].
+    BudgetReport,
+    Report(Report),
+    History(History),
+    /// See [`Runtime::select`].
+    Select(query::Plan),
+    Help(Help),
+    Assert(Assert),
+    /// See [`Runtime::begin`].
+    Begin,
+    /// See [`Runtime::commit`].
+    Commit,
+}
+
+impl Command {
+    /// Whether this only reads and prints what it found,
+    /// changing nothing.
+    pub fn is_query(&self) -> bool {
+        matches!(
+            self,
+            Command::Balance(_)
+                | Command::BudgetReport
+                | Command::Report(_)
+                | Command::History(_)
+                | Command::Select(_)
+                | Command::Help(_)
+        )
+    }
 }
 
 /// A [`model::Entity`] except that it might not exist yet.
@@ -57,6 +146,8 @@ pub struct Concept {
     pub name: Name,
     pub default_price: Option<Money>,
     pub gtin: Option<Gtin>,
+    pub category: Option<Name>,
+    pub tags: Vec<Name>,
 }
 
 /// A [`model::Object`] except that it might not exist yet.
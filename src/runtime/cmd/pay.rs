@@ -1,39 +1,63 @@
-use std::collections::btree_map::Entry;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     Runtime,
     aux::Owned,
-    ext::{Balance, Money},
+    ext::{Balance, Date, Money},
+    runtime::error,
 };
 
-use super::model::Dir;
+use super::{
+    Name,
+    model::{Dir, Transfer},
+};
 
 /// Move money from *source* to *target*.
 ///
-/// This modifies the [`super::Balance`] ***negatively***!
-/// The balance denotes how much *source* owes the *target*,
-/// which is how much *source* would need to pay *target*
+/// This modifies the [`super::Balance`] ***positively***!
+/// The balance from *source* to *target* denotes how much *target* owes *source*,
+/// which is how much *target* would need to pay *source*
 /// to be on 0 again.
 ///
 /// For example, `A` paying 1€ to `B` means
-/// the balance from `A` to `B` will be -1€.
-#[derive(Owned!)]
+/// the balance from `A` to `B` will be 1€.
+#[derive(Owned!, Serialize, Deserialize)]
 pub struct Pay {
     pub amount: Money,
     pub who: Dir,
+    #[serde(default)]
+    pub date: Option<Date>,
+    /// Given with `as`, unique among all transfers.
+    #[serde(default)]
+    pub label: Option<Name>,
+    /// Free-form note, e.g. what it was for.
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Given with `tags`, e.g. to find it again in the history.
+    #[serde(default)]
+    pub tags: Vec<Name>,
+    /// Whether it was taken back by [`super::Void`].
+    #[serde(default)]
+    pub voided: bool,
 }
 
 impl Runtime {
-    pub fn pay(&mut self, cmd: Pay) {
-        let mut value: Balance = cmd.amount.into();
-        let key = value.take_order(cmd.who);
+    pub fn pay(&mut self, cmd: Pay) -> Result<(), error::Storage> {
+        let transfer = Transfer::Pay(cmd);
+        self.shift(transfer.shifted(), transfer.who().clone())?;
+        self.store(|s| s.transfer(&transfer))
+    }
+
+    /// Moves `amount` from `who.source` to `who.target` in the balances
+    /// without recording anything in the ledger.
+    pub(super) fn shift(&mut self, amount: Money, who: Dir) -> Result<(), error::Storage> {
+        let mut value: Balance = amount.into();
+        let key = value.take_order(who);
 
-        match self.state.balances.entry(key) {
-            Entry::Occupied(mut entry) => *entry.get_mut() += value,
-            Entry::Vacant(entry) => {
-                entry.insert(value);
-            }
+        if let Some(previous) = self.state.balances.get(&key) {
+            value += previous.clone();
         }
+        self.store(|s| s.balance(&key, &value))
     }
 }
 
@@ -0,0 +1,235 @@
+use crate::{
+    Runtime,
+    aux::Owned,
+    ext::{Date, Money, Natural},
+    runtime::{
+        error,
+        model::{Dir, Entity, Product, Rounding, Split},
+    },
+};
+
+use super::Name;
+
+/// One purchase paid by one entity,
+/// consisting of many lines consumed by different entities each.
+///
+/// This is lowered into deliveries:
+///
+/// 1. If `at` is set,
+///     `payer` pays `at` the total and
+///     `at` delivers every line to `payer`.
+/// 2. Every line is delivered from `payer` to all its consumers,
+///     each of them paying an equal part, rounded down to the cent.
+///     If `payer` is a consumer itself,
+///     its part is just not delivered.
+///     Whatever is left over after rounding is paid by `payer`.
+///     These deliveries have no units,
+///     so all bought units count for the inventory of `payer`.
+#[derive(Owned!)]
+pub struct Receipt {
+    pub payer: Entity,
+    /// From `payer` to where the purchase was made.
+    pub at: Option<Dir>,
+    pub lines: Vec<Line>,
+    /// Given to all transfers this is lowered into.
+    pub date: Option<Date>,
+    /// Given to all transfers this is lowered into,
+    /// so voiding it voids all of them.
+    pub label: Option<Name>,
+    /// Given to all transfers this is lowered into.
+    pub memo: Option<String>,
+    /// Given to all transfers this is lowered into.
+    pub tags: Vec<Name>,
+}
+
+/// `qty` times `product` for `price` each, consumed by `consumers`.
+#[derive(Owned!)]
+pub struct Line {
+    pub product: Product,
+    pub qty: Natural,
+    pub price: Money,
+    /// Without duplicates.
+    pub consumers: Vec<Entity>,
+}
+
+impl Line {
+    /// How much this line costs in total.
+    pub fn total(&self) -> Money {
+        Money(self.price.0.clone() * self.qty.clone())
+    }
+}
+
+impl Receipt {
+    /// How much all lines cost together.
+    pub fn total(&self) -> Money {
+        let mut total = Money(Natural::ZERO);
+        for line in &self.lines {
+            total += line.total();
+        }
+        total
+    }
+}
+
+impl Runtime {
+    pub fn receipt(&mut self, receipt: Receipt) -> Result<(), error::Storage> {
+        let total = receipt.total();
+        let Receipt {
+            payer,
+            at,
+            lines,
+            date,
+            label,
+            memo,
+            tags,
+        } = receipt;
+
+        if let Some(at) = &at {
+            self.pay(super::Pay {
+                amount: total,
+                who: at.clone(),
+                date,
+                label: label.clone(),
+                memo: memo.clone(),
+                tags: tags.clone(),
+                voided: false,
+            })?;
+        }
+
+        for line in lines {
+            let price = line.total();
+
+            if let Some(at) = &at {
+                let mut who = at.clone();
+                who.flip();
+                self.deliver(super::Deliver {
+                    who,
+                    product: line.product.clone(),
+                    price: price.clone(),
+                    split: Split::default(),
+                    rounding: Rounding::default(),
+                    qty: line.qty.clone(),
+                    date,
+                    label: label.clone(),
+                    memo: memo.clone(),
+                    tags: tags.clone(),
+                    voided: false,
+                })?;
+            }
+
+            // the payer pays their own part, so it's not delivered
+            // but still counts for the share
+            if line.consumers.is_empty() {
+                continue;
+            }
+            let share = Money(price.0 / line.consumers.len());
+            for consumer in line.consumers.into_iter().filter(|c| *c != payer) {
+                self.deliver(super::Deliver {
+                    who: Dir::new(payer.clone(), consumer).unwrap(),
+                    product: line.product.clone(),
+                    price: share.clone(),
+                    split: Split::default(),
+                    rounding: Rounding::default(),
+                    // only the price is shared, the units stay with the payer
+                    qty: Natural::ZERO,
+                    date,
+                    label: label.clone(),
+                    memo: memo.clone(),
+                    tags: tags.clone(),
+                    voided: false,
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval, ext::Integer};
+
+    #[test]
+    fn lines() {
+        let rt = eval(
+            "
+            create entity { Store; A; B; C }
+            create concept Mate price=1.50€
+            create concept Pizza
+
+            receipt from=A at=Store total=13.50€ {
+                Mate 3 for B C
+                Pizza 9€ for A B C
+            }
+            ",
+        )
+        .unwrap();
+
+        let bal = |source, target| rt.state().balance(rt.get_dir(source, target).unwrap()).0;
+
+        // 4.50€ / 2 + 9€ / 3
+        assert_eq!(bal("A", "B"), Integer::from(225 + 300));
+        assert_eq!(bal("A", "C"), Integer::from(225 + 300));
+        assert_eq!(bal("A", "Store"), Integer::ZERO);
+    }
+
+    #[test]
+    fn rounding() {
+        let rt = eval(
+            "
+            create entity { A; B; C; D; E; F; G }
+            create concept X
+
+            receipt from=A {
+                X 1€ for A B C D E F G
+                X 10ct for A B C
+            }
+            ",
+        )
+        .unwrap();
+
+        // 1€ / 7 = 14ct and 10ct / 3 = 3ct,
+        // A pays what's left over
+        for (other, share) in [("B", 14 + 3), ("C", 14 + 3), ("G", 14)] {
+            let dir = rt.get_dir("A", other).unwrap();
+            assert_eq!(rt.state().balance(dir).0, Integer::from(share));
+        }
+        assert_eq!(
+            rt.state().net(rt.get_entity("A").unwrap()).0,
+            Integer::from(-(6 * 14 + 2 * 3))
+        );
+    }
+
+    #[test]
+    fn total_mismatch() {
+        let err = eval(
+            "
+            create entity { A; B }
+            create concept Mate price=1.50€
+
+            receipt from=A total=3€ {
+                Mate for B
+            }
+            ",
+        )
+        .unwrap_err();
+
+        assert!(format!("{err:?}").contains("off by 1.50 €"));
+    }
+
+    #[test]
+    fn bought_from_themselves() {
+        let err = eval(
+            "
+            create entity A
+            create concept Mate price=1.50€
+
+            receipt from=A at=A {
+                Mate
+            }
+            ",
+        )
+        .unwrap_err();
+
+        assert!(format!("{err:?}").contains("are the same"));
+    }
+}
@@ -0,0 +1,380 @@
+use std::str::FromStr;
+
+use crate::{
+    Map, Runtime,
+    aux::{Owned, Stack},
+    ext::{Balance, Date, Money, Natural, table::Table},
+    runtime::model::{Concept, Entity, Transfer},
+};
+
+use super::{Name, Schedule, every::Period};
+
+/// Sums up the ledger from a larger point of view.
+#[derive(Owned!)]
+pub struct Report {
+    pub spending: Spending,
+    pub format: Format,
+}
+
+/// What a [`Report`] sums up.
+#[derive(Owned!)]
+pub enum Spending {
+    /// See [`Runtime::spending_by`].
+    By(Grouping),
+    /// See [`Runtime::activity`].
+    Per(Periodic),
+}
+
+/// What [`Spending::By`] sums up by.
+#[derive(Stack!)]
+pub enum Grouping {
+    /// Tags of the delivery or its concept.
+    Tag,
+    /// The category of the delivered concept.
+    Category,
+}
+
+impl FromStr for Grouping {
+    type Err = ();
+
+    /// `tag` or `category`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "tag" => Self::Tag,
+            "category" => Self::Category,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// What one entity did in every period between two days.
+#[derive(Owned!)]
+pub struct Periodic {
+    pub of: Entity,
+    pub per: Period,
+    /// The first and last day to count transfers on, both included.
+    /// If not given, from the first to the last dated transfer of [`Periodic::of`].
+    pub during: Option<(Date, Date)>,
+}
+
+/// How many periods [`Periodic::during`] may span at most,
+/// so a typo in a year can't make a report take forever.
+pub const MAX_PERIODS: usize = 10_000;
+
+impl Periodic {
+    /// Whether [`Periodic::during`] spans at most [`MAX_PERIODS`] periods.
+    pub fn is_bounded(&self) -> bool {
+        let Some((from, to)) = self.during else {
+            return true;
+        };
+        let schedule = Schedule {
+            period: self.per,
+            from: self.per.start(from),
+            until: None,
+        };
+        schedule.until(to).nth(MAX_PERIODS).is_none()
+    }
+}
+
+/// How a [`Report`] is emitted.
+#[derive(Stack!, Default)]
+pub enum Format {
+    /// Aligned for humans.
+    #[default]
+    Table,
+    /// For spreadsheets.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    /// `table` or `csv`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "table" => Self::Table,
+            "csv" => Self::Csv,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// How much one entity spent on one tag or category.
+#[derive(Owned!)]
+pub struct Spent {
+    pub group: Name,
+    pub of: Entity,
+    pub amount: Money,
+}
+
+/// What one entity did in one period.
+#[derive(Owned!)]
+pub struct Activity {
+    /// The first day of the period.
+    pub during: Date,
+    /// Money it paid to others.
+    pub paid: Money,
+    /// Money others paid to it.
+    pub received: Money,
+    /// Value it is expected to pay for deliveries to it.
+    pub got: Money,
+    /// Value others are expected to pay for deliveries from it.
+    pub gave: Money,
+}
+
+impl Activity {
+    fn new(during: Date) -> Self {
+        let zero = || Money(Natural::ZERO);
+        Self {
+            during,
+            paid: zero(),
+            received: zero(),
+            got: zero(),
+            gave: zero(),
+        }
+    }
+
+    /// How much more the entity owes all others together afterwards,
+    /// like [`State::net`][crate::runtime::State::net].
+    pub fn net(&self) -> Balance {
+        Balance::from(self.received.clone()) + self.got.clone()
+            - self.paid.clone()
+            - self.gave.clone()
+    }
+}
+
+impl Runtime {
+    /// What each entity spent on each tag or category,
+    /// sorted by that and then by entity.
+    ///
+    /// Like for [budgets][super::Budget],
+    /// spending is what an entity is expected to pay
+    /// for the deliveries it receives,
+    /// but regardless of their date.
+    /// A delivery with several tags counts towards each of them,
+    /// one without any towards none.
+    pub fn spending_by(&self, by: Grouping) -> Vec<Spent> {
+        let mut spent: Map<(Name, Entity), Money> = Map::new();
+        for transfer in &self.ledger {
+            let Transfer::Deliver(deliver) = transfer else {
+                continue;
+            };
+            if deliver.voided {
+                continue;
+            }
+
+            let groups = match by {
+                Grouping::Tag => transfer.tags(),
+                Grouping::Category => deliver
+                    .product
+                    .concept()
+                    .and_then(Concept::category)
+                    .into_iter()
+                    .collect(),
+            };
+            for group in groups {
+                *spent
+                    .entry((group.to_owned(), deliver.who.target().clone()))
+                    .or_insert(Money(Natural::ZERO)) += transfer.shifted();
+            }
+        }
+
+        spent
+            .into_iter()
+            .map(|((group, of), amount)| Spent { group, of, amount })
+            .collect()
+    }
+
+    /// What `of` paid, received and had delivered in every period,
+    /// in order and including periods where nothing happened.
+    ///
+    /// Only dated transfers that weren't voided count,
+    /// since the others don't belong to any period.
+    pub fn activity(&self, Periodic { of, per, during }: &Periodic) -> Vec<Activity> {
+        let involved = self
+            .ledger
+            .iter()
+            .filter(|transfer| {
+                let who = transfer.who();
+                !transfer.is_voided() && (who.source() == of || who.target() == of)
+            })
+            .filter_map(|transfer| Some((transfer.date()?, transfer)));
+
+        let (from, to) = match during {
+            Some(during) => *during,
+            None => {
+                let dates = involved.clone().map(|(date, _)| date);
+                match (dates.clone().min(), dates.max()) {
+                    (Some(from), Some(to)) => (from, to),
+                    _ => return Vec::new(),
+                }
+            }
+        };
+
+        let schedule = Schedule {
+            period: *per,
+            from: per.start(from),
+            until: None,
+        };
+        let mut activity: Map<Date, Activity> = schedule
+            .until(to)
+            .map(|start| (start, Activity::new(start)))
+            .collect();
+
+        for (date, transfer) in involved {
+            if date < from || date > to {
+                continue;
+            }
+            let Some(period) = activity.get_mut(&per.start(date)) else {
+                continue;
+            };
+
+            let outgoing = transfer.who().source() == of;
+            let amount = transfer.shifted();
+            let sum = match (transfer, outgoing) {
+                (Transfer::Pay(_), true) => &mut period.paid,
+                (Transfer::Pay(_), false) => &mut period.received,
+                (Transfer::Deliver(_), true) => &mut period.gave,
+                (Transfer::Deliver(_), false) => &mut period.got,
+            };
+            *sum += amount;
+        }
+
+        activity.into_values().collect()
+    }
+
+    pub fn report(&self, spending: Spending) -> Table {
+        match spending {
+            Spending::By(by) => {
+                let group = match by {
+                    Grouping::Tag => "tag",
+                    Grouping::Category => "category",
+                };
+                let mut table = Table::new([group, "entity", "spent"]);
+                for Spent { group, of, amount } in self.spending_by(by) {
+                    table.push([group, of.name().to_owned(), amount.to_string()]);
+                }
+                table
+            }
+            Spending::Per(periodic) => {
+                let mut table = Table::new(["period", "paid", "received", "got", "gave", "net"]);
+                for activity in self.activity(&periodic) {
+                    table.push([
+                        periodic.per.label(activity.during),
+                        activity.paid.to_string(),
+                        activity.received.to_string(),
+                        activity.got.to_string(),
+                        activity.gave.to_string(),
+                        activity.net().to_string(),
+                    ]);
+                }
+                table
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval;
+
+    use super::*;
+
+    #[test]
+    fn spending() {
+        let rt = eval(
+            "
+            create entity { A; B; Store }
+            create concept Mate price=1.50€ tags=drinks,bio category=kiosk
+            create concept Soap price=2€
+            deliver Mate from=Store to=A
+            deliver Mate from=Store to=B split=1:1
+            deliver Soap from=Store to=A tags=cleaning
+            deliver Soap from=Store to=B
+            tag Soap cleaning
+            deliver Soap from=Store to=B tags=cleaning as=twice
+            void twice
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            rt.report(Spending::By(Grouping::Tag)).to_string(),
+            concat!(
+                "tag       entity  spent\n",
+                "bio       A       1.50 €\n",
+                "bio       B       0.75 €\n",
+                "cleaning  A       2.00 €\n",
+                "cleaning  B       2.00 €\n",
+                "drinks    A       1.50 €\n",
+                "drinks    B       0.75 €\n",
+            )
+        );
+
+        let by_category = rt.spending_by(Grouping::Category);
+        assert_eq!(by_category.len(), 2);
+        assert_eq!(by_category[0].group, "kiosk");
+    }
+
+    #[test]
+    fn periodic() {
+        let rt = eval(
+            "
+            create entity { A; B; Store }
+            create concept Milk price=1.20€
+            pay 450€ from=A to=B date=2025-01-01
+            deliver Milk from=Store to=A date=2025-01-15
+            pay 20€ from=B to=A date=2025-03-31
+            deliver Milk price=3€ from=A to=B split=1:2 date=2025-03-02
+            # neither dated nor voided ones count
+            pay 1€ from=A to=B
+            pay 1€ from=A to=B date=2025-03-01 as=oops
+            void oops
+            ",
+        )
+        .unwrap();
+
+        let report = |during| {
+            let periodic = Periodic {
+                of: rt.get_entity("A").unwrap().clone(),
+                per: Period::Month,
+                during,
+            };
+            rt.report(Spending::Per(periodic)).to_string()
+        };
+
+        assert_eq!(
+            report(None),
+            concat!(
+                "period   paid      received  got     gave    net\n",
+                "2025-01  450.00 €  0.00 €    1.20 €  0.00 €  -448.80 €\n",
+                "2025-02  0.00 €    0.00 €    0.00 €  0.00 €  0.00 €\n",
+                "2025-03  0.00 €    20.00 €   0.00 €  2.00 €  18.00 €\n",
+            )
+        );
+
+        let date = |src: &str| src.parse().unwrap();
+        assert_eq!(
+            report(Some((date("2025-01-10"), date("2025-02-28")))),
+            concat!(
+                "period   paid    received  got     gave    net\n",
+                "2025-01  0.00 €  0.00 €    1.20 €  0.00 €  1.20 €\n",
+                "2025-02  0.00 €  0.00 €    0.00 €  0.00 €  0.00 €\n",
+            )
+        );
+    }
+
+    #[test]
+    fn too_many_periods() {
+        let report = |during| {
+            eval(format!(
+                "create entity A
+                report spending per=day of=A during={during}"
+            ))
+        };
+
+        assert!(report("2025-01-01..2025-12-31").is_ok());
+        let err = report("0001-01-01..9999-12-31").unwrap_err();
+        assert!(format!("{err:?}").contains("at most 10000 periods long"));
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{
+    Runtime,
+    aux::Owned,
+    runtime::{error, model::Concept},
+};
+
+use super::Name;
+
+/// Adds a tag to an existing concept,
+/// e.g. to group it with others in a [report][super::Report].
+///
+/// Like a rename, this also applies to past deliveries of it.
+/// Tagging it again with a tag it already has changes nothing.
+#[derive(Owned!)]
+pub struct Tag {
+    pub concept: Concept,
+    pub tag: Name,
+}
+
+impl Runtime {
+    pub fn tag(&mut self, cmd: Tag) -> Result<(), error::Storage> {
+        if cmd.concept.tags().contains(&cmd.tag) {
+            return Ok(());
+        }
+        self.store(|s| s.tag(&cmd.concept, &cmd.tag))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::{
+    Runtime,
+    aux::Owned,
+    runtime::{error, model::Transfer},
+};
+
+/// Takes back transfers as if they never happened,
+/// but keeps them in the [ledger][crate::runtime::State::ledger]
+/// marked as voided.
+#[derive(Owned!)]
+pub struct Void {
+    /// Positions in the ledger, none of them voided yet.
+    pub transfers: Vec<usize>,
+}
+
+impl Runtime {
+    pub fn void(&mut self, cmd: Void) -> Result<(), error::Storage> {
+        for idx in cmd.transfers {
+            let transfer: &Transfer = &self.state.ledger[idx];
+            let amount = transfer.shifted();
+            let mut back = transfer.who().clone();
+            back.flip();
+
+            self.shift(amount, back)?;
+            self.store(|s| s.void(idx))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{eval, ext::Integer};
+
+    #[test]
+    fn label() {
+        let rt = eval(
+            "
+            create entity { A; B; C }
+            create concept Mate price=1.50€
+
+            pay 3€ from=A to=B as=coffee-0412
+            pay 1€ from=A to=B
+            receipt from=A as=groceries { Mate 2 for B C }
+            void coffee-0412
+            void groceries
+            ",
+        )
+        .unwrap();
+
+        let bal = |source, target| rt.state().balance(rt.get_dir(source, target).unwrap()).0;
+        assert_eq!(bal("A", "B"), Integer::from(100));
+        assert_eq!(bal("A", "C"), Integer::ZERO);
+
+        // still in the ledger, just marked
+        assert_eq!(rt.ledger.len(), 4);
+        assert_eq!(
+            rt.ledger.iter().map(|t| t.is_voided()).collect::<Vec<_>>(),
+            [true, false, true, true]
+        );
+        let mate = rt.get_concept("Mate").unwrap().clone();
+        let b = rt.get_entity("B").unwrap();
+        assert_eq!(
+            rt.inventory(b, &crate::runtime::model::Product::Concept(mate)),
+            Integer::ZERO
+        );
+    }
+
+    #[test]
+    fn position() {
+        let rt = eval(
+            "
+            create entity { A; B }
+            pay 3€ from=A to=B
+            pay 1€ from=A to=B
+            void 1
+            assert balance from=A to=B = 1€
+            ",
+        )
+        .unwrap();
+        assert!(rt.ledger[0].is_voided());
+    }
+
+    #[test]
+    fn invalid() {
+        let setup = "create entity { A; B }\npay 3€ from=A to=B as=x\n";
+        for (script, reason) in [
+            ("pay 1€ from=A to=B as=x", "already labelled"),
+            ("void y", "no transfer"),
+            ("void 2", "no transfer"),
+            ("void x\nvoid x", "already voided"),
+            ("void 1\nvoid x", "already voided"),
+        ] {
+            let err = eval(format!("{setup}{script}")).unwrap_err();
+            assert!(format!("{err:?}").contains(reason), "{script}: {err:?}");
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    Runtime,
+    aux::Owned,
+    ext::Balance,
+    runtime::{
+        error,
+        model::{Pair, State},
+        schema,
+    },
+    syntax::ast::{Script, Stmt},
+};
+
+/// How the balance between two entities changed in a [simulation][Runtime::simulate].
+///
+/// Both balances are how much [`Pair::a`] owes [`Pair::b`],
+/// like in [`State::balances`].
+#[derive(Owned!)]
+pub struct Change {
+    pub between: Pair,
+    pub before: Balance,
+    pub after: Balance,
+}
+
+impl Runtime {
+    /// Runs `script` on a copy of the current state
+    /// and returns how every balance that it touches would change.
+    ///
+    /// Nothing is kept, neither in the state nor in the storage.
+    pub fn simulate(&self, script: Script) -> Result<Vec<Change>, error::Located> {
+        let mut copy = Runtime::from(self.state.clone());
+        copy.horizon = self.horizon;
+        copy.run(script)?;
+        Ok(changes(&self.state, copy.state()))
+    }
+
+    /// Runs the body of a `whatif` statement
+    /// and prints how it'd change the balances.
+    pub(in crate::runtime) fn whatif(&self, stmt: Stmt) -> Result<(), error::Run> {
+        let Stmt {
+            cmd, args, body, ..
+        } = stmt;
+        schema::bind(&cmd, args).map_err(error::Repr::from)?;
+
+        let body = body.unwrap_or(Script(Vec::new()));
+        let changes = self
+            .simulate(body)
+            .map_err(|err| error::Run::Whatif(Box::new(err)))?;
+
+        if changes.is_empty() {
+            println!("no balance would change");
+        }
+        for change in changes {
+            println!("{change}");
+        }
+        Ok(())
+    }
+}
+
+/// Every balance that differs between `before` and `after`.
+fn changes(before: &State, after: &State) -> Vec<Change> {
+    let zero = Balance(0.into());
+    let between: BTreeSet<&Pair> = before
+        .balances
+        .keys()
+        .chain(after.balances.keys())
+        .collect();
+
+    between
+        .into_iter()
+        .map(|pair| Change {
+            between: pair.clone(),
+            before: before.balances.get(pair).unwrap_or(&zero).clone(),
+            after: after.balances.get(pair).unwrap_or(&zero).clone(),
+        })
+        .filter(|change| change.before != change.after)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Script, eval, ext::Integer};
+
+    #[test]
+    fn simulate() {
+        let rt = eval(
+            "
+            create entity { A; B; C }
+            pay 5€ from=A to=B
+            ",
+        )
+        .unwrap();
+        let before = rt.state().clone();
+
+        let script = Script::parse(
+            "
+            create entity D
+            pay 1€ from=B to=A
+            pay 2€ from=C to=D
+            pay 0€ from=A to=C
+            ",
+        )
+        .unwrap();
+        let changes = rt.simulate(script).unwrap();
+
+        // the 0€ payment doesn't change anything
+        let changed = |a: &str, b: &str| {
+            changes
+                .iter()
+                .find(|change| change.between.a().name() == a && change.between.b().name() == b)
+                .map(|change| (change.before.0.clone(), change.after.0.clone()))
+        };
+        assert_eq!(changes.len(), 2);
+        assert_eq!(
+            changed("A", "B"),
+            Some((Integer::from(-500), Integer::from(-400)))
+        );
+        assert_eq!(
+            changed("C", "D"),
+            Some((Integer::ZERO, Integer::from(-200)))
+        );
+
+        assert_eq!(*rt.state(), before);
+    }
+
+    #[test]
+    fn statement() {
+        let rt = eval(
+            "
+            create entity { A; B }
+            whatif {
+                pay 3€ from=A to=B
+                receipt from=A { }
+            }
+            assert balance from=A to=B = 0€
+            ",
+        )
+        .unwrap();
+        assert!(rt.ledger.is_empty());
+
+        let err = eval(
+            "
+            create entity A
+            whatif { pay 1€ from=A to=Nobody }
+            ",
+        )
+        .unwrap_err();
+        assert!(format!("{err:?}").contains("unknown entity Nobody"));
+    }
+}
@@ -2,13 +2,76 @@
 
 use thiserror::Error;
 
-use crate::{aux::Owned, ext::Gtin};
+use crate::{
+    aux::Owned,
+    ext::{Balance, Gtin, Money},
+    syntax::ast::{self, Span},
+};
 
 use super::{
     cmd::Name,
     model::{Entity, Product},
 };
 
+/// Running the statement at `span` failed.
+#[derive(Owned!, Error)]
+#[error("statement failed")]
+pub struct Located {
+    pub span: Span,
+    #[source]
+    pub source: Run,
+}
+
+#[derive(Owned!, Error)]
+pub enum Run {
+    #[error(transparent)]
+    Repr(#[from] Repr),
+    #[error(transparent)]
+    Fulfil(#[from] Fulfil),
+    /// A statement in a `whatif` block failed.
+    #[error("while simulating")]
+    Whatif(#[source] Box<Located>),
+    /// A statement in an `every` block failed.
+    #[error("while repeating")]
+    Every(#[source] Box<Located>),
+}
+
+/// A command couldn't be fulfilled.
+#[derive(Owned!, Error)]
+pub enum Fulfil {
+    #[error(transparent)]
+    Assert(#[from] AssertFailed),
+    #[error(transparent)]
+    Storage(#[from] Storage),
+    #[error(transparent)]
+    Transaction(#[from] Transaction),
+}
+
+/// A transaction couldn't be committed or aborted.
+#[derive(Owned!, Error)]
+pub enum Transaction {
+    #[error("there's no transaction to end, `begin` one first")]
+    NoneOpen,
+    #[error("transaction was never committed, it needs a `commit` after its `begin`")]
+    Unclosed,
+    #[error(transparent)]
+    Storage(#[from] Storage),
+}
+
+/// Writing into a [`super::storage::Storage`] failed.
+#[derive(Owned!, Error)]
+#[error("could not write into storage: {0}")]
+pub struct Storage(pub String);
+
+/// An `assert` statement didn't hold.
+#[derive(Owned!, Error)]
+#[error("assertion failed: expected {what} to be {expected}, but it is {actual}")]
+pub struct AssertFailed {
+    pub what: String,
+    pub expected: String,
+    pub actual: String,
+}
+
 #[derive(Owned!, thiserror::Error)]
 #[error("could not semantically understand input")]
 pub enum Repr {
@@ -16,6 +79,47 @@ pub enum Repr {
     Same(#[from] Same),
     Unspecified(#[from] PriceUnspecified),
     BothZero(#[from] BothZero),
+    Arg(#[from] Arg),
+    Usage(#[from] Usage),
+    TotalMismatch(#[from] TotalMismatch),
+    Label(#[from] Label),
+    Naming(#[from] Naming),
+    Nested(#[from] Nested),
+    Recurring(#[from] Recurring),
+    Query(#[from] Query),
+}
+
+/// The arguments of a statement don't fit its command.
+#[derive(Owned!, Error)]
+pub enum Arg {
+    #[error("missing argument `{0}`")]
+    Missing(String),
+    #[error("argument `{key}` needs to be {expected}")]
+    Type { key: String, expected: String },
+    #[error("didn't expect argument `{0}` here")]
+    Unexpected(String),
+    #[error("unknown argument `{0}`")]
+    Unknown(String),
+    #[error("argument `{0}` is given more than once")]
+    Duplicate(String),
+    #[error("expected {0} right after the command")]
+    Form(String),
+}
+
+/// The arguments of a statement don't fit the [schema] of its command.
+///
+/// [schema]: super::schema::Schema
+#[derive(Owned!, Error)]
+#[error(
+    "{arg}{}\nusage: {usage}",
+    suggestion.as_ref().map(|s| format!(" -- did you mean `{s}`?")).unwrap_or_default()
+)]
+pub struct Usage {
+    pub arg: Arg,
+    /// What might've been meant instead.
+    pub suggestion: Option<String>,
+    /// Of the command, as generated from its schema.
+    pub usage: String,
 }
 
 #[derive(Owned!, Error)]
@@ -26,6 +130,7 @@ pub enum UnknownActor {
     ConceptGtin(#[from] UnknownConceptGtin),
     Object(#[from] UnknownObject),
     ProductName(#[from] UnknownProductName),
+    Name(#[from] UnknownName),
 }
 
 #[derive(Owned!, thiserror::Error)]
@@ -48,6 +153,10 @@ pub struct UnknownObject(pub Name);
 #[error("unknown product {0} (is neither an object name nor a concept name)")]
 pub struct UnknownProductName(pub Name);
 
+#[derive(Owned!, thiserror::Error)]
+#[error("unknown name {0} (is neither an entity, object nor concept name)")]
+pub struct UnknownName(pub Name);
+
 /// There is no reason for a noop in money processing. Likely a typo.
 #[derive(Owned!, Error)]
 #[error("{0} and {1} are the same, but mustn't be")]
@@ -61,9 +170,88 @@ pub struct Same(pub Entity, pub Entity);
 #[derive(Owned!, thiserror::Error)]
 #[error("cannot deliver {product} without knowing the money expected in return at some point -- specify 0 if it's a gift")]
 pub struct PriceUnspecified {
-    pub product: Product,
+    pub product: Box<Product>,
 }
 
 #[derive(Owned!, thiserror::Error)]
 #[error("tried to construct a ratio with both parts being zero, at least one of them has to be zero")]
 pub struct BothZero;
+
+/// The total written on a receipt isn't what its lines sum up to.
+/// Likely a line is missing or has a typo.
+#[derive(Owned!, thiserror::Error)]
+#[error(
+    "stated total {stated} is off by {} from the sum of the lines {sum}",
+    Balance::from(stated.clone()) - sum.clone()
+)]
+pub struct TotalMismatch {
+    pub stated: Money,
+    pub sum: Money,
+}
+
+/// A label given with `as` or to `void` doesn't fit the ledger.
+#[derive(Owned!, Error)]
+pub enum Label {
+    #[error("label {0} is already labelled on an earlier transfer, pick another one")]
+    Taken(Name),
+    #[error("no transfer is labelled or numbered {0}")]
+    Unknown(String),
+    #[error("transfer {0} is already voided")]
+    Voided(String),
+}
+
+/// A name given to `alias` or `rename` doesn't fit the actors.
+#[derive(Owned!, Error)]
+pub enum Naming {
+    #[error("{name} is already taken by another {kind}, pick another name")]
+    Taken { name: Name, kind: &'static str },
+    #[error(
+        "{0} names more than one kind of actor, put `entity`, `concept` or `object` in front of it"
+    )]
+    Ambiguous(Name),
+}
+
+/// The statement runs the statements in its body,
+/// so it can't be represented as a single command.
+#[derive(Owned!, thiserror::Error)]
+#[error("`{0}` runs the statements in its braces and can't be represented as a single command")]
+pub struct Nested(pub ast::Command);
+
+/// A `select` doesn't fit the table it reads.
+#[derive(Owned!, Error)]
+pub enum Query {
+    #[error(
+        "unknown table `{0}`, only `payments`, `deliveries`, `entities`, `concepts` and `objects` exist"
+    )]
+    UnknownTable(String),
+    #[error("table `{table}` has no column `{column}`")]
+    UnknownColumn { table: &'static str, column: String },
+    #[error("unknown function `{0}`, only `count`, `sum`, `min` and `max` exist")]
+    UnknownFunction(String),
+    #[error("`{0}` doesn't work on that column")]
+    Inapplicable(String),
+    #[error("`{column}` can only be compared with {expected}")]
+    Type {
+        column: String,
+        expected: &'static str,
+    },
+    #[error("`{0}` can only be compared with `=` or `!=`")]
+    Unordered(String),
+    #[error("can't group by `{0}`, it's neither a column nor a period of the `date` column")]
+    Ungroupable(String),
+    #[error("`{0}` is neither grouped by nor aggregated, e.g. with `count` or `sum`")]
+    Ungrouped(String),
+    #[error("can only order by a selected column, but `{0}` isn't one")]
+    NotSelected(String),
+}
+
+/// The body of an `every` statement can't be repeated as written.
+#[derive(Owned!, thiserror::Error)]
+pub enum Recurring {
+    #[error("`every` never ends, give it an `until` date or run the ledger only up to a date")]
+    Endless,
+    #[error("only `pay`, `deliver` and `receipt` can be repeated, not `{0}`")]
+    Unrepeatable(ast::Command),
+    #[error("`date` is given by `every` already")]
+    Dated,
+}
@@ -5,16 +5,37 @@
 pub mod cmd;
 pub mod error;
 pub mod model;
+pub mod query;
+pub mod repr;
+pub mod schema;
+pub mod storage;
 
 use std::ops::Deref;
 
 pub use model::State;
 
-use crate::{aux::NotOrd, syntax::ast::Script};
+use crate::{
+    ext::Date,
+    syntax::ast::{self, Script, Span, Stmt},
+};
 
-#[derive(NotOrd!, Default)]
+use error::Located;
+use storage::{Backend, Storage};
+
+#[derive(Debug, Default)]
 pub struct Runtime {
     state: State,
+    backend: Backend,
+    /// What the state was at the start of each open transaction
+    /// and how many overruns there were, innermost last.
+    open: Vec<(State, usize)>,
+    /// Last day recurring transfers happen on, if limited.
+    horizon: Option<Date>,
+    /// Whether queries are only checked, not answered.
+    quiet: bool,
+    /// Budgets deliveries went over, in order,
+    /// until [taken][Runtime::take_overruns].
+    overruns: Vec<cmd::budget::Usage>,
 }
 
 impl Runtime {
@@ -22,6 +43,28 @@ impl Runtime {
         &self.state
     }
 
+    /// Lets recurring transfers from `every` only happen
+    /// up to and including `date` from now on,
+    /// as if the ledger was evaluated on that day.
+    ///
+    /// Without one, every `every` needs to end on its own.
+    pub fn set_horizon(&mut self, date: Option<Date>) {
+        self.horizon = date;
+    }
+
+    /// Only checks queries such as `balance` or `whatif` from now on
+    /// instead of printing their answers,
+    /// e.g. to answer a question asked from outside of the script instead.
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    /// Returns every budget a delivery went over since the last call,
+    /// leaving out those undone by [`Runtime::abort`].
+    pub fn take_overruns(&mut self) -> Vec<cmd::budget::Usage> {
+        std::mem::take(&mut self.overruns)
+    }
+
     pub fn to_state(self) -> State {
         self.state
     }
@@ -35,26 +78,169 @@ impl Runtime {
         Self::default()
     }
 
+    /// Initializes an empty runtime
+    /// that also writes everything into `storage`.
+    pub fn with_storage(storage: impl Storage + Send + 'static) -> Self {
+        Self {
+            backend: Backend::new(storage),
+            ..Self::default()
+        }
+    }
+
     /// Evaluate a whole parsed [`Script`]
     /// by [representing][Runtime::repr]
     /// every [statement][crate::syntax::ast::Stmt]
     /// as the corresponding [command][cmd::Command]
     /// and running it.
     ///
+    /// Each `begin … commit` block in the script is applied atomically.
+    ///
     /// # Error
     ///
     /// Note that in the case of an error,
-    /// the runtime is not rolled back
-    /// and still holds the state built _until_ the
+    /// only the open `begin … commit` blocks are rolled back.
+    /// Otherwise the runtime still holds the state built _until_ the
     /// invalid instruction.
-    pub fn run(&mut self, script: Script) -> Result<(), ()> {
+    /// Use [`Runtime::run_atomic`] to roll back the whole script.
+    pub fn run(&mut self, script: Script) -> Result<(), Located> {
+        let depth = self.open.len();
+        self.run_within(script, depth).inspect_err(|_| {
+            // the original error is more useful than a failed rollback,
+            // and the backend is in an unknown state either way
+            while self.open.len() > depth {
+                let _ = self.abort();
+            }
+        })
+    }
+
+    /// Like [`Runtime::run`],
+    /// but if any statement fails,
+    /// the runtime is rolled back to before the script.
+    pub fn run_atomic(&mut self, script: Script) -> Result<(), Located> {
+        let span = Span {
+            start: script.0.first().map_or(0, |stmt| stmt.span.start),
+            end: script.0.last().map_or(0, |stmt| stmt.span.end),
+        };
+        let located = |err: error::Transaction| Located {
+            span,
+            source: error::Fulfil::from(err).into(),
+        };
+
+        self.begin().map_err(located)?;
+        self.run(script).inspect_err(|_| {
+            let _ = self.abort();
+        })?;
+        self.commit().map_err(located)
+    }
+
+    /// Runs all statements,
+    /// making sure all transactions opened after `depth` are closed again.
+    fn run_within(&mut self, script: Script, depth: usize) -> Result<(), Located> {
+        // where each transaction opened by the script was opened
+        let mut begins = Vec::new();
+
         for stmt in script.0 {
-            let cmd = todo!();
-            self.fulfil(cmd);
+            let span = stmt.span;
+            for line in stmt.node.unblock() {
+                let cmd = line.cmd.clone();
+                // don't end a transaction opened before running
+                if cmd == ast::Command::Commit && begins.is_empty() {
+                    return Err(Located {
+                        span,
+                        source: error::Fulfil::from(error::Transaction::NoneOpen).into(),
+                    });
+                }
+
+                self.run_stmt(line)
+                    .map_err(|source| Located { span, source })?;
+
+                match cmd {
+                    ast::Command::Begin => begins.push(span),
+                    ast::Command::Commit => {
+                        begins.pop();
+                    }
+                    _ => {}
+                }
+            }
         }
 
+        debug_assert_eq!(self.open.len(), depth + begins.len());
+        match begins.pop() {
+            Some(span) => Err(Located {
+                span,
+                source: error::Fulfil::from(error::Transaction::Unclosed).into(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn run_stmt(&mut self, stmt: Stmt) -> Result<(), error::Run> {
+        match stmt.cmd {
+            ast::Command::Whatif if self.quiet => return Ok(()),
+            ast::Command::Whatif => return self.whatif(stmt),
+            ast::Command::Every => return self.every(stmt),
+            _ => {}
+        }
+
+        let cmd = self.repr(stmt)?;
+        if self.quiet && cmd.is_query() {
+            return Ok(());
+        }
+        self.fulfil(cmd)?;
         Ok(())
     }
+
+    /// Opens a transaction,
+    /// so everything afterwards can be undone by [`Runtime::abort`]
+    /// until the matching [`Runtime::commit`].
+    ///
+    /// Transactions can be nested.
+    /// Ending one always ends the innermost open one.
+    pub fn begin(&mut self) -> Result<(), error::Transaction> {
+        self.backend.write(|storage| storage.begin())?;
+        self.open.push((self.state.clone(), self.overruns.len()));
+        Ok(())
+    }
+
+    /// Keeps everything since the matching [`Runtime::begin`].
+    pub fn commit(&mut self) -> Result<(), error::Transaction> {
+        if self.open.is_empty() {
+            return Err(error::Transaction::NoneOpen);
+        }
+        self.backend.write(|storage| storage.commit())?;
+        self.open.pop();
+        Ok(())
+    }
+
+    /// Undoes everything since the matching [`Runtime::begin`].
+    ///
+    /// The state is rolled back even if the backend fails to.
+    pub fn abort(&mut self) -> Result<(), error::Transaction> {
+        let (state, overruns) = self.open.pop().ok_or(error::Transaction::NoneOpen)?;
+        self.state = state;
+        self.overruns.truncate(overruns);
+        self.backend.write(|storage| storage.abort())?;
+        Ok(())
+    }
+
+    /// Applies `op` to the state and the backend, if any.
+    fn store(
+        &mut self,
+        op: impl Fn(&mut dyn Storage) -> Result<(), error::Storage>,
+    ) -> Result<(), error::Storage> {
+        op(&mut self.state)?;
+        self.backend.write(op)
+    }
+}
+
+impl From<State> for Runtime {
+    /// Continues from a previously reached state.
+    fn from(state: State) -> Self {
+        Self {
+            state,
+            ..Self::default()
+        }
+    }
 }
 
 impl Deref for Runtime {
@@ -64,3 +250,124 @@ impl Deref for Runtime {
         &self.state
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Script,
+        ext::{Integer, Money},
+    };
+
+    use super::*;
+
+    const SETUP: &str = "create entity { A; B }";
+
+    fn run(rt: &mut Runtime, source: &str) -> Result<(), error::Run> {
+        rt.run(Script::parse(source).unwrap())
+            .map_err(|err| err.source)
+    }
+
+    fn balance(rt: &Runtime) -> Integer {
+        rt.state().balance(rt.get_dir("A", "B").unwrap()).0
+    }
+
+    #[test]
+    fn block() {
+        let mut rt = Runtime::new();
+        run(&mut rt, SETUP).unwrap();
+
+        let err = run(
+            &mut rt,
+            "
+            pay 1€ from=A to=B
+            begin
+            pay 2€ from=A to=B
+            assert balance from=A to=B = 0€
+            commit
+            ",
+        );
+        assert!(matches!(
+            err,
+            Err(error::Run::Fulfil(error::Fulfil::Assert(_)))
+        ));
+        // only the block is rolled back
+        assert_eq!(balance(&rt), Integer::from(100));
+
+        run(&mut rt, "begin; pay 2€ from=A to=B; commit").unwrap();
+        assert_eq!(balance(&rt), Integer::from(300));
+    }
+
+    #[test]
+    fn atomic() {
+        let mut rt = Runtime::new();
+        run(&mut rt, SETUP).unwrap();
+        let before = rt.state().clone();
+
+        let script = Script::parse("pay 1€ from=A to=B; pay 1€ from=A to=C").unwrap();
+        assert!(rt.run_atomic(script).is_err());
+        assert_eq!(*rt.state(), before);
+    }
+
+    #[test]
+    fn unbalanced() {
+        let mut rt = Runtime::new();
+        run(&mut rt, SETUP).unwrap();
+
+        let unclosed = run(&mut rt, "begin\npay 1€ from=A to=B");
+        assert!(matches!(
+            unclosed,
+            Err(error::Run::Fulfil(error::Fulfil::Transaction(
+                error::Transaction::Unclosed
+            )))
+        ));
+        assert_eq!(balance(&rt), Integer::ZERO);
+
+        // can't end what the caller began
+        rt.begin().unwrap();
+        let unopened = run(&mut rt, "pay 1€ from=A to=B\ncommit");
+        assert!(matches!(
+            unopened,
+            Err(error::Run::Fulfil(error::Fulfil::Transaction(
+                error::Transaction::NoneOpen
+            )))
+        ));
+        // which also decides about what ran before the error
+        assert_eq!(balance(&rt), Integer::from(100));
+        rt.abort().unwrap();
+        assert_eq!(balance(&rt), Integer::ZERO);
+    }
+
+    #[test]
+    fn threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Runtime>();
+    }
+
+    #[test]
+    fn library() {
+        let mut rt = Runtime::new();
+        run(&mut rt, SETUP).unwrap();
+        let dir = rt.get_dir("A", "B").unwrap();
+        let pay = |amount: u8| {
+            cmd::Command::Pay(cmd::Pay {
+                amount: Money(amount.into()),
+                who: dir.clone(),
+                date: None,
+                label: None,
+                memo: None,
+                tags: Vec::new(),
+                voided: false,
+            })
+        };
+
+        rt.begin().unwrap();
+        rt.fulfil(pay(100)).unwrap();
+        rt.begin().unwrap();
+        rt.fulfil(pay(200)).unwrap();
+        rt.abort().unwrap();
+        rt.commit().unwrap();
+
+        assert_eq!(balance(&rt), Integer::from(100));
+        assert!(matches!(rt.commit(), Err(error::Transaction::NoneOpen)));
+    }
+}
@@ -12,16 +12,19 @@
 //! [`Entity`]ies, [`Concept`]s or [`Object`]s:
 //! if there is one, it has to exist and hence be created at some point.
 
-use std::{array::IntoIter, mem};
+use std::{array::IntoIter, mem, ops, str::FromStr};
+
+use num_bigint::Sign;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     Map,
     aux::{NotOrd, Owned},
-    ext::{Balance, Gtin, Money, Natural},
+    ext::{Balance, Date, Debit, Gtin, Integer, Money, Natural},
 };
 
 use super::{
-    cmd::{Name, NameRef},
+    cmd::{Budget, Deliver, Name, NameRef, Pay},
     error::{
         self, PriceUnspecified, UnknownActor, UnknownConcept, UnknownConceptGtin, UnknownEntity,
         UnknownObject,
@@ -40,7 +43,7 @@ use super::{
 /// In this case, they will never become accessible again.
 /// ***However***, they will still be referred to
 /// when referenced elsewhere.
-/// 
+///
 /// For example, take this script:
 ///
 /// ```text
@@ -54,29 +57,42 @@ use super::{
 /// as parent.
 /// However, only the latter concept `C` with price 2€
 /// can be referred to by its name.
-#[derive(NotOrd!, Default)]
+#[derive(NotOrd!, Default, Serialize, Deserialize)]
 pub struct State {
     // not much use -- yet, that is
     pub entities: Map<Name, Entity>,
     pub concepts: Map<Name, Concept>,
     pub concepts_gtin: Map<Gtin, Concept>,
     pub objects: Map<Name, Object>,
+    /// Names given to actors after creation, see [`super::cmd::Alias`].
+    #[serde(default)]
+    pub aliases: Aliases,
 
+    #[serde(with = "crate::ext::json::balances")]
     pub balances: Map<Pair, Balance>,
+
+    /// Every transfer, in the order it happened.
+    pub ledger: Vec<Transfer>,
+
+    /// At most one per entity and category.
+    #[serde(default)]
+    pub budgets: Vec<Budget>,
 }
 
 impl State {
-    /// Looks up an already created [`Entity`] by name.
+    /// Looks up an already created [`Entity`] by name or alias.
     pub fn get_entity(&self, name: NameRef) -> Result<&Entity, UnknownEntity> {
         self.entities
             .get(name)
+            .or_else(|| self.aliases.entities.get(name))
             .ok_or_else(|| UnknownEntity(name.to_owned()))
     }
 
-    /// Looks up an already created [`Concept`] by name.
+    /// Looks up an already created [`Concept`] by name or alias.
     pub fn get_concept(&self, name: NameRef) -> Result<&Concept, UnknownConcept> {
         self.concepts
             .get(name)
+            .or_else(|| self.aliases.concepts.get(name))
             .ok_or_else(|| UnknownConcept(name.to_owned()))
     }
 
@@ -107,10 +123,11 @@ impl State {
             .ok_or(UnknownConceptGtin(*gtin))
     }
 
-    /// Looks up an already created [`Object`] by name.
+    /// Looks up an already created [`Object`] by name or alias.
     pub fn get_object(&self, name: NameRef) -> Result<&Object, UnknownObject> {
         self.objects
             .get(name)
+            .or_else(|| self.aliases.objects.get(name))
             .ok_or_else(|| UnknownObject(name.to_owned()))
     }
 
@@ -121,10 +138,10 @@ impl State {
         Ok(dir)
     }
 
-    /// Returns how much the [`Dir::source`] owes [`Dir::target`].
+    /// Returns how much the [`Dir::target`] owes [`Dir::source`].
     ///
     /// If the balance is _negative_, that means the balance is _in reverse_,
-    /// how much [`Dir::target`] owes [`Dir::source`] in absolute value!
+    /// how much [`Dir::source`] owes [`Dir::target`] in absolute value!
     pub fn balance(&self, dir: Dir) -> Balance {
         let mut bal = self
             .balances
@@ -135,30 +152,344 @@ impl State {
         bal.take_order(dir);
         bal
     }
+
+    /// Like [`State::balance`],
+    /// but only counting transfers up to and including `date`.
+    ///
+    /// Transfers without a date always count.
+    pub fn balance_as_of(&self, dir: Dir, date: Date) -> Balance {
+        let mut flipped = dir.clone();
+        flipped.flip();
+
+        let mut bal = Balance(0.into());
+        for transfer in &self.ledger {
+            if transfer.is_voided() || transfer.date().is_some_and(|on| on > date) {
+                continue;
+            }
+            if *transfer.who() == dir {
+                bal += Balance::from(transfer.shifted());
+            } else if *transfer.who() == flipped {
+                bal -= Balance::from(transfer.shifted());
+            }
+        }
+        bal
+    }
+
+    /// Returns the fractions of a cent the target still owes the source
+    /// from earlier deliveries with [`Rounding::Carry`] between them,
+    /// which is negative if it's the other way around.
+    pub fn carried(&self, dir: &Dir) -> Remainder {
+        let mut flipped = dir.clone();
+        flipped.flip();
+
+        let mut carried = Remainder::default();
+        for transfer in self.ledger.iter().filter(|t| !t.is_voided()) {
+            let Some(carry) = transfer.carry() else {
+                continue;
+            };
+            if transfer.who() == dir {
+                carried = carried + carry;
+            } else if *transfer.who() == flipped {
+                carried = carried - carry;
+            }
+        }
+        carried
+    }
+
+    /// Returns how much `entity` owes all other entities together.
+    ///
+    /// If the balance is _negative_, `entity` is owed money overall.
+    pub fn net(&self, entity: &Entity) -> Balance {
+        let mut net = Balance(0.into());
+
+        for (pair, bal) in &self.balances {
+            if pair.a() == entity {
+                net += bal.clone();
+            } else if pair.b() == entity {
+                net -= bal.clone();
+            }
+        }
+
+        net
+    }
+
+    /// Every non-zero balance, from debtor to creditor.
+    pub fn debts(&self) -> Vec<Debit> {
+        self.balances
+            .iter()
+            .filter(|(_, bal)| bal.0 != Integer::ZERO)
+            .map(|(pair, bal)| {
+                let mut between = Dir::from(pair.clone());
+                if bal.0.sign() == Sign::Minus {
+                    between.flip();
+                }
+                Debit {
+                    between,
+                    amount: bal.clone().abs(),
+                }
+            })
+            .collect()
+    }
+
+    /// Payments that would bring every entity's [net][State::net] to 0,
+    /// using as few as is easily possible.
+    ///
+    /// Who pays whom doesn't need to match the balances,
+    /// e.g. if `A` owes `B` and `B` owes `C` the same amount,
+    /// `A` just pays `C` directly.
+    pub fn settle(&self) -> Vec<Debit> {
+        // greedily let the largest debtor pay the largest creditor
+        let mut debtors = Vec::new();
+        let mut creditors = Vec::new();
+        for entity in self.entities.values() {
+            let Balance(net) = self.net(entity);
+            match net.sign() {
+                Sign::Plus => debtors.push((entity.clone(), net)),
+                Sign::Minus => creditors.push((entity.clone(), -net)),
+                Sign::NoSign => {}
+            }
+        }
+        let by_amount = |a: &(Entity, Integer), b: &(Entity, Integer)| {
+            b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0))
+        };
+        debtors.sort_by(by_amount);
+        creditors.sort_by(by_amount);
+
+        let mut payments = Vec::new();
+        let (mut debtors, mut creditors) = (debtors.into_iter(), creditors.into_iter());
+        let (mut debtor, mut creditor) = (debtors.next(), creditors.next());
+
+        while let (Some((from, owes)), Some((to, owed))) = (&mut debtor, &mut creditor) {
+            let amount = owes.clone().min(owed.clone());
+            *owes -= &amount;
+            *owed -= &amount;
+
+            payments.push(Debit {
+                between: Dir::new(from.clone(), to.clone()).unwrap(),
+                amount: Money(amount.into_parts().1),
+            });
+
+            if *owes == Integer::ZERO {
+                debtor = debtors.next();
+            }
+            if *owed == Integer::ZERO {
+                creditor = creditors.next();
+            }
+        }
+
+        payments
+    }
+
+    /// How often `product` has been delivered to `entity`
+    /// minus how often `entity` delivered it to someone else.
+    ///
+    /// If `product` is a concept,
+    /// objects that are an instance of it count as well.
+    pub fn inventory(&self, entity: &Entity, product: &Product) -> Integer {
+        let mut count = Integer::ZERO;
+
+        for transfer in &self.ledger {
+            let Transfer::Deliver(delivery) = transfer else {
+                continue;
+            };
+            if delivery.voided {
+                continue;
+            }
+
+            let matches = match (&delivery.product, product) {
+                (
+                    Product::Object(Object {
+                        parent: Some(parent),
+                        ..
+                    }),
+                    Product::Concept(concept),
+                ) => parent == concept,
+                (delivered, product) => delivered == product,
+            };
+            if !matches {
+                continue;
+            }
+
+            let qty = Integer::from(delivery.qty.clone());
+            if delivery.who.target() == entity {
+                count += &qty;
+            }
+            if delivery.who.source() == entity {
+                count -= &qty;
+            }
+        }
+
+        count
+    }
 }
 
-/// Someone who holds money and deliver things.
+/// Other names actors can be referred to by,
+/// next to the one they were created or renamed with.
+#[derive(NotOrd!, Default, Serialize, Deserialize)]
+pub struct Aliases {
+    pub entities: Map<Name, Entity>,
+    pub concepts: Map<Name, Concept>,
+    pub objects: Map<Name, Object>,
+}
+
+/// Any of the actors that can be referred to by name.
 #[derive(Owned!)]
+pub enum Actor {
+    Entity(Entity),
+    Concept(Concept),
+    Object(Object),
+}
+
+impl Actor {
+    /// What it was created or last renamed as.
+    pub fn name(&self) -> Option<NameRef<'_>> {
+        match self {
+            Actor::Entity(entity) => Some(entity.name()),
+            Actor::Concept(concept) => Some(concept.name()),
+            Actor::Object(object) => object.name(),
+        }
+    }
+
+    /// How it is called in source code, as in `create <kind>`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Actor::Entity(_) => "entity",
+            Actor::Concept(_) => "concept",
+            Actor::Object(_) => "object",
+        }
+    }
+}
+
+/// Something that moved from one [`Entity`] to another.
+#[derive(Owned!, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Transfer {
+    Pay(Pay),
+    Deliver(Box<Deliver>),
+}
+
+impl Transfer {
+    pub fn who(&self) -> &Dir {
+        match self {
+            Transfer::Pay(pay) => &pay.who,
+            Transfer::Deliver(deliver) => &deliver.who,
+        }
+    }
+
+    pub fn label(&self) -> Option<NameRef<'_>> {
+        match self {
+            Transfer::Pay(pay) => pay.label.as_deref(),
+            Transfer::Deliver(deliver) => deliver.label.as_deref(),
+        }
+    }
+
+    pub fn memo(&self) -> Option<&str> {
+        match self {
+            Transfer::Pay(pay) => pay.memo.as_deref(),
+            Transfer::Deliver(deliver) => deliver.memo.as_deref(),
+        }
+    }
+
+    pub fn date(&self) -> Option<Date> {
+        match self {
+            Transfer::Pay(pay) => pay.date,
+            Transfer::Deliver(deliver) => deliver.date,
+        }
+    }
+
+    /// Its own tags, followed by those of the delivered concept
+    /// (or the concept of the delivered object) not given already.
+    pub fn tags(&self) -> Vec<NameRef<'_>> {
+        let (own, concept) = match self {
+            Transfer::Pay(pay) => (&pay.tags, None),
+            Transfer::Deliver(deliver) => (&deliver.tags, deliver.product.concept()),
+        };
+
+        let mut tags: Vec<NameRef> = own.iter().map(String::as_str).collect();
+        for tag in concept.map(Concept::tags).unwrap_or_default() {
+            if !tags.contains(&tag.as_str()) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+
+    /// Whether `tag` is one of its [tags][Transfer::tags].
+    pub fn is_tagged(&self, tag: NameRef) -> bool {
+        self.tags().contains(&tag)
+    }
+
+    pub fn is_voided(&self) -> bool {
+        match self {
+            Transfer::Pay(pay) => pay.voided,
+            Transfer::Deliver(deliver) => deliver.voided,
+        }
+    }
+
+    /// How much this moves from [`Dir::source`] to [`Dir::target`] in the balances.
+    ///
+    /// Possession is not modelled,
+    /// so balance-wise a delivery is just a payment
+    /// of what the target is expected to pay back.
+    pub fn shifted(&self) -> Money {
+        match self {
+            Transfer::Pay(pay) => pay.amount.clone(),
+            Transfer::Deliver(deliver) => {
+                let (_source_supply, target_supply) = deliver
+                    .split
+                    .clone()
+                    .split(deliver.price.clone(), &deliver.rounding);
+                target_supply
+            }
+        }
+    }
+
+    /// What [`Transfer::shifted`] rounded away from the exact share,
+    /// if this is a delivery rounded with [`Rounding::Carry`].
+    /// All of these together are what's [carried][State::carried].
+    pub fn carry(&self) -> Option<Remainder> {
+        let Transfer::Deliver(deliver) = self else {
+            return None;
+        };
+        if !matches!(deliver.rounding, Rounding::Carry(_)) {
+            return None;
+        }
+
+        let exact = deliver.split.clone().exact(deliver.price.clone());
+        let shifted = Remainder::new(self.shifted().0.into(), 1u8.into());
+        Some(exact - shifted)
+    }
+}
+
+/// Someone who holds money and deliver things.
+#[derive(Owned!, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Entity {
     pub(super) name: Name,
 }
 
 impl Entity {
-    pub fn name(&self) -> NameRef {
+    pub fn name(&self) -> NameRef<'_> {
         &self.name
     }
 }
 
 /// Designed idea of [`Object`]s.
-#[derive(Owned!)]
+#[derive(Owned!, Serialize, Deserialize)]
 pub struct Concept {
     pub(super) name: Name,
     pub(super) default_price: Option<Money>,
     pub(super) gtin: Option<Gtin>,
+    /// What it counts as for [budgets][super::cmd::Budget], e.g. `groceries`.
+    #[serde(default)]
+    pub(super) category: Option<Name>,
+    /// Given with `tags` or added by `tag`, in that order.
+    #[serde(default)]
+    pub(super) tags: Vec<Name>,
 }
 
 impl Concept {
-    pub fn name(&self) -> NameRef {
+    pub fn name(&self) -> NameRef<'_> {
         &self.name
     }
 
@@ -169,17 +500,25 @@ impl Concept {
     pub fn gtin(&self) -> Option<Gtin> {
         self.gtin
     }
+
+    pub fn category(&self) -> Option<NameRef<'_>> {
+        self.category.as_deref()
+    }
+
+    pub fn tags(&self) -> &[Name] {
+        &self.tags
+    }
 }
 
 /// Physically holdable something.
-#[derive(Owned!)]
+#[derive(Owned!, Serialize, Deserialize)]
 pub struct Object {
     pub(super) name: Option<Name>,
     pub(super) parent: Option<Concept>,
 }
 
 impl Object {
-    pub fn name(&self) -> Option<NameRef> {
+    pub fn name(&self) -> Option<NameRef<'_>> {
         self.name.as_ref().map(String::as_ref)
     }
 
@@ -197,7 +536,8 @@ impl Object {
 }
 
 /// Don't care about hypotheticality, the user just wants one *thing*? Use this.
-#[derive(Owned!)]
+#[derive(Owned!, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
 pub enum Product {
     /// Instantiate this concept into an anonymous object.
     Concept(Concept),
@@ -206,12 +546,20 @@ pub enum Product {
 }
 
 impl Product {
+    /// The concept itself or the parent of the object, if any.
+    pub fn concept(&self) -> Option<&Concept> {
+        match self {
+            Product::Concept(concept) => Some(concept),
+            Product::Object(object) => object.parent.as_ref(),
+        }
+    }
+
     /// How much is this product worth by default,
     /// iff that is set (either directly or by parent)?
     pub fn default_price(&self) -> Result<&Money, PriceUnspecified> {
         let err = || {
             Err(PriceUnspecified {
-                product: self.clone(),
+                product: Box::new(self.clone()),
             })
         };
 
@@ -238,7 +586,8 @@ impl Product {
 }
 
 /// **Directed** edge between 2 different [`Entity`]ies.
-#[derive(Owned!)]
+#[derive(Owned!, Serialize, Deserialize)]
+#[serde(try_from = "RawDir")]
 pub struct Dir {
     pub(super) source: Entity,
     pub(super) target: Entity,
@@ -280,12 +629,36 @@ impl Dir {
     }
 }
 
+/// A [`Dir`] as deserialized, before checking it through [`Dir::new`].
+#[derive(Deserialize)]
+struct RawDir {
+    source: Entity,
+    target: Entity,
+}
+
+impl TryFrom<RawDir> for Dir {
+    type Error = error::Same;
+    fn try_from(RawDir { source, target }: RawDir) -> Result<Self, Self::Error> {
+        Self::new(source, target)
+    }
+}
+
 impl From<Dir> for Pair {
     fn from(Dir { source, target }: Dir) -> Self {
         Self::new(source, target).unwrap()
     }
 }
 
+impl From<Pair> for Dir {
+    /// From [`Pair::a`] to [`Pair::b`].
+    fn from(Pair { a, b }: Pair) -> Self {
+        Self {
+            source: a,
+            target: b,
+        }
+    }
+}
+
 impl From<Dir> for [Entity; 2] {
     fn from(Dir { source, target }: Dir) -> Self {
         [source, target]
@@ -301,7 +674,8 @@ impl IntoIterator for Dir {
 }
 
 /// **Undirected** edge between 2 different [`Entity`]ies.
-#[derive(Owned!)]
+#[derive(Owned!, Serialize, Deserialize)]
+#[serde(try_from = "RawPair")]
 pub struct Pair {
     // invariant: a <= b
     pub(super) a: Entity,
@@ -337,6 +711,20 @@ impl Pair {
     }
 }
 
+/// A [`Pair`] as deserialized, before checking it through [`Pair::new`].
+#[derive(Deserialize)]
+struct RawPair {
+    a: Entity,
+    b: Entity,
+}
+
+impl TryFrom<RawPair> for Pair {
+    type Error = error::Same;
+    fn try_from(RawPair { a, b }: RawPair) -> Result<Self, Self::Error> {
+        Self::new(a, b)
+    }
+}
+
 impl From<Pair> for [Entity; 2] {
     fn from(Pair { a, b }: Pair) -> Self {
         [a, b]
@@ -352,10 +740,13 @@ impl IntoIterator for Pair {
 }
 
 /// Split an amount between 2 parties.
-#[derive(Owned!)]
+#[derive(Owned!, Serialize, Deserialize)]
+#[serde(try_from = "RawSplit")]
 pub struct Split {
     // invariant: at least one of {source,target} is non-zero
+    #[serde(with = "crate::ext::json::natural")]
     source: Natural,
+    #[serde(with = "crate::ext::json::natural")]
     target: Natural,
 }
 
@@ -368,6 +759,16 @@ impl Split {
         Ok(Self { source, target })
     }
 
+    /// Part paid by the source.
+    pub fn source(&self) -> &Natural {
+        &self.source
+    }
+
+    /// Part paid by the target.
+    pub fn target(&self) -> &Natural {
+        &self.target
+    }
+
     /// How many parts is this ratio over?
     pub fn denominator(self) -> Natural {
         self.source + self.target
@@ -378,15 +779,204 @@ impl Split {
     /// # Rounding
     ///
     /// If the ratio causes the money to be split into fractional cents,
-    /// the source part is rounded down to the next smaller chunk and
-    /// the target part is rounded up to cover the rest.
-    pub fn split(self, full: Money) -> (Money, Money) {
-        let chunk = full.0.clone() / self.clone().denominator();
-        let source = self.source * chunk;
-        let target = full.0 - source.clone();
+    /// `rounding` decides who covers the cent left over.
+    /// Either way, both parts always add up to `full`.
+    pub fn split(self, full: Money, rounding: &Rounding) -> (Money, Money) {
+        let denominator = self.clone().denominator();
+        let exact = full.0.clone() * &self.target;
+        let floor = &exact / &denominator;
+        let rest = exact % &denominator;
+
+        let target = match rounding {
+            Rounding::Payer => floor,
+            Rounding::Receiver if rest != Natural::ZERO => floor + 1u8,
+            Rounding::Receiver => floor,
+            // ties go to the payer, like for the carried remainder
+            Rounding::Nearest if &rest * 2u8 > denominator => floor + 1u8,
+            Rounding::Nearest => floor,
+            Rounding::Carry(carried) => {
+                let owed = self.exact(full.clone()) + *carried.clone();
+                // only below 0 if more was carried the other way,
+                // which then stays carried
+                owed.round().to_biguint().unwrap_or_default()
+            }
+        };
+        let source = full.0 - target.clone();
 
         (Money(source), Money(target))
     }
+
+    /// The target part of `full` without any rounding, in cents.
+    pub fn exact(self, full: Money) -> Remainder {
+        let target = Integer::from(full.0 * &self.target);
+        Remainder::new(target, self.denominator())
+    }
+}
+
+/// Who covers the cent left over when a [`Split`] doesn't divide evenly.
+///
+/// The payer is the [`Dir::source`] of a delivery,
+/// who paid for all of it in the first place,
+/// the receiver is the [`Dir::target`].
+#[derive(Owned!, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rounding {
+    /// The part with the largest remainder,
+    /// which is the one closer to the next cent, is rounded up.
+    /// On a tie, the payer covers the cent.
+    #[default]
+    Nearest,
+    /// The receiver's part is rounded down.
+    Payer,
+    /// The receiver's part is rounded up.
+    Receiver,
+    /// Like [`Rounding::Nearest`],
+    /// but the fraction rounded away is added to
+    /// the next delivery between the same entities with this rounding,
+    /// so over many deliveries nobody pays more than their share.
+    ///
+    /// Holds what was [carried][State::carried] into this delivery,
+    /// boxed to keep deliveries small.
+    Carry(Box<Remainder>),
+}
+
+impl Rounding {
+    /// All names accepted by [`Rounding::from_str`],
+    /// `nearest` being another one for `largest-remainder`.
+    pub const NAMES: [&str; 5] = ["largest-remainder", "nearest", "payer", "receiver", "carry"];
+
+    /// How it's written in source code.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rounding::Nearest => "largest-remainder",
+            Rounding::Payer => "payer",
+            Rounding::Receiver => "receiver",
+            Rounding::Carry(_) => "carry",
+        }
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = ();
+
+    /// Anything in [`Rounding::NAMES`],
+    /// with nothing carried yet for `carry`.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "largest-remainder" | "nearest" => Self::Nearest,
+            "payer" => Self::Payer,
+            "receiver" => Self::Receiver,
+            "carry" => Self::Carry(Box::default()),
+            _ => return Err(()),
+        })
+    }
+}
+
+/// An exact amount of cents, possibly with a fraction of a cent.
+///
+/// Only used for what [rounding][Rounding] leaves over,
+/// actual money is always whole cents.
+#[derive(Owned!)]
+pub struct Remainder {
+    // invariant: denominator is non-zero and shares no factor with the numerator
+    numerator: Integer,
+    denominator: Natural,
+}
+
+impl Remainder {
+    /// `numerator / denominator` cents.
+    ///
+    /// # Panics
+    ///
+    /// If `denominator` is 0.
+    pub fn new(numerator: Integer, denominator: Natural) -> Self {
+        assert_ne!(denominator, Natural::ZERO, "remainder over 0 parts");
+
+        let common = gcd(numerator.magnitude().clone(), denominator.clone());
+        Self {
+            numerator: numerator / Integer::from(common.clone()),
+            denominator: denominator / common,
+        }
+    }
+
+    pub fn numerator(&self) -> &Integer {
+        &self.numerator
+    }
+
+    pub fn denominator(&self) -> &Natural {
+        &self.denominator
+    }
+
+    /// The closest whole amount of cents, rounding down on a tie.
+    pub fn round(&self) -> Integer {
+        let denominator = Integer::from(self.denominator.clone());
+        // round(x) = floor(x + 1/2) with ties going up,
+        // so ceil(x - 1/2) for them to go down
+        let doubled = &self.numerator * 2u8 - &denominator;
+        let twice = &denominator * 2u8;
+        let mut rounded = &doubled / &twice;
+        if doubled.sign() == Sign::Plus && rounded.clone() * &twice != doubled {
+            rounded += 1u8;
+        }
+        rounded
+    }
+}
+
+impl Default for Remainder {
+    fn default() -> Self {
+        Self::new(Integer::ZERO, 1u8.into())
+    }
+}
+
+impl ops::Add for Remainder {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        let numerator = self.numerator * Integer::from(rhs.denominator.clone())
+            + rhs.numerator * Integer::from(self.denominator.clone());
+        Self::new(numerator, self.denominator * rhs.denominator)
+    }
+}
+
+impl ops::Neg for Remainder {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            numerator: -self.numerator,
+            ..self
+        }
+    }
+}
+
+impl ops::Sub for Remainder {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+fn gcd(mut a: Natural, mut b: Natural) -> Natural {
+    while b != Natural::ZERO {
+        let rest = a % &b;
+        a = b;
+        b = rest;
+    }
+    a
+}
+
+/// A [`Split`] as deserialized, before checking it through [`Split::new`].
+#[derive(Deserialize)]
+struct RawSplit {
+    #[serde(with = "crate::ext::json::natural")]
+    source: Natural,
+    #[serde(with = "crate::ext::json::natural")]
+    target: Natural,
+}
+
+impl TryFrom<RawSplit> for Split {
+    type Error = error::BothZero;
+    fn try_from(RawSplit { source, target }: RawSplit) -> Result<Self, Self::Error> {
+        Self::new(source, target)
+    }
 }
 
 impl Default for Split {
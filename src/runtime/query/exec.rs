@@ -0,0 +1,148 @@
+use crate::{
+    Map, Runtime,
+    ext::{Money, Natural, table::Table},
+    syntax::ast::Compare,
+};
+
+use super::{
+    Cell, Kind,
+    plan::{Filter, Key, Output, Plan},
+};
+
+impl Runtime {
+    /// Runs a planned query.
+    ///
+    /// Rows keep the order of their table unless ordered otherwise,
+    /// groups are sorted by what they're grouped by.
+    pub fn select(&self, plan: &Plan) -> Table {
+        let rows = self
+            .rows(plan.source)
+            .into_iter()
+            .filter(|row| plan.filter.iter().all(|filter| filter.holds(row)));
+
+        let mut result: Vec<Vec<Cell>> = if plan.grouped {
+            let mut groups: Map<Cell, Vec<Vec<Cell>>> = Map::new();
+            for row in rows {
+                let key = match plan.group {
+                    Some(Key::Column(idx)) => row[idx].clone(),
+                    Some(Key::Period { period, date }) => match &row[date] {
+                        Cell::Date(date) => Cell::Date(period.start(*date)),
+                        _ => Cell::Empty,
+                    },
+                    None => Cell::Empty,
+                };
+                groups.entry(key).or_default().push(row);
+            }
+            // aggregating over nothing still tells something, e.g. a count of 0
+            if plan.group.is_none() && groups.is_empty() {
+                groups.insert(Cell::Empty, Vec::new());
+            }
+
+            groups
+                .into_iter()
+                .map(|(key, rows)| {
+                    plan.outputs
+                        .iter()
+                        .map(|output| match (output, plan.group) {
+                            (Output::Key, Some(Key::Period { period, .. })) => match &key {
+                                Cell::Date(start) => Cell::Text(period.label(*start)),
+                                _ => Cell::Empty,
+                            },
+                            (Output::Key, _) => key.clone(),
+                            (Output::Column(_), _) => unreachable!("planner to only aggregate"),
+                            (aggregate, _) => aggregate.over(&rows),
+                        })
+                        .collect()
+                })
+                .collect()
+        } else {
+            rows.map(|row| {
+                plan.outputs
+                    .iter()
+                    .map(|output| match output {
+                        Output::Column(idx) => row[*idx].clone(),
+                        _ => unreachable!("planner to not aggregate ungrouped rows"),
+                    })
+                    .collect()
+            })
+            .collect()
+        };
+
+        if let Some((idx, descending)) = plan.order {
+            result.sort_by(|a, b| {
+                let order = a[idx].cmp(&b[idx]);
+                if descending { order.reverse() } else { order }
+            });
+        }
+        if let Some(limit) = plan.limit {
+            result.truncate(limit);
+        }
+
+        let mut table = Table::new(&plan.header);
+        for row in result {
+            table.push(row);
+        }
+        table
+    }
+}
+
+impl Filter {
+    /// An empty cell is only unequal to anything,
+    /// and tags are equal to any one of them.
+    pub(super) fn holds(&self, row: &[Cell]) -> bool {
+        let ordering = match (&row[self.column], &self.value) {
+            (Cell::Empty, _) => return self.compare == Compare::Ne,
+            (Cell::Tags(tags), Cell::Text(tag)) => {
+                return tags.contains(tag) == (self.compare == Compare::Eq);
+            }
+            (cell, value) => cell.cmp(value),
+        };
+
+        match self.compare {
+            Compare::Eq => ordering.is_eq(),
+            Compare::Ne => ordering.is_ne(),
+            Compare::Lt => ordering.is_lt(),
+            Compare::Le => ordering.is_le(),
+            Compare::Gt => ordering.is_gt(),
+            Compare::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+impl Output {
+    /// Aggregates the cells of all `rows` in a group,
+    /// skipping empty ones.
+    fn over(self, rows: &[Vec<Cell>]) -> Cell {
+        let cells = |idx: usize| {
+            rows.iter()
+                .map(move |row| &row[idx])
+                .filter(|cell| **cell != Cell::Empty)
+        };
+
+        match self {
+            Output::Count(None) => Cell::Natural(rows.len().into()),
+            Output::Count(Some(idx)) => Cell::Natural(cells(idx).count().into()),
+            Output::Sum(idx, Kind::Money) => {
+                let mut sum = Money(Natural::ZERO);
+                for cell in cells(idx) {
+                    if let Cell::Money(money) = cell {
+                        sum += money.clone();
+                    }
+                }
+                Cell::Money(sum)
+            }
+            Output::Sum(idx, _) => {
+                let mut sum = Natural::ZERO;
+                for cell in cells(idx) {
+                    if let Cell::Natural(n) = cell {
+                        sum += n;
+                    }
+                }
+                Cell::Natural(sum)
+            }
+            Output::Min(idx) => cells(idx).min().cloned().unwrap_or(Cell::Empty),
+            Output::Max(idx) => cells(idx).max().cloned().unwrap_or(Cell::Empty),
+            Output::Column(_) | Output::Key => unreachable!("not an aggregate"),
+        }
+    }
+}
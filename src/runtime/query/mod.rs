@@ -0,0 +1,434 @@
+//! Read-only `select` queries over the ledger and the actors.
+//!
+//! A [query][crate::syntax::ast::Query] is first [planned][super::Runtime::plan],
+//! which checks it against the table it reads
+//! like [`super::Runtime::repr`] checks any other statement,
+//! and then [executed][super::Runtime::select] into a [`Table`].
+//!
+//! [`Table`]: crate::ext::table::Table
+
+mod exec;
+mod plan;
+
+use std::str::FromStr;
+
+pub use plan::Plan;
+
+use crate::{
+    aux::{Owned, Stack},
+    ext::{Date, Gtin, Money, Natural},
+};
+
+use super::{
+    State,
+    cmd::Name,
+    model::{Product, Transfer},
+};
+
+/// A table a query can read from.
+#[derive(Stack!)]
+pub enum Source {
+    /// Payments that weren't voided, in the order they happened.
+    Payments,
+    /// Deliveries that weren't voided, in the order they happened.
+    Deliveries,
+    Entities,
+    Concepts,
+    Objects,
+}
+
+impl Source {
+    pub const ALL: [Source; 5] = [
+        Source::Payments,
+        Source::Deliveries,
+        Source::Entities,
+        Source::Concepts,
+        Source::Objects,
+    ];
+
+    /// How it's written in source code.
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Payments => "payments",
+            Source::Deliveries => "deliveries",
+            Source::Entities => "entities",
+            Source::Concepts => "concepts",
+            Source::Objects => "objects",
+        }
+    }
+
+    /// Names and kinds of its columns,
+    /// in the same order as in [its rows][State::rows].
+    pub fn columns(self) -> &'static [(&'static str, Kind)] {
+        use Kind as K;
+        match self {
+            Source::Payments => &[
+                ("number", K::Natural),
+                ("date", K::Date),
+                ("from", K::Entity),
+                ("to", K::Entity),
+                ("amount", K::Money),
+                ("label", K::Text),
+                ("memo", K::Text),
+                ("tags", K::Tags),
+            ],
+            Source::Deliveries => &[
+                ("number", K::Natural),
+                ("date", K::Date),
+                ("from", K::Entity),
+                ("to", K::Entity),
+                ("product", K::Product),
+                ("qty", K::Natural),
+                ("price", K::Money),
+                ("share", K::Money),
+                ("label", K::Text),
+                ("memo", K::Text),
+                ("tags", K::Tags),
+            ],
+            Source::Entities => &[("name", K::Text)],
+            Source::Concepts => &[
+                ("name", K::Text),
+                ("price", K::Money),
+                ("gtin", K::Gtin),
+                ("category", K::Text),
+                ("tags", K::Tags),
+            ],
+            Source::Objects => &[("name", K::Text), ("parent", K::Text)],
+        }
+    }
+
+    /// Position of the column called `name` in a row.
+    pub fn column(self, name: &str) -> Option<usize> {
+        self.columns()
+            .iter()
+            .position(|(column, _)| *column == name)
+    }
+}
+
+impl FromStr for Source {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|source| source.name() == name)
+            .ok_or(())
+    }
+}
+
+/// What a column holds.
+#[derive(Stack!)]
+pub enum Kind {
+    Text,
+    /// The current name of an entity.
+    /// Names compared with it need to be ones of entities,
+    /// so aliases work and typos are caught.
+    Entity,
+    /// The name of a delivered object,
+    /// or of its concept if it has none.
+    /// Names compared with it are looked up like entity names,
+    /// objects before concepts.
+    Product,
+    Money,
+    Natural,
+    Date,
+    Gtin,
+    /// Names such as the [tags of a transfer][Transfer::tags].
+    /// `=` checks whether the given one is among them.
+    Tags,
+}
+
+impl Kind {
+    /// What a value compared with a column of this kind needs to be.
+    pub fn expected(self) -> &'static str {
+        match self {
+            Kind::Text => "a name",
+            Kind::Entity => "an entity",
+            Kind::Product => "a product",
+            Kind::Money => "an amount of money",
+            Kind::Natural => "a natural number",
+            Kind::Date => "a date",
+            Kind::Gtin => "a GTIN",
+            Kind::Tags => "a tag",
+        }
+    }
+}
+
+/// One value in a row.
+///
+/// Cells of different variants are never compared with each other,
+/// except that an empty one sorts first.
+#[derive(Owned!)]
+pub enum Cell {
+    /// Nothing given, e.g. a transfer without label.
+    Empty,
+    Text(String),
+    Money(Money),
+    Natural(Natural),
+    Date(Date),
+    Gtin(Gtin),
+    Tags(Vec<Name>),
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Cell::Empty, Into::into)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.to_owned())
+    }
+}
+
+impl From<Date> for Cell {
+    fn from(date: Date) -> Self {
+        Cell::Date(date)
+    }
+}
+
+impl From<Gtin> for Cell {
+    fn from(gtin: Gtin) -> Self {
+        Cell::Gtin(gtin)
+    }
+}
+
+impl From<&Money> for Cell {
+    fn from(money: &Money) -> Self {
+        Cell::Money(money.clone())
+    }
+}
+
+impl State {
+    /// Every row of `source`,
+    /// with the cells in the same order as [its columns][Source::columns].
+    pub fn rows(&self, source: Source) -> Vec<Vec<Cell>> {
+        let transfers = || {
+            self.ledger
+                .iter()
+                .enumerate()
+                .filter(|(_, transfer)| !transfer.is_voided())
+        };
+        let tags = |transfer: &Transfer| {
+            Cell::Tags(transfer.tags().into_iter().map(str::to_owned).collect())
+        };
+
+        match source {
+            Source::Payments => transfers()
+                .filter_map(|(idx, transfer)| match transfer {
+                    Transfer::Pay(pay) => Some(vec![
+                        Cell::Natural((idx + 1).into()),
+                        pay.date.into(),
+                        pay.who.source().name().into(),
+                        pay.who.target().name().into(),
+                        (&pay.amount).into(),
+                        pay.label.as_deref().into(),
+                        pay.memo.as_deref().into(),
+                        tags(transfer),
+                    ]),
+                    Transfer::Deliver(_) => None,
+                })
+                .collect(),
+            Source::Deliveries => transfers()
+                .filter_map(|(idx, transfer)| match transfer {
+                    Transfer::Deliver(deliver) => Some(vec![
+                        Cell::Natural((idx + 1).into()),
+                        deliver.date.into(),
+                        deliver.who.source().name().into(),
+                        deliver.who.target().name().into(),
+                        product_name(&deliver.product).into(),
+                        Cell::Natural(deliver.qty.clone()),
+                        (&deliver.price).into(),
+                        (&transfer.shifted()).into(),
+                        deliver.label.as_deref().into(),
+                        deliver.memo.as_deref().into(),
+                        tags(transfer),
+                    ]),
+                    Transfer::Pay(_) => None,
+                })
+                .collect(),
+            Source::Entities => self
+                .entities
+                .values()
+                .map(|entity| vec![entity.name().into()])
+                .collect(),
+            Source::Concepts => self
+                .concepts
+                .values()
+                .map(|concept| {
+                    vec![
+                        concept.name().into(),
+                        concept.default_price().into(),
+                        concept.gtin().into(),
+                        concept.category().into(),
+                        Cell::Tags(concept.tags().to_vec()),
+                    ]
+                })
+                .collect(),
+            Source::Objects => self
+                .objects
+                .values()
+                .map(|object| {
+                    vec![
+                        object.name().into(),
+                        object.parent().map(|parent| parent.name()).into(),
+                    ]
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The object's name, or the name of its concept if it has none.
+fn product_name(product: &Product) -> Option<&str> {
+    match product {
+        Product::Concept(concept) => Some(concept.name()),
+        Product::Object(object) => object.name().or(object.parent().map(|c| c.name())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Runtime, eval,
+        runtime::error::{Query, Repr, UnknownActor},
+        syntax::ast::Script,
+    };
+
+    fn select(rt: &Runtime, src: &str) -> Result<String, Repr> {
+        let script = Script::parse(src).unwrap();
+        let query = script.0[0].node.query.clone().unwrap();
+        Ok(rt.select(&rt.plan(query)?).to_string())
+    }
+
+    fn ledger() -> Runtime {
+        eval(
+            "
+            create entity { A; B; Store }
+            create concept Mate price=1.50€ tags=drinks
+            create concept Soap price=2€ gtin=12345678
+            create object Bottle parent=Mate
+            deliver Mate from=Store to=B date=2025-01-03
+            deliver Mate from=Store to=B date=2025-01-20 price=3€
+            deliver Bottle from=Store to=B date=2025-02-01 split=1:1
+            deliver Soap from=Store to=A date=2025-02-02
+            pay 5€ from=B to=Store date=2025-02-10 tags=rent memo=\"for March\"
+            pay 1€ from=A to=B
+            pay 9€ from=A to=B as=oops
+            void oops
+            alias Bea = B
+            ",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn aggregate() {
+        let rt = ledger();
+        assert_eq!(
+            select(
+                &rt,
+                "select sum(price) from deliveries where to = Bea and product = Mate group by month"
+            )
+            .unwrap(),
+            concat!("month    sum(price)\n", "2025-01  4.50 €\n",)
+        );
+        assert_eq!(
+            select(
+                &rt,
+                "select to, count(*), sum(share), max(date) from deliveries group by to order by count(*) desc"
+            )
+            .unwrap(),
+            concat!(
+                "to  count(*)  sum(share)  max(date)\n",
+                "B   3         5.25 €      2025-02-01\n",
+                "A   1         2.00 €      2025-02-02\n",
+            )
+        );
+        // voided ones aren't there at all
+        assert_eq!(
+            select(&rt, "select count(*), sum(amount) from payments").unwrap(),
+            "count(*)  sum(amount)\n2         6.00 €\n"
+        );
+        assert_eq!(
+            select(&rt, "select count(label) from payments where to = Store").unwrap(),
+            "count(label)\n0\n"
+        );
+    }
+
+    #[test]
+    fn rows() {
+        let rt = ledger();
+        assert_eq!(
+            select(&rt, "select * from payments where tags = rent").unwrap(),
+            concat!(
+                "number  date        from  to     amount  label  memo       tags\n",
+                "5       2025-02-10  B     Store  5.00 €         for March  rent\n",
+            )
+        );
+        assert_eq!(
+            select(
+                &rt,
+                "select number, product from deliveries where date >= 2025-01-20 and price < 3€"
+            )
+            .unwrap(),
+            "number  product\n3       Bottle\n4       Soap\n"
+        );
+        assert_eq!(
+            select(
+                &rt,
+                "select name, gtin from concepts order by name desc limit 1"
+            )
+            .unwrap(),
+            "name  gtin\nSoap  12345678\n"
+        );
+        assert_eq!(
+            select(&rt, "select name from concepts where category != kiosk").unwrap(),
+            "name\nMate\nSoap\n"
+        );
+    }
+
+    #[test]
+    fn planning() {
+        let rt = ledger();
+        let err = |src| select(&rt, src).unwrap_err();
+
+        assert!(matches!(
+            err("select * from transfers"),
+            Repr::Query(Query::UnknownTable(_))
+        ));
+        assert!(matches!(
+            err("select amount from deliveries"),
+            Repr::Query(Query::UnknownColumn { .. })
+        ));
+        assert!(matches!(
+            err("select from, sum(amount) from payments"),
+            Repr::Query(Query::Ungrouped(_))
+        ));
+        assert!(matches!(
+            err("select sum(label) from payments"),
+            Repr::Query(Query::Inapplicable(_))
+        ));
+        assert!(matches!(
+            err("select count(*) from entities group by month"),
+            Repr::Query(Query::Ungroupable(_))
+        ));
+        assert!(matches!(
+            err("select * from payments where amount = 5"),
+            Repr::Query(Query::Type { .. })
+        ));
+        assert!(matches!(
+            err("select * from payments where tags > rent"),
+            Repr::Query(Query::Unordered(_))
+        ));
+        assert!(matches!(
+            err("select * from payments where from = C"),
+            Repr::UnknownActor(UnknownActor::Entity(_))
+        ));
+        assert!(matches!(
+            err("select from from payments order by to"),
+            Repr::Query(Query::NotSelected(_))
+        ));
+    }
+}
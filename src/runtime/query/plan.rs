@@ -0,0 +1,258 @@
+use crate::{
+    Runtime,
+    aux::{Owned, Stack},
+    runtime::{
+        cmd::every::Period,
+        error::{self, UnknownActor, UnknownProductName},
+    },
+    syntax::ast::{self, Column, Compare, Ident, Value},
+};
+
+use super::{Cell, Kind, Source};
+
+/// A [query][ast::Query] checked against the table it reads,
+/// with every column resolved to its position in a row.
+#[derive(Owned!)]
+pub struct Plan {
+    pub(super) source: Source,
+    /// All of these need to hold for a row to be read.
+    pub(super) filter: Vec<Filter>,
+    /// What rows are grouped by, if by anything in particular.
+    pub(super) group: Option<Key>,
+    /// Whether rows are put into groups at all,
+    /// which is also the case if only aggregates are selected.
+    pub(super) grouped: bool,
+    pub(super) outputs: Vec<Output>,
+    pub(super) header: Vec<String>,
+    /// Which output to sort by, and whether descending.
+    pub(super) order: Option<(usize, bool)>,
+    pub(super) limit: Option<usize>,
+}
+
+/// Only rows with the cell at `column` comparing to `value` as given.
+#[derive(Owned!)]
+pub(super) struct Filter {
+    pub column: usize,
+    pub compare: Compare,
+    pub value: Cell,
+}
+
+#[derive(Stack!)]
+pub(super) enum Key {
+    Column(usize),
+    /// The period the date at this position is in.
+    Period {
+        period: Period,
+        date: usize,
+    },
+}
+
+/// What one cell of the result is.
+#[derive(Stack!)]
+pub(super) enum Output {
+    Column(usize),
+    /// What the group is grouped by.
+    Key,
+    /// How many rows are in the group,
+    /// or how many of them aren't empty at this position.
+    Count(Option<usize>),
+    /// Of a column that is either [`Kind::Money`] or [`Kind::Natural`].
+    Sum(usize, Kind),
+    Min(usize),
+    Max(usize),
+}
+
+impl Runtime {
+    /// Checks `query` against the table it reads,
+    /// resolving its columns and the values they're compared with.
+    ///
+    /// Once grouped by or aggregated,
+    /// only what is grouped by can be selected as-is.
+    /// If it isn't, it's shown in front of the aggregates anyway.
+    pub fn plan(&self, query: ast::Query) -> Result<Plan, error::Repr> {
+        let ast::Query {
+            columns,
+            from,
+            filter,
+            group,
+            order,
+            limit,
+        } = query;
+
+        let source: Source = from
+            .0
+            .parse()
+            .map_err(|()| error::Query::UnknownTable(from.0.to_owned()))?;
+        let column = |name: &str| {
+            source
+                .column(name)
+                .ok_or_else(|| error::Query::UnknownColumn {
+                    table: source.name(),
+                    column: name.to_owned(),
+                })
+        };
+
+        let filter = filter
+            .into_iter()
+            .map(|condition| self.plan_filter(source, condition))
+            .collect::<Result<_, _>>()?;
+
+        let key = group
+            .map(|Ident(name)| match (source.column(name), name.parse()) {
+                (Some(idx), _) => Ok(Key::Column(idx)),
+                (None, Ok(period)) => match source.column("date") {
+                    Some(date) => Ok(Key::Period { period, date }),
+                    None => Err(error::Query::Ungroupable(name.to_owned())),
+                },
+                (None, Err(())) => Err(error::Query::Ungroupable(name.to_owned())),
+            })
+            .transpose()?;
+        let grouped = key.is_some()
+            || columns
+                .iter()
+                .any(|c| matches!(c, Column::Aggregate { .. }));
+
+        let mut outputs = Vec::new();
+        let mut header = Vec::new();
+        for selected in &columns {
+            let output = match selected {
+                Column::All if grouped => {
+                    return Err(error::Query::Ungrouped("*".to_owned()).into());
+                }
+                Column::All => {
+                    for (idx, (name, _)) in source.columns().iter().enumerate() {
+                        outputs.push(Output::Column(idx));
+                        header.push(name.to_string());
+                    }
+                    continue;
+                }
+                Column::Field(Ident(name)) if group.is_some_and(|Ident(by)| by == *name) => {
+                    Output::Key
+                }
+                Column::Field(Ident(name)) => {
+                    let idx = column(name)?;
+                    if grouped {
+                        return Err(error::Query::Ungrouped(name.to_string()).into());
+                    }
+                    Output::Column(idx)
+                }
+                Column::Aggregate { func, of } => {
+                    let of = of.map(|Ident(name)| column(name)).transpose()?;
+                    let kind = of.map(|idx| source.columns()[idx].1);
+                    match (func.0, of, kind) {
+                        ("count", of, _) => Output::Count(of),
+                        ("sum", Some(idx), Some(kind @ (Kind::Money | Kind::Natural))) => {
+                            Output::Sum(idx, kind)
+                        }
+                        ("min", Some(idx), Some(kind)) if kind != Kind::Tags => Output::Min(idx),
+                        ("max", Some(idx), Some(kind)) if kind != Kind::Tags => Output::Max(idx),
+                        ("sum" | "min" | "max", ..) => {
+                            return Err(error::Query::Inapplicable(selected.to_string()).into());
+                        }
+                        (func, ..) => {
+                            return Err(error::Query::UnknownFunction(func.to_owned()).into());
+                        }
+                    }
+                }
+            };
+            outputs.push(output);
+            header.push(selected.to_string());
+        }
+        if let Some(Ident(by)) = group
+            && !outputs.contains(&Output::Key)
+        {
+            outputs.insert(0, Output::Key);
+            header.insert(0, by.to_owned());
+        }
+
+        let order = order
+            .map(|ast::Order { by, descending }| {
+                let by = by.to_string();
+                match header.iter().position(|name| *name == by) {
+                    Some(idx) => Ok((idx, descending)),
+                    None => Err(error::Query::NotSelected(by)),
+                }
+            })
+            .transpose()?;
+
+        Ok(Plan {
+            source,
+            filter,
+            group: key,
+            grouped,
+            outputs,
+            header,
+            order,
+            limit: limit.map(|limit| usize::try_from(&limit).unwrap_or(usize::MAX)),
+        })
+    }
+
+    /// Resolves the value to a cell of the same kind as the column.
+    /// Entities and products are looked up like in any other statement,
+    /// so they're compared by their current name.
+    fn plan_filter(
+        &self,
+        source: Source,
+        ast::Condition {
+            field,
+            compare,
+            value,
+        }: ast::Condition,
+    ) -> Result<Filter, error::Repr> {
+        let Ident(name) = field;
+        let column = source
+            .column(name)
+            .ok_or_else(|| error::Query::UnknownColumn {
+                table: source.name(),
+                column: name.to_owned(),
+            })?;
+        let kind = source.columns()[column].1;
+        if kind == Kind::Tags && !matches!(compare, Compare::Eq | Compare::Ne) {
+            return Err(error::Query::Unordered(name.to_owned()).into());
+        }
+
+        let text = match &value {
+            Value::Name(ast::Name(Ident(text))) => Some(text.to_string()),
+            Value::Quoted(text) => Some(text.clone()),
+            _ => None,
+        };
+        let value = match (kind, value, text) {
+            (Kind::Text | Kind::Tags, _, Some(text)) => Cell::Text(text),
+            (Kind::Entity, _, Some(text)) => {
+                let entity = self.get_entity(&text).map_err(UnknownActor::from)?;
+                entity.name().into()
+            }
+            (Kind::Product, Value::Gtin(gtin), _) => {
+                let concept = self
+                    .get_concept_by_gtin(&gtin)
+                    .map_err(UnknownActor::from)?;
+                concept.name().into()
+            }
+            (Kind::Product, _, Some(text)) => match self.get_object(&text) {
+                Ok(object) => object.name().into(),
+                Err(_) => match self.get_concept(&text) {
+                    Ok(concept) => concept.name().into(),
+                    Err(_) => return Err(UnknownActor::from(UnknownProductName(text)).into()),
+                },
+            },
+            (Kind::Money, Value::Money(money), _) => Cell::Money(money),
+            (Kind::Natural, Value::Natural(n), _) => Cell::Natural(n),
+            (Kind::Natural, Value::Gtin(n), _) => Cell::Natural(n.get().into()),
+            (Kind::Date, Value::Date(date), _) => Cell::Date(date),
+            (Kind::Gtin, Value::Gtin(gtin), _) => Cell::Gtin(gtin),
+            _ => {
+                return Err(error::Query::Type {
+                    column: name.to_owned(),
+                    expected: kind.expected(),
+                }
+                .into());
+            }
+        };
+
+        Ok(Filter {
+            column,
+            compare,
+            value,
+        })
+    }
+}
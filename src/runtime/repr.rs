@@ -0,0 +1,662 @@
+//! Represent parsed [statements][Stmt] as [commands][Command].
+//!
+//! This is where the semantic requirements are enforced,
+//! e.g. that every actor has been created before it is used.
+//! Which arguments a command takes at all is up to its [schema].
+
+use crate::{
+    ext::{Balance, Date, Gtin, Integer, Money, Natural},
+    syntax::ast::{self, Args, Stmt, Value},
+};
+
+use super::{
+    Runtime,
+    cmd::{self, Command, receipt},
+    error::{self, UnknownActor, UnknownProductName},
+    model::{self, Actor, Dir, Entity, Product, Rounding, Split},
+    schema::{self, Bound, Type},
+};
+
+impl Runtime {
+    /// Checks if the statement makes sense in the current state
+    /// and converts it into a [`Command`] if so.
+    ///
+    /// After this, [`Runtime::fulfil`] can only fail
+    /// for the reasons listed there,
+    /// not because the command doesn't fit the state.
+    pub fn repr(&self, stmt: Stmt) -> Result<Command, error::Repr> {
+        let Stmt {
+            cmd,
+            args,
+            block,
+            query,
+            ..
+        } = stmt;
+
+        use ast::Command as C;
+        let args = match cmd {
+            C::Select => {
+                let query = query.expect("parser to give every `select` a query");
+                return Ok(Command::Select(self.plan(query)?));
+            }
+            C::Whatif | C::Every => return Err(error::Nested(cmd).into()),
+            C::Alias => alias_args(args),
+            _ => args,
+        };
+        let (schema, mut args) = schema::bind(&cmd, args)?;
+
+        let cmd = match cmd {
+            C::Create => Command::Create(self.repr_create(schema.form, &mut args)?),
+            C::Pay => Command::Pay(cmd::Pay {
+                amount: args.req("amount")?.money("amount")?,
+                who: self.repr_dir(&mut args)?,
+                date: args.opt("date").map(|v| v.date("date")).transpose()?,
+                label: self.repr_label(&mut args)?,
+                memo: args.opt("memo").map(|v| v.text("memo")).transpose()?,
+                tags: args
+                    .opt("tags")
+                    .map(|v| v.names("tags"))
+                    .transpose()?
+                    .unwrap_or_default(),
+                voided: false,
+            }),
+            C::Deliver => Command::Deliver(self.repr_deliver(&mut args)?),
+            C::Receipt => Command::Receipt(self.repr_receipt(&mut args, block)?),
+            C::Void => Command::Void(self.repr_void(args.req("transfer")?)?),
+            C::Balance => Command::Balance(cmd::Balance {
+                between: self.repr_dir(&mut args)?,
+                as_of: args.opt("as-of").map(|v| v.date("as-of")).transpose()?,
+            }),
+            C::Alias => Command::Alias(self.repr_alias(&mut args)?),
+            C::Rename => Command::Rename(self.repr_rename(&mut args)?),
+            C::Assert => Command::Assert(self.repr_assert(schema.form, &mut args)?),
+            C::Tag => Command::Tag(cmd::Tag {
+                concept: self.repr_concept(args.req("concept")?)?,
+                tag: args.req("tag")?.name("tag")?.to_owned(),
+            }),
+            C::Budget if schema.form == Some("report") => Command::BudgetReport,
+            C::Budget => Command::Budget(self.repr_budget(&mut args)?),
+            C::Report => Command::Report(self.repr_report(&mut args)?),
+            C::History => Command::History(cmd::History {
+                of: args.opt("of").map(|v| self.repr_entity(v)).transpose()?,
+                tag: args
+                    .opt("tag")
+                    .map(|v| v.name("tag").map(str::to_owned))
+                    .transpose()?,
+            }),
+            C::Help => Command::Help(repr_help(&mut args)?),
+            C::Begin => Command::Begin,
+            C::Commit => Command::Commit,
+            C::Select | C::Whatif | C::Every => unreachable!("handled above"),
+        };
+
+        Ok(cmd)
+    }
+
+    fn repr_create(
+        &self,
+        kind: Option<&str>,
+        args: &mut Bound,
+    ) -> Result<cmd::Create, error::Repr> {
+        let name = args.req("name")?.name("name")?.to_owned();
+
+        let create = match kind {
+            Some("entity") => cmd::Create::Entity(cmd::Entity { name }),
+            Some("concept") => cmd::Create::Concept(cmd::Concept {
+                name,
+                default_price: args.opt("price").map(|v| v.money("price")).transpose()?,
+                gtin: args.opt("gtin").map(|v| v.gtin("gtin")).transpose()?,
+                category: args
+                    .opt("category")
+                    .map(|v| v.name("category").map(str::to_owned))
+                    .transpose()?,
+                tags: args
+                    .opt("tags")
+                    .map(|v| v.names("tags"))
+                    .transpose()?
+                    .unwrap_or_default(),
+            }),
+            Some("object") => cmd::Create::Object(cmd::Object {
+                name,
+                parent: args
+                    .opt("parent")
+                    .map(|v| self.repr_concept(v))
+                    .transpose()?,
+            }),
+            _ => unreachable!("schema to only have these forms"),
+        };
+
+        Ok(create)
+    }
+
+    /// `(<kind>) <new> = <existing>`
+    fn repr_alias(&self, args: &mut Bound) -> Result<cmd::Alias, error::Repr> {
+        let kind = args.opt("kind");
+        let kind = kind.as_ref().map(|kind| kind.name("kind")).transpose()?;
+        let existing = args.req("existing")?;
+        let actor = self.repr_actor(kind, existing.name("existing")?)?;
+        let name = self.repr_new_name(&actor, args.req("name")?.name("name")?)?;
+
+        Ok(cmd::Alias { actor, name })
+    }
+
+    /// `(<kind>) <old> to <new>`
+    fn repr_rename(&self, args: &mut Bound) -> Result<cmd::Rename, error::Repr> {
+        let kind = args.opt("kind");
+        let kind = kind.as_ref().map(|kind| kind.name("kind")).transpose()?;
+        let from = args.req("name")?;
+        let from = from.name("name")?;
+        let actor = self.repr_actor(kind, from)?;
+        let to = self.repr_new_name(&actor, args.req("to")?.name("to")?)?;
+
+        Ok(cmd::Rename {
+            actor,
+            from: from.to_owned(),
+            to,
+        })
+    }
+
+    /// Looks up `name` as actor of `kind`,
+    /// or of whichever kind has an actor called that if there's no `kind`.
+    fn repr_actor(&self, kind: Option<&str>, name: &str) -> Result<Actor, error::Repr> {
+        let entity = || self.get_entity(name).cloned().map(Actor::Entity);
+        let concept = || self.get_concept(name).cloned().map(Actor::Concept);
+        let object = || self.get_object(name).cloned().map(Actor::Object);
+
+        let actor = match kind {
+            Some("entity") => entity().map_err(UnknownActor::from)?,
+            Some("concept") => concept().map_err(UnknownActor::from)?,
+            Some("object") => object().map_err(UnknownActor::from)?,
+            Some(_) => return Err(unknown_kind().into()),
+            None => {
+                let mut found = [entity().ok(), concept().ok(), object().ok()]
+                    .into_iter()
+                    .flatten();
+                match (found.next(), found.next()) {
+                    (Some(actor), None) => actor,
+                    (Some(_), Some(_)) => {
+                        return Err(error::Naming::Ambiguous(name.to_owned()).into());
+                    }
+                    (None, _) => {
+                        return Err(UnknownActor::from(error::UnknownName(name.to_owned())).into());
+                    }
+                }
+            }
+        };
+        Ok(actor)
+    }
+
+    /// Makes sure no other actor of the same kind as `actor` is called `name` yet.
+    fn repr_new_name(&self, actor: &Actor, name: &str) -> Result<cmd::Name, error::Repr> {
+        let taken = match actor {
+            Actor::Entity(_) => self.get_entity(name).is_ok(),
+            Actor::Concept(_) => self.get_concept(name).is_ok(),
+            Actor::Object(_) => self.get_object(name).is_ok(),
+        };
+        if taken {
+            return Err(error::Naming::Taken {
+                name: name.to_owned(),
+                kind: actor.kind(),
+            }
+            .into());
+        }
+        Ok(name.to_owned())
+    }
+
+    fn repr_deliver(&self, args: &mut Bound) -> Result<cmd::Deliver, error::Repr> {
+        let product = self.repr_product(args.req("product")?)?;
+        let price = match args.opt("price") {
+            Some(price) => price.money("price")?,
+            None => product.default_price()?.clone(),
+        };
+        let who = self.repr_dir(args)?;
+        let split = match args.opt("split") {
+            Some(split) => {
+                let ast::Split { from, to } = split.split("split")?;
+                Split::new(from, to)?
+            }
+            None => Split::default(),
+        };
+        let rounding = args
+            .opt("round")
+            .map(|v| v.rounding("round"))
+            .transpose()?
+            .unwrap_or_default();
+
+        let date = args.opt("date").map(|v| v.date("date")).transpose()?;
+        let label = self.repr_label(args)?;
+        let memo = args.opt("memo").map(|v| v.text("memo")).transpose()?;
+        let tags = args.opt("tags").map(|v| v.names("tags")).transpose()?;
+
+        Ok(cmd::Deliver {
+            who,
+            product,
+            price,
+            split,
+            rounding,
+            qty: 1u8.into(),
+            date,
+            label,
+            memo,
+            tags: tags.unwrap_or_default(),
+            voided: false,
+        })
+    }
+
+    fn repr_receipt(
+        &self,
+        args: &mut Bound,
+        block: Option<ast::Block>,
+    ) -> Result<cmd::Receipt, error::Repr> {
+        let payer = self.repr_entity(args.req("from")?)?;
+        let at = match args.opt("at") {
+            Some(at) => Some(Dir::new(payer.clone(), self.repr_entity(at)?)?),
+            None => None,
+        };
+        let stated = args.opt("total").map(|v| v.money("total")).transpose()?;
+        let date = args.opt("date").map(|v| v.date("date")).transpose()?;
+        let label = self.repr_label(args)?;
+        let memo = args.opt("memo").map(|v| v.text("memo")).transpose()?;
+        let tags = args.opt("tags").map(|v| v.names("tags")).transpose()?;
+
+        let lines = block
+            .map(|ast::Block(lines)| lines)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|line| self.repr_receipt_line(line))
+            .collect::<Result<_, _>>()?;
+
+        let receipt = cmd::Receipt {
+            payer,
+            at,
+            lines,
+            date,
+            label,
+            memo,
+            tags: tags.unwrap_or_default(),
+        };
+
+        if let Some(stated) = stated {
+            let sum = receipt.total();
+            if stated != sum {
+                return Err(error::TotalMismatch { stated, sum }.into());
+            }
+        }
+
+        Ok(receipt)
+    }
+
+    /// `<product> (qty) (price) (for <consumers>...)`
+    fn repr_receipt_line(&self, args: Args) -> Result<receipt::Line, error::Repr> {
+        let mut item = schema::RECEIPT_LINE.bind(args)?;
+        let product = self.repr_product(item.req("product")?)?;
+        let qty = match item.opt("qty") {
+            Some(qty) => qty.natural("qty")?,
+            None => 1u8.into(),
+        };
+        let price = match item.opt("price") {
+            Some(price) => price.money("price")?,
+            None => product.default_price()?.clone(),
+        };
+
+        let consumers = match item.opt("for").map(|given| given.0) {
+            Some(Value::List(values)) => values,
+            Some(value) => vec![value],
+            None => Vec::new(),
+        };
+        let mut entities = Vec::new();
+        for consumer in consumers {
+            let entity = self.repr_entity(Given(consumer))?;
+            if !entities.contains(&entity) {
+                entities.push(entity);
+            }
+        }
+
+        Ok(receipt::Line {
+            product,
+            qty,
+            price,
+            consumers: entities,
+        })
+    }
+
+    /// `<subject> <args>... = <expected>`
+    fn repr_assert(
+        &self,
+        subject: Option<&str>,
+        args: &mut Bound,
+    ) -> Result<cmd::Assert, error::Repr> {
+        let assert = match subject {
+            Some("balance") => cmd::Assert::Balance {
+                between: self.repr_dir(args)?,
+                expected: args.req("expected")?.balance("expected")?,
+            },
+            Some("net") => cmd::Assert::Net {
+                of: self.repr_entity(args.req("entity")?)?,
+                expected: args.req("expected")?.balance("expected")?,
+            },
+            Some("inventory") => cmd::Assert::Inventory {
+                of: self.repr_entity(args.req("entity")?)?,
+                product: self.repr_product(args.req("product")?)?,
+                expected: args.req("expected")?.integer("expected")?,
+            },
+            Some("price") => cmd::Assert::Price {
+                concept: self.repr_concept(args.req("concept")?)?,
+                expected: args.req("expected")?.money("expected")?,
+            },
+            _ => unreachable!("schema to only have these forms"),
+        };
+
+        Ok(assert)
+    }
+
+    /// `<entity> <category> <money> per <period>`,
+    /// `budget report` is told apart by its schema.
+    fn repr_budget(&self, args: &mut Bound) -> Result<cmd::Budget, error::Repr> {
+        Ok(cmd::Budget {
+            of: self.repr_entity(args.req("entity")?)?,
+            category: args.req("category")?.name("category")?.to_owned(),
+            limit: args.req("limit")?.money("limit")?,
+            per: args.req("per")?.period("per")?,
+        })
+    }
+
+    /// `spending (by <grouping>) (per <period> of <entity> (during <dates>)) (format <format>)`,
+    /// either `by` or `per`.
+    fn repr_report(&self, args: &mut Bound) -> Result<cmd::Report, error::Repr> {
+        use cmd::report::{MAX_PERIODS, Periodic, Spending};
+
+        let spending = match args.opt("per") {
+            Some(per) => {
+                let periodic = Periodic {
+                    of: self.repr_entity(args.req("of")?)?,
+                    per: per.period("per")?,
+                    during: args.opt("during").map(|v| v.dates("during")).transpose()?,
+                };
+                if !periodic.is_bounded() {
+                    return Err(error::Arg::Type {
+                        key: "during".to_owned(),
+                        expected: format!("at most {MAX_PERIODS} periods long"),
+                    }
+                    .into());
+                }
+                Spending::Per(periodic)
+            }
+            None => Spending::By(args.req("by")?.grouping("by")?),
+        };
+        let format = args
+            .opt("format")
+            .map(|v| v.format("format"))
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(cmd::Report { spending, format })
+    }
+
+    /// `<period> from <date> (until <date>)` of an `every` statement.
+    pub(super) fn repr_schedule(&self, args: Args) -> Result<cmd::Schedule, error::Repr> {
+        let (_, mut args) = schema::bind(&ast::Command::Every, args)?;
+        Ok(cmd::Schedule {
+            period: args.req("period")?.period("period")?,
+            from: args.req("from")?.date("from")?,
+            until: args.opt("until").map(|v| v.date("until")).transpose()?,
+        })
+    }
+
+    /// Takes the `as` argument, making sure no transfer has that label yet.
+    fn repr_label(&self, args: &mut Bound) -> Result<Option<cmd::Name>, error::Repr> {
+        let Some(label) = args.opt("as") else {
+            return Ok(None);
+        };
+        let label = label.name("as")?;
+
+        if self.ledger.iter().any(|t| t.label() == Some(label)) {
+            return Err(error::Label::Taken(label.to_owned()).into());
+        }
+        Ok(Some(label.to_owned()))
+    }
+
+    /// Finds all transfers with the label `value`,
+    /// or the one at that position in the ledger counting from 1
+    /// if it's a number.
+    fn repr_void(&self, value: Given<'_>) -> Result<cmd::Void, error::Repr> {
+        let given = value.0.to_string();
+        let transfers: Vec<usize> = match value.0 {
+            Value::Natural(position) => usize::try_from(position)
+                .ok()
+                .and_then(|position| position.checked_sub(1))
+                .filter(|idx| *idx < self.ledger.len())
+                .into_iter()
+                .collect(),
+            _ => {
+                let label = value.name("transfer")?;
+                (0..self.ledger.len())
+                    .filter(|idx| self.ledger[*idx].label() == Some(label))
+                    .collect()
+            }
+        };
+
+        if transfers.is_empty() {
+            return Err(error::Label::Unknown(given).into());
+        }
+        if transfers.iter().any(|idx| self.ledger[*idx].is_voided()) {
+            return Err(error::Label::Voided(given).into());
+        }
+        Ok(cmd::Void { transfers })
+    }
+
+    /// Looks up the `from` and `to` arguments as entities.
+    fn repr_dir(&self, args: &mut Bound) -> Result<Dir, error::Repr> {
+        let source = self.repr_entity(args.req("from")?)?;
+        let target = self.repr_entity(args.req("to")?)?;
+        Ok(Dir::new(source, target)?)
+    }
+
+    fn repr_entity(&self, value: Given<'_>) -> Result<Entity, error::Repr> {
+        let name = value.name("entity")?;
+        let entity = self.get_entity(name).map_err(UnknownActor::from)?;
+        Ok(entity.clone())
+    }
+
+    fn repr_concept(&self, value: Given<'_>) -> Result<model::Concept, error::Repr> {
+        let concept = match value.0 {
+            Value::Gtin(gtin) => self
+                .get_concept_by_gtin(&gtin)
+                .map_err(UnknownActor::from)?,
+            _ => self
+                .get_concept(value.name("concept")?)
+                .map_err(UnknownActor::from)?,
+        };
+        Ok(concept.clone())
+    }
+
+    /// Objects are looked up before concepts.
+    fn repr_product(&self, value: Given<'_>) -> Result<Product, error::Repr> {
+        if let Value::Gtin(_) = value.0 {
+            return Ok(Product::Concept(self.repr_concept(value)?));
+        }
+
+        let name = value.name("product")?;
+        if let Ok(object) = self.get_object(name) {
+            return Ok(Product::Object(object.clone()));
+        }
+        if let Ok(concept) = self.get_concept(name) {
+            return Ok(Product::Concept(concept.clone()));
+        }
+
+        Err(UnknownActor::from(UnknownProductName(name.to_owned())).into())
+    }
+}
+
+fn unknown_kind() -> error::Arg {
+    Given::mismatch("kind", Type::Choice(schema::KINDS))
+}
+
+/// `(<command> (<form>))`
+fn repr_help(args: &mut Bound) -> Result<cmd::Help, error::Repr> {
+    let Some(command) = args.opt("command") else {
+        return Ok(cmd::Help::Overview);
+    };
+    let command = command.name("command")?;
+    let command = ast::Command::ALL
+        .into_iter()
+        .find(|cmd| cmd.keyword() == command)
+        .expect("schema to only accept commands");
+    let form = args.opt("form");
+    let form = form.as_ref().map(|form| form.name("form")).transpose()?;
+    Ok(cmd::Help::on(&command, form)?)
+}
+
+/// Without whitespace before it, the `=` belongs to a named argument,
+/// so `alias Bob=Robert` is split up like `alias Bob = Robert`.
+fn alias_args(Args(args): Args) -> Args {
+    match &args[..] {
+        [ast::Arg::Named { key, value }] => Args(vec![
+            ast::Arg::Pos(Value::Name(ast::Name(*key))),
+            ast::Arg::Equals,
+            ast::Arg::Pos(value.clone()),
+        ]),
+        _ => Args(args),
+    }
+}
+
+impl<'tok> Bound<'tok> {
+    /// Takes the argument for `key`, if it is given.
+    fn opt(&mut self, key: &'static str) -> Option<Given<'tok>> {
+        self.take(key).map(Given)
+    }
+
+    /// Takes the argument for `key`, failing if it is not given.
+    ///
+    /// The schema already checks the required ones,
+    /// this is for those only needed together with others.
+    fn req(&mut self, key: &'static str) -> Result<Given<'tok>, error::Arg> {
+        self.opt(key)
+            .ok_or_else(|| error::Arg::Missing(key.to_owned()))
+    }
+}
+
+/// One argument value, taken out of [`Bound`].
+struct Given<'tok>(Value<'tok>);
+
+impl<'tok> Given<'tok> {
+    fn mismatch(key: &str, expected: Type) -> error::Arg {
+        error::Arg::Type {
+            key: key.to_owned(),
+            expected: expected.expected(),
+        }
+    }
+
+    fn name(&self, key: &str) -> Result<&str, error::Arg> {
+        match &self.0 {
+            Value::Name(ast::Name(ast::Ident(name))) => Ok(name),
+            Value::Quoted(name) => Ok(name),
+            _ => Err(Self::mismatch(key, Type::Name)),
+        }
+    }
+
+    /// One name or several separated by commas.
+    fn names(self, key: &str) -> Result<Vec<cmd::Name>, error::Arg> {
+        let values = match self.0 {
+            Value::List(values) => values,
+            value => vec![value],
+        };
+        values
+            .into_iter()
+            .map(|value| Given(value).name(key).map(str::to_owned))
+            .collect::<Result<_, _>>()
+            .map_err(|_| Self::mismatch(key, Type::Names))
+    }
+
+    /// A quoted string, or a name taken literally.
+    fn text(self, key: &str) -> Result<String, error::Arg> {
+        match self.0 {
+            Value::Quoted(text) => Ok(text),
+            Value::Name(ast::Name(ast::Ident(name))) => Ok(name.to_owned()),
+            _ => Err(Self::mismatch(key, Type::Text)),
+        }
+    }
+
+    fn money(self, key: &str) -> Result<Money, error::Arg> {
+        match self.0 {
+            Value::Money(money) => Ok(money),
+            _ => Err(Self::mismatch(key, Type::Money)),
+        }
+    }
+
+    fn natural(self, key: &str) -> Result<Natural, error::Arg> {
+        match self.0 {
+            Value::Natural(n) => Ok(n),
+            _ => Err(Self::mismatch(key, Type::Natural)),
+        }
+    }
+
+    fn integer(self, key: &str) -> Result<Integer, error::Arg> {
+        match self.0 {
+            Value::Natural(n) => Ok(n.into()),
+            Value::NegativeNatural(n) => Ok(-Integer::from(n)),
+            _ => Err(Self::mismatch(key, Type::Integer)),
+        }
+    }
+
+    fn balance(self, key: &str) -> Result<Balance, error::Arg> {
+        match self.0 {
+            Value::Money(money) => Ok(money.into()),
+            Value::Negative(money) => Ok(Balance(-Integer::from(money.0))),
+            _ => Err(Self::mismatch(key, Type::Balance)),
+        }
+    }
+
+    fn gtin(self, key: &str) -> Result<Gtin, error::Arg> {
+        match self.0 {
+            Value::Gtin(gtin) => Ok(gtin),
+            _ => Err(Self::mismatch(key, Type::Gtin)),
+        }
+    }
+
+    fn date(self, key: &str) -> Result<Date, error::Arg> {
+        match self.0 {
+            Value::Date(date) => Ok(date),
+            _ => Err(Self::mismatch(key, Type::Date)),
+        }
+    }
+
+    /// Days from one to another, the first not after the last.
+    fn dates(self, key: &str) -> Result<(Date, Date), error::Arg> {
+        match self.0 {
+            Value::Dates(ast::Dates { from, to }) if from <= to => Ok((from, to)),
+            _ => Err(Self::mismatch(key, Type::Dates)),
+        }
+    }
+
+    fn period(&self, key: &str) -> Result<cmd::every::Period, error::Arg> {
+        self.name(key)?
+            .parse()
+            .map_err(|()| Self::mismatch(key, Type::Choice(schema::PERIODS)))
+    }
+
+    fn format(&self, key: &str) -> Result<cmd::report::Format, error::Arg> {
+        self.name(key)?
+            .parse()
+            .map_err(|()| Self::mismatch(key, Type::Choice(schema::FORMATS)))
+    }
+
+    fn grouping(&self, key: &str) -> Result<cmd::report::Grouping, error::Arg> {
+        self.name(key)?
+            .parse()
+            .map_err(|()| Self::mismatch(key, Type::Choice(schema::GROUPINGS)))
+    }
+
+    fn rounding(&self, key: &str) -> Result<Rounding, error::Arg> {
+        self.name(key)?
+            .parse()
+            .map_err(|()| Self::mismatch(key, Type::Choice(schema::ROUNDINGS)))
+    }
+
+    fn split(self, key: &str) -> Result<ast::Split, error::Arg> {
+        match self.0 {
+            Value::Split(split) => Ok(split),
+            _ => Err(Self::mismatch(key, Type::Split)),
+        }
+    }
+}
@@ -0,0 +1,1067 @@
+//! What arguments each command takes.
+//!
+//! Every command, or every form of it such as `create entity`,
+//! declares its parameters in a [`Schema`].
+//! [Binding][bind] the arguments of a statement against it
+//! catches unknown, missing, duplicate and mistyped ones
+//! before [`super::repr`] looks at what they mean,
+//! and the same schema is [shown][fmt::Display] as usage text.
+
+use std::{collections::VecDeque, fmt};
+
+use crate::{
+    Map,
+    aux::{Owned, Stack},
+    syntax::ast::{self, Arg, Args, Command, Value},
+};
+
+use super::error;
+
+/// Parameters of one command, or of one form of it.
+#[derive(Owned!)]
+pub struct Schema {
+    pub command: Command,
+    /// Word right after the command selecting this form,
+    /// as `entity` in `create entity`.
+    pub form: Option<&'static str>,
+    pub params: &'static [Param],
+    /// Parameters after a lone `=`, as in `assert` and `alias`.
+    pub expected: &'static [Param],
+    /// What follows the arguments, if anything, only shown in the usage.
+    pub body: Option<&'static str>,
+    /// What it does, in one sentence.
+    pub about: &'static str,
+    /// Statements using it, each of them [binding][Schema::bind] to it.
+    pub examples: &'static [&'static str],
+    /// Whether this is for each line in the block of `command`
+    /// instead of the command itself.
+    pub line: bool,
+}
+
+#[derive(Stack!)]
+pub struct Param {
+    pub name: &'static str,
+    pub ty: Type,
+    pub slot: Slot,
+    pub required: bool,
+    /// What it is for.
+    pub about: &'static str,
+    /// What it is if not given, if that's worth telling.
+    pub default: Option<&'static str>,
+}
+
+/// How an argument is matched to its parameter.
+#[derive(Stack!)]
+pub enum Slot {
+    /// By position among the positional arguments,
+    /// in the order of the parameters.
+    /// An optional one is skipped if the argument is needed for a required one after it,
+    /// or doesn't fit its type but one left after it.
+    /// Can also be named like any other.
+    Pos,
+    /// `key=value`, or `key value` if `key` isn't a value on its own.
+    Named,
+    /// `key` followed by any number of values,
+    /// which are all positional arguments after it.
+    Rest,
+}
+
+/// What an argument needs to be.
+#[derive(Stack!)]
+pub enum Type {
+    Name,
+    /// One name or several separated by commas.
+    Names,
+    /// A quoted string, or a name taken literally.
+    Text,
+    Money,
+    /// Money, possibly negative.
+    Balance,
+    Natural,
+    /// A natural number, possibly negative.
+    Integer,
+    Gtin,
+    Date,
+    /// Days from one to another, as in `2025-01-01..2025-12-31`.
+    Dates,
+    Split,
+    /// The name of an entity.
+    Entity,
+    /// The name or GTIN of a concept.
+    Concept,
+    /// The name of an object or concept, or the GTIN of a concept.
+    Product,
+    /// The label of a transfer, or its position in the ledger.
+    Transfer,
+    /// One of these words.
+    Choice(&'static [&'static str]),
+}
+
+/// Kinds of actors, as in `create <kind>`.
+pub const KINDS: &[&str] = &["entity", "concept", "object"];
+/// As understood by [`super::cmd::every::Period`].
+pub const PERIODS: &[&str] = &["day", "week", "month", "year"];
+/// As understood by [`super::model::Rounding`].
+pub const ROUNDINGS: &[&str] = &super::model::Rounding::NAMES;
+/// As understood by [`super::cmd::report::Grouping`].
+pub const GROUPINGS: &[&str] = &["tag", "category"];
+/// As understood by [`super::cmd::report::Format`].
+pub const FORMATS: &[&str] = &["table", "csv"];
+/// Keywords of every [`Command`].
+pub const COMMANDS: &[&str] = &[
+    "create", "pay", "deliver", "receipt", "void", "balance", "assert", "begin", "commit",
+    "whatif", "every", "budget", "tag", "report", "history", "select", "alias", "rename", "help",
+];
+/// Words selecting a [form][Schema::form] of any command.
+pub const FORMS: &[&str] = &[
+    "entity",
+    "concept",
+    "object",
+    "balance",
+    "net",
+    "inventory",
+    "price",
+    "report",
+    "spending",
+];
+
+impl Param {
+    /// A required positional parameter.
+    pub const fn pos(name: &'static str, ty: Type) -> Self {
+        Self {
+            name,
+            ty,
+            slot: Slot::Pos,
+            required: true,
+            about: "",
+            default: None,
+        }
+    }
+
+    /// An optional named parameter.
+    pub const fn named(name: &'static str, ty: Type) -> Self {
+        Self {
+            name,
+            ty,
+            slot: Slot::Named,
+            required: false,
+            about: "",
+            default: None,
+        }
+    }
+
+    /// An optional parameter taking all positional arguments after it.
+    pub const fn rest(name: &'static str, ty: Type) -> Self {
+        Self {
+            name,
+            ty,
+            slot: Slot::Rest,
+            required: false,
+            about: "",
+            default: None,
+        }
+    }
+
+    pub const fn required(self) -> Self {
+        Self {
+            required: true,
+            ..self
+        }
+    }
+
+    pub const fn optional(self) -> Self {
+        Self {
+            required: false,
+            ..self
+        }
+    }
+
+    pub const fn about(self, about: &'static str) -> Self {
+        Self { about, ..self }
+    }
+
+    pub const fn default(self, default: &'static str) -> Self {
+        Self {
+            default: Some(default),
+            ..self
+        }
+    }
+}
+
+impl Type {
+    /// Whether `value` has the right shape,
+    /// regardless of whether it names anything that exists.
+    pub fn accepts(self, value: &Value) -> bool {
+        let is_name = matches!(value, Value::Name(_) | Value::Quoted(_));
+        match self {
+            Type::Name | Type::Text | Type::Entity => is_name,
+            Type::Names => match value {
+                Value::List(values) => values.iter().all(|value| self.accepts(value)),
+                _ => is_name,
+            },
+            Type::Money => matches!(value, Value::Money(_)),
+            Type::Balance => matches!(value, Value::Money(_) | Value::Negative(_)),
+            Type::Natural => matches!(value, Value::Natural(_)),
+            Type::Integer => matches!(value, Value::Natural(_) | Value::NegativeNatural(_)),
+            Type::Gtin => matches!(value, Value::Gtin(_)),
+            Type::Date => matches!(value, Value::Date(_)),
+            Type::Dates => matches!(value, Value::Dates(_)),
+            Type::Split => matches!(value, Value::Split(_)),
+            Type::Concept | Type::Product => is_name || matches!(value, Value::Gtin(_)),
+            Type::Transfer => is_name || matches!(value, Value::Natural(_)),
+            Type::Choice(words) => words.iter().any(|word| *value == Value::name(word)),
+        }
+    }
+
+    /// What a value needs to be to be [accepted][Type::accepts].
+    pub fn expected(self) -> String {
+        match self {
+            Type::Name | Type::Entity => "a name",
+            Type::Names => "names separated by commas",
+            Type::Text => "a string in quotes",
+            Type::Money | Type::Balance => "money",
+            Type::Natural => "a natural number",
+            Type::Integer => "an integer",
+            Type::Gtin => "a GTIN",
+            Type::Date => "a date like 2024-12-31",
+            Type::Dates => "days like 2025-01-01..2025-12-31, the first not after the last",
+            Type::Split => "a ratio",
+            Type::Concept => "a concept name or GTIN",
+            Type::Product => "a product name or GTIN",
+            Type::Transfer => "a label or a position in the ledger",
+            Type::Choice(words) => return one_of(words),
+        }
+        .to_owned()
+    }
+
+    /// How it is shown in usage text.
+    fn placeholder(self) -> String {
+        match self {
+            Type::Name => "name",
+            Type::Names => "name,...",
+            Type::Text => "text",
+            Type::Money | Type::Balance => "money",
+            Type::Natural => "n",
+            Type::Integer => "integer",
+            Type::Gtin => "gtin",
+            Type::Date => "date",
+            Type::Dates => "date..date",
+            Type::Split => "n:n",
+            Type::Entity => "entity",
+            Type::Concept => "concept",
+            Type::Product => "product",
+            Type::Transfer => "transfer",
+            Type::Choice(words) => return words.join("|"),
+        }
+        .to_owned()
+    }
+}
+
+/// `` `a`, `b` or `c` ``, prefixed by "one of" if there's more than one.
+pub(crate) fn one_of(words: &[&str]) -> String {
+    let quoted: Vec<_> = words.iter().map(|word| format!("`{word}`")).collect();
+    match quoted.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("one of {} or {last}", rest.join(", ")),
+        None => String::new(),
+    }
+}
+
+use Param as P;
+use Type as T;
+
+const DATE: Param = P::named("date", T::Date).about("day it happened on");
+const LABEL: Param = P::named("as", T::Name).about("label to `void` it by later, used only once");
+const MEMO: Param = P::named("memo", T::Text).about("free-form note kept with it");
+const TAGS: Param = P::named("tags", T::Names).about("groups it with others for reports");
+const KIND: Param = P::pos("kind", T::Choice(KINDS))
+    .optional()
+    .about("only needed if the name is taken by several kinds of actors");
+
+const PAY: &[Param] = &[
+    P::pos("amount", T::Money).about("how much is paid"),
+    P::named("from", T::Entity).required().about("who pays"),
+    P::named("to", T::Entity).required().about("who is paid"),
+    DATE,
+    LABEL,
+    MEMO,
+    TAGS,
+];
+
+const DELIVER: &[Param] = &[
+    P::pos("product", T::Product).about("what is delivered"),
+    P::named("price", T::Money)
+        .about("what it costs")
+        .default("the default price of its concept"),
+    P::named("from", T::Entity).required().about("who delivers"),
+    P::named("to", T::Entity)
+        .required()
+        .about("who receives and pays"),
+    P::named("split", T::Split)
+        .about("how much the one delivering gifts to how much the one receiving pays")
+        .default("0:1"),
+    P::named("round", T::Choice(ROUNDINGS))
+        .about(
+            "who covers a cent the split leaves over: largest-remainder, payer, receiver or carry",
+        )
+        .default("largest-remainder"),
+    DATE,
+    LABEL,
+    MEMO,
+    TAGS,
+];
+
+const RECEIPT: &[Param] = &[
+    P::named("from", T::Entity).required().about("who paid"),
+    P::named("at", T::Entity).about("store that is paid and delivers every line"),
+    P::named("total", T::Money).about("checked to be the sum of all lines"),
+    DATE,
+    LABEL,
+    MEMO,
+    TAGS,
+];
+
+const EXPECTED_BALANCE: &[Param] =
+    &[P::pos("expected", T::Balance).about("negative if it's the other way around")];
+
+/// Every command in every form,
+/// in the order they are usually needed in.
+pub const SCHEMAS: &[Schema] = &[
+    Schema::of(Command::Create)
+        .form("entity")
+        .about("Registers a new entity, which can pay and deliver.")
+        .params(&[P::pos("name", T::Name).about("what it's called")])
+        .body("[{ <name>; ... }]")
+        .examples(&["create entity Alice", "create entity { Alice; Bob; Kiosk }"]),
+    Schema::of(Command::Create)
+        .form("concept")
+        .about("Registers a new concept, an off-the-shelf product.")
+        .params(&[
+            P::pos("name", T::Name).about("what it's called"),
+            P::named("price", T::Money)
+                .about("default price of deliveries of it")
+                .default("none, each delivery needs one"),
+            P::named("gtin", T::Gtin).about("barcode number to refer to it by"),
+            P::named("category", T::Name).about("groups it with similar ones for budgets"),
+            P::named("tags", T::Names).about("groups it and its deliveries with others"),
+        ])
+        .examples(&["create concept Mate price=1.50€ gtin=4029764001807 category=drinks"]),
+    Schema::of(Command::Create)
+        .form("object")
+        .about("Registers a new object, one physical thing.")
+        .params(&[
+            P::pos("name", T::Name).about("what it's called"),
+            P::named("parent", T::Concept).about("concept it is an instance of"),
+        ])
+        .examples(&["create object Bottle parent=Mate"]),
+    Schema::of(Command::Pay)
+        .about("Transfers money from one entity to another.")
+        .params(PAY)
+        .examples(&[
+            "pay 20€ from=Alice to=Bob",
+            "pay 450€ from Alice to Landlord tags=rent",
+        ]),
+    Schema::of(Command::Deliver)
+        .about("Delivers a product, for which the one receiving owes its price.")
+        .params(DELIVER)
+        .examples(&[
+            "deliver Mate from=Kiosk to=Alice",
+            "deliver Pizza price=12€ from=Alice to=Bob split=1:1",
+        ]),
+    Schema::of(Command::Receipt)
+        .about("One purchase, split over lines consumed by different entities.")
+        .params(RECEIPT)
+        .body("{ <line>; ... }")
+        .examples(&["receipt from=Alice at=Kiosk total=4.50€ { Mate 2 for Alice; Mate for Bob }"]),
+    RECEIPT_LINE,
+    Schema::of(Command::Void)
+        .about("Undoes transfers, keeping them in the ledger marked as voided.")
+        .params(&[P::pos("transfer", T::Transfer).about("label it was given with `as`")])
+        .examples(&["void oops", "void 3"]),
+    Schema::of(Command::Balance)
+        .about("Shows how much one entity owes another.")
+        .params(&[
+            P::named("from", T::Entity)
+                .required()
+                .about("who might be owed"),
+            P::named("to", T::Entity).required().about("who might owe"),
+            P::named("as-of", T::Date).about("only counts transfers up to this day"),
+        ])
+        .examples(&[
+            "balance from=Alice to=Bob",
+            "balance from Alice to Bob as-of 2025-01-31",
+        ]),
+    Schema::of(Command::Alias)
+        .about("Lets an actor also be referred to by another name.")
+        .params(&[KIND, P::pos("name", T::Name).about("new name")])
+        .expected(&[P::pos("existing", T::Name).about("name it already has")])
+        .examples(&[
+            "alias Bea = Beatrice",
+            "alias concept Mate = \"Club-Mate 0.5l\"",
+        ]),
+    Schema::of(Command::Rename)
+        .about("Refers to an actor by another name from now on, also in past transfers.")
+        .params(&[
+            KIND,
+            P::pos("name", T::Name).about("name it has"),
+            P::named("to", T::Name)
+                .required()
+                .about("name it has from now on"),
+        ])
+        .examples(&["rename Späti to Kiosk"]),
+    Schema::of(Command::Tag)
+        .about("Adds a tag to a concept, also for past deliveries of it.")
+        .params(&[
+            P::pos("concept", T::Concept).about("what is tagged"),
+            P::pos("tag", T::Name).about("tag it gets"),
+        ])
+        .examples(&["tag Mate drinks"]),
+    Schema::of(Command::Assert)
+        .form("balance")
+        .about("Stops with an error unless one entity owes another as much as expected.")
+        .params(&[
+            P::named("from", T::Entity).required().about("who is owed"),
+            P::named("to", T::Entity).required().about("who owes"),
+        ])
+        .expected(EXPECTED_BALANCE)
+        .examples(&["assert balance from=Alice to=Bob = -2€"]),
+    Schema::of(Command::Assert)
+        .form("net")
+        .about("Stops with an error unless an entity owes all others as much as expected.")
+        .params(&[P::pos("entity", T::Entity).about("who owes")])
+        .expected(EXPECTED_BALANCE)
+        .examples(&["assert net Alice = -2€"]),
+    Schema::of(Command::Assert)
+        .form("inventory")
+        .about("Stops with an error unless an entity got a product as often as expected.")
+        .params(&[
+            P::pos("entity", T::Entity).about("who has it"),
+            P::pos("product", T::Product).about("what was delivered"),
+        ])
+        .expected(
+            &[P::pos("expected", T::Integer).about("deliveries to it minus deliveries by it")],
+        )
+        .examples(&["assert inventory Alice Mate = 2"]),
+    Schema::of(Command::Assert)
+        .form("price")
+        .about("Stops with an error unless a concept has the expected default price.")
+        .params(&[P::pos("concept", T::Concept).about("what has the price")])
+        .expected(&[P::pos("expected", T::Money).about("its default price")])
+        .examples(&["assert price Mate = 1.50€"]),
+    Schema::of(Command::Begin)
+        .about("Starts a transaction, kept only if it's committed.")
+        .examples(&["begin"]),
+    Schema::of(Command::Commit)
+        .about("Ends the innermost transaction, keeping what happened in it.")
+        .examples(&["commit"]),
+    Schema::of(Command::Whatif)
+        .about("Shows how balances would change, without keeping any of it.")
+        .body("{ <statement>; ... }")
+        .examples(&["whatif { pay 5€ from=Alice to=Bob }"]),
+    Schema::of(Command::Every)
+        .about("Repeats transfers periodically, each dated to its occurrence.")
+        .params(&[
+            P::pos("period", T::Choice(PERIODS)).about("how often"),
+            P::named("from", T::Date)
+                .required()
+                .about("first occurrence"),
+            P::named("until", T::Date)
+                .about("last day it can occur on")
+                .default("the day given with `--until`"),
+        ])
+        .body("{ <statement>; ... }")
+        .examples(&["every month from=2025-01-01 { pay 450€ from=Alice to=Landlord }"]),
+    Schema::of(Command::Budget)
+        .about("Limits what an entity spends on a category, warning when crossed.")
+        .params(&[
+            P::pos("entity", T::Entity).about("who spends"),
+            P::pos("category", T::Name).about("category or tag of what is spent on"),
+            P::pos("limit", T::Money).about("how much at most"),
+            P::named("per", T::Choice(PERIODS))
+                .required()
+                .about("how often it starts over"),
+        ])
+        .examples(&["budget Alice drinks 20€ per=month"]),
+    Schema::of(Command::Budget)
+        .form("report")
+        .about("Shows what was spent per budget and period, and by how much it was over.")
+        .examples(&["budget report"]),
+    Schema::of(Command::Report)
+        .form("spending")
+        .about("Shows what entities spent, either by tag or category, or per period.")
+        .params(&[
+            P::named("by", T::Choice(GROUPINGS)).about("what to group spending by, without `per`"),
+            P::named("per", T::Choice(PERIODS)).about("one row per period, needs `of`"),
+            P::named("of", T::Entity).about("whose spending, with `per`"),
+            P::named("during", T::Dates)
+                .about("days to show, with `per`")
+                .default("the first to the last dated transfer"),
+            P::named("format", T::Choice(FORMATS))
+                .about("for humans or spreadsheets")
+                .default("table"),
+        ])
+        .examples(&[
+            "report spending by=tag",
+            "report spending per=month of=Alice during=2025-01-01..2025-12-31 format=csv",
+        ]),
+    Schema::of(Command::History)
+        .about("Shows every transfer numbered in order, voided ones marked.")
+        .params(&[
+            P::named("of", T::Entity).about("only those from or to this entity"),
+            P::named("tag", T::Name).about("only those with this tag"),
+        ])
+        .examples(&["history", "history of=Alice tag=rent"]),
+    Schema::of(Command::Select)
+        .about("Shows a table of what the ledger says, like SQL does.")
+        .body(
+            "<column>, ... from <table> [where <condition> and ...] [group by <group>] \
+             [order by <column> [desc]] [limit <n>]",
+        )
+        .examples(&[
+            "select * from payments where from = Alice",
+            "select to, sum(share) from deliveries group by to order by sum(share) desc",
+        ]),
+    Schema::of(Command::Help)
+        .about("Explains a command, or lists all of them.")
+        .params(&[
+            P::pos("command", T::Choice(COMMANDS))
+                .optional()
+                .about("what to explain"),
+            P::pos("form", T::Choice(FORMS))
+                .optional()
+                .about("only this form of it"),
+        ])
+        .examples(&["help", "help deliver", "help create concept"]),
+];
+
+/// A line in the block of a `receipt`.
+pub const RECEIPT_LINE: Schema = Schema::of(Command::Receipt)
+    .line()
+    .about("One line in the block of a receipt.")
+    .params(&[
+        P::pos("product", T::Product).about("what was bought"),
+        P::pos("qty", T::Natural)
+            .optional()
+            .about("how many")
+            .default("1"),
+        P::pos("price", T::Money)
+            .optional()
+            .about("what one costs")
+            .default("the default price of its concept"),
+        P::rest("for", T::Entity)
+            .about("who pays an equal part each")
+            .default("the one who paid"),
+    ])
+    .examples(&["Mate 2 1.50€ for Alice Bob"]);
+
+impl Schema {
+    /// A form of `command` without any parameters so far.
+    pub const fn of(command: Command) -> Self {
+        Self {
+            command,
+            form: None,
+            params: &[],
+            expected: &[],
+            body: None,
+            about: "",
+            examples: &[],
+            line: false,
+        }
+    }
+
+    pub const fn line(self) -> Self {
+        Self { line: true, ..self }
+    }
+
+    pub const fn form(self, form: &'static str) -> Self {
+        Self {
+            form: Some(form),
+            ..self
+        }
+    }
+
+    pub const fn params(self, params: &'static [Param]) -> Self {
+        Self { params, ..self }
+    }
+
+    pub const fn expected(self, expected: &'static [Param]) -> Self {
+        Self { expected, ..self }
+    }
+
+    pub const fn about(self, about: &'static str) -> Self {
+        Self { about, ..self }
+    }
+
+    pub const fn examples(self, examples: &'static [&'static str]) -> Self {
+        Self { examples, ..self }
+    }
+
+    pub const fn body(self, body: &'static str) -> Self {
+        Self {
+            body: Some(body),
+            ..self
+        }
+    }
+
+    /// Every form of `command`, without its [lines][Schema::line].
+    pub fn forms(command: &Command) -> impl Iterator<Item = &'static Schema> + Clone {
+        SCHEMAS
+            .iter()
+            .filter(move |schema| schema.command == *command && !schema.line)
+    }
+
+    /// Matches `args` to the parameters.
+    ///
+    /// If this is a form, the first argument needs to be its word.
+    pub fn bind<'tok>(&self, args: Args<'tok>) -> Result<Bound<'tok>, error::Usage> {
+        self.bind_args(args)
+            .map_err(|(arg, suggestion)| error::Usage {
+                arg,
+                suggestion,
+                usage: self.to_string(),
+            })
+    }
+
+    fn bind_args<'tok>(&self, Args(mut args): Args<'tok>) -> Result<Bound<'tok>, Failed> {
+        if let Some(form) = self.form {
+            match args.first() {
+                Some(Arg::Pos(value)) if *value == Value::name(form) => {
+                    args.remove(0);
+                }
+                _ => return Err((error::Arg::Missing(form.to_owned()), None)),
+            }
+        }
+
+        let expected = match args.iter().position(|arg| *arg == Arg::Equals) {
+            Some(idx) if !self.expected.is_empty() => args.split_off(idx).split_off(1),
+            None if !self.expected.is_empty() => {
+                let missing = format!("= <{}>", self.expected[0].name);
+                return Err((error::Arg::Missing(missing), None));
+            }
+            _ => Vec::new(),
+        };
+
+        let mut bound = Bound(Map::new());
+        bound.fill(self.params, args)?;
+        bound.fill(self.expected, expected)?;
+        Ok(bound)
+    }
+}
+
+/// Binds `args` against whichever form of `command` fits them:
+/// first the one named by the first argument, if any,
+/// then the one without a name.
+/// If none does, reports why the first one tried doesn't.
+pub fn bind<'tok>(
+    command: &Command,
+    args: Args<'tok>,
+) -> Result<(&'static Schema, Bound<'tok>), error::Usage> {
+    let word = match args.0.first() {
+        Some(Arg::Pos(Value::Name(ast::Name(ast::Ident(word))))) => Some(*word),
+        _ => None,
+    };
+    let forms = Schema::forms(command);
+    let named = forms.clone().filter(|schema| schema.form.is_some());
+    let candidates = named
+        .clone()
+        .filter(|schema| schema.form == word)
+        .chain(forms.filter(|schema| schema.form.is_none()));
+
+    let mut failed = None;
+    for schema in candidates {
+        match schema.bind(args.clone()) {
+            Ok(bound) => return Ok((schema, bound)),
+            Err(err) => {
+                failed.get_or_insert(err);
+            }
+        }
+    }
+
+    Err(failed.unwrap_or_else(|| {
+        let words: Vec<_> = named.clone().filter_map(|schema| schema.form).collect();
+        let usage: Vec<_> = named.map(ToString::to_string).collect();
+        error::Usage {
+            arg: error::Arg::Form(one_of(&words)),
+            suggestion: word.and_then(|word| suggest(word, words)),
+            usage: usage.join("\n       "),
+        }
+    }))
+}
+
+/// Why binding failed, and what might've been meant instead.
+type Failed = (error::Arg, Option<String>);
+
+/// Arguments matched to the parameters of a [`Schema`], by name.
+#[derive(Owned!)]
+pub struct Bound<'tok>(Map<&'static str, Value<'tok>>);
+
+impl<'tok> Bound<'tok> {
+    /// Takes the argument given for the parameter `name`, if any.
+    ///
+    /// One for a [`Slot::Rest`] is a [`Value::List`] if there's more than one.
+    pub fn take(&mut self, name: &str) -> Option<Value<'tok>> {
+        self.0.remove(name)
+    }
+
+    fn insert(&mut self, param: &Param, value: Value<'tok>) -> Result<(), Failed> {
+        let mismatch = || {
+            let suggestion = match (param.ty, &value) {
+                (Type::Choice(words), Value::Name(ast::Name(ast::Ident(given)))) => {
+                    suggest(given, words.iter().copied())
+                }
+                _ => None,
+            };
+            let arg = error::Arg::Type {
+                key: param.name.to_owned(),
+                expected: param.ty.expected(),
+            };
+            (arg, suggestion)
+        };
+        let fits = match (param.slot, &value) {
+            (Slot::Rest, Value::List(values)) => values.iter().all(|v| param.ty.accepts(v)),
+            _ => param.ty.accepts(&value),
+        };
+        if !fits {
+            return Err(mismatch());
+        }
+
+        if self.0.insert(param.name, value).is_some() {
+            return Err((error::Arg::Duplicate(param.name.to_owned()), None));
+        }
+        Ok(())
+    }
+
+    /// Binds `args` against `params`,
+    /// first the named ones and then the positional ones in order.
+    fn fill(&mut self, params: &[Param], args: Vec<Arg<'tok>>) -> Result<(), Failed> {
+        let find = |key: &str| params.iter().find(|param| param.name == key);
+        let unknown = |key: &str| {
+            let suggestion = suggest(key, params.iter().map(|param| param.name));
+            (error::Arg::Unknown(key.to_owned()), suggestion)
+        };
+
+        let mut pos = VecDeque::new();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            match arg {
+                Arg::Named { key, value } => {
+                    let param = find(key.0).ok_or_else(|| unknown(key.0))?;
+                    self.insert(param, value)?;
+                }
+                Arg::Pos(Value::Name(ast::Name(key)))
+                    if matches!(args.peek(), Some(Arg::Pos(_)))
+                        && find(key.0).is_some_and(|param| param.slot != Slot::Pos) =>
+                {
+                    let param = find(key.0).expect("just checked it");
+                    let mut values = Vec::new();
+                    while let Some(Arg::Pos(_)) = args.peek() {
+                        let Some(Arg::Pos(value)) = args.next() else {
+                            unreachable!("just peeked it");
+                        };
+                        values.push(value);
+                        if param.slot == Slot::Named {
+                            break;
+                        }
+                    }
+                    let value = match values.len() {
+                        1 => values.remove(0),
+                        _ => Value::List(values),
+                    };
+                    self.insert(param, value)?;
+                }
+                Arg::Pos(value) => pos.push_back(value),
+                Arg::Equals => return Err((error::Arg::Unexpected("=".to_owned()), None)),
+            }
+        }
+
+        let slots: Vec<_> = params
+            .iter()
+            .filter(|param| param.slot == Slot::Pos && !self.0.contains_key(param.name))
+            .collect();
+        for (idx, param) in slots.iter().enumerate() {
+            let Some(value) = pos.front() else {
+                break;
+            };
+            let after = &slots[idx + 1..];
+            let required_after = after.iter().filter(|p| p.required).count();
+            // if it doesn't fit anywhere else, it's the wrong type for this one
+            let fits_later = after.iter().any(|p| p.ty.accepts(value));
+            let skip = !param.ty.accepts(value) && pos.len() <= after.len() && fits_later
+                || pos.len() <= required_after;
+            if !param.required && skip {
+                continue;
+            }
+            let value = pos.pop_front().expect("just peeked it");
+            self.insert(param, value)?;
+        }
+
+        if let Some(value) = pos.pop_front() {
+            let suggestion = match &value {
+                Value::Name(ast::Name(ast::Ident(given))) => suggest(
+                    given,
+                    params
+                        .iter()
+                        .filter(|param| param.slot != Slot::Pos)
+                        .map(|param| param.name),
+                ),
+                _ => None,
+            };
+            return Err((error::Arg::Unexpected(value.to_string()), suggestion));
+        }
+
+        match params
+            .iter()
+            .find(|param| param.required && !self.0.contains_key(param.name))
+        {
+            Some(param) => Err((error::Arg::Missing(param.name.to_owned()), None)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The option closest to `given`, if it's close enough to be a typo of it
+/// and not just the same.
+fn suggest<'a>(given: &str, options: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let allowed = if given.chars().count() < 4 { 1 } else { 2 };
+    options
+        .into_iter()
+        .map(|option| (distance(given, option), option))
+        .filter(|(distance, _)| (1..=allowed).contains(distance))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, option)| option.to_owned())
+}
+
+/// How many characters need to be inserted, removed, replaced
+/// or swapped with their neighbor to get from `a` to `b`.
+fn distance(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<_>, Vec<_>) = (a.chars().collect(), b.chars().collect());
+    // rows of the previous two and the current character of `a`
+    let mut before: Vec<usize> = Vec::new();
+    let mut last: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (last[j] + 1).min(row[j - 1] + 1).min(last[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        before = std::mem::replace(&mut last, row);
+    }
+    last[b.len()]
+}
+
+impl fmt::Display for Schema {
+    /// As in `pay <amount:money> from=<entity> to=<entity> [date=<date>]`,
+    /// a [line][Schema::line] without the command.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words = self.params.iter().map(ToString::to_string);
+        if !self.line {
+            write!(f, "{}", self.command)?;
+            if let Some(form) = self.form {
+                write!(f, " {form}")?;
+            }
+        } else if let Some(first) = words.next() {
+            write!(f, "{first}")?;
+        }
+        for param in words {
+            write!(f, " {param}")?;
+        }
+        if !self.expected.is_empty() {
+            write!(f, " =")?;
+            for param in self.expected {
+                write!(f, " {param}")?;
+            }
+        }
+        if let Some(body) = self.body {
+            write!(f, " {body}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Param {
+    /// Choices are listed unless there are too many to fit in one line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let placeholder = match self.ty {
+            Type::Choice(words) if words.len() > 4 => self.name.to_owned(),
+            ty => ty.placeholder(),
+        };
+        let shown = match self.slot {
+            Slot::Pos if matches!(self.ty, Type::Choice(_)) || placeholder == self.name => {
+                format!("<{placeholder}>")
+            }
+            Slot::Pos => format!("<{}:{placeholder}>", self.name),
+            Slot::Named => format!("{}=<{placeholder}>", self.name),
+            Slot::Rest => format!("{} <{placeholder}>...", self.name),
+        };
+        match self.required {
+            true => write!(f, "{shown}"),
+            false => write!(f, "[{shown}]"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        runtime::{
+            cmd::{
+                every::Period,
+                report::{Format, Grouping},
+            },
+            model::Rounding,
+        },
+        syntax::ast::Script,
+    };
+
+    use super::*;
+
+    /// Binds the arguments of the only statement in `src`.
+    fn bind_src(src: &str) -> Result<(&'static Schema, Vec<&'static str>), error::Usage> {
+        let script = Script::parse(src).unwrap();
+        let stmt = script.0[0].node.clone();
+        let (schema, bound) = bind(&stmt.cmd, stmt.args)?;
+        Ok((schema, bound.0.into_keys().collect()))
+    }
+
+    #[test]
+    fn usage() {
+        let usage = |command, form| {
+            Schema::forms(&command)
+                .find(|schema| schema.form == form)
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(
+            usage(Command::Pay, None),
+            "pay <amount:money> from=<entity> to=<entity> [date=<date>] [as=<name>] [memo=<text>] [tags=<name,...>]"
+        );
+        assert_eq!(
+            usage(Command::Create, Some("object")),
+            "create object <name> [parent=<concept>]"
+        );
+        assert_eq!(
+            usage(Command::Alias, None),
+            "alias [<entity|concept|object>] <name> = <existing:name>"
+        );
+        assert_eq!(usage(Command::Help, None), "help [<command>] [<form>]");
+        assert_eq!(
+            RECEIPT_LINE.to_string(),
+            "<product> [<qty:n>] [<price:money>] [for <entity>...]"
+        );
+    }
+
+    #[test]
+    fn binding() {
+        let (schema, keys) = bind_src("create concept Mate price 1.50€ gtin=12345678").unwrap();
+        assert_eq!(schema.form, Some("concept"));
+        assert_eq!(keys, ["gtin", "name", "price"]);
+
+        // an optional positional one is skipped if it doesn't fit
+        let line = |src| {
+            // any command does, only the arguments are bound
+            let src = format!("pay {src}");
+            let script = Script::parse(&src).unwrap();
+            let mut bound = RECEIPT_LINE.bind(script.0[0].node.args.clone()).unwrap();
+            ["qty", "price", "for"].map(|key| bound.take(key).map(|value| value.to_string()))
+        };
+        assert_eq!(
+            line("Mate 1.50€ for A B"),
+            [None, Some("1.50€".to_owned()), Some("A,B".to_owned())]
+        );
+        assert_eq!(line("Mate 2"), [Some("2".to_owned()), None, None]);
+
+        // or if it's needed for a required one after it
+        let (_, keys) = bind_src("rename entity to=B").unwrap();
+        assert_eq!(keys, ["name", "to"]);
+        let (_, keys) = bind_src("rename entity A to=B").unwrap();
+        assert_eq!(keys, ["kind", "name", "to"]);
+
+        // the named form is tried first, then the one without name
+        let (schema, _) = bind_src("budget report").unwrap();
+        assert_eq!(schema.form, Some("report"));
+        let (schema, _) = bind_src("budget report groceries 10€ per=month").unwrap();
+        assert_eq!(schema.form, None);
+    }
+
+    #[test]
+    fn errors() {
+        let err = |src| {
+            let err = bind_src(src).unwrap_err();
+            (err.arg, err.suggestion)
+        };
+        let suggested = |word: &str| Some(word.to_owned());
+
+        assert_eq!(
+            err("deliver Mate prcie=2€ from=A to=B"),
+            (error::Arg::Unknown("prcie".to_owned()), suggested("price"))
+        );
+        assert_eq!(
+            err("pay 2€ form A to=B"),
+            (error::Arg::Unexpected("form".to_owned()), suggested("from"))
+        );
+        assert_eq!(
+            err("pay 2€ from=A to=B from=C"),
+            (error::Arg::Duplicate("from".to_owned()), None)
+        );
+        assert_eq!(
+            err("pay 2€ from=A"),
+            (error::Arg::Missing("to".to_owned()), None)
+        );
+        assert_eq!(
+            err("pay A from=A to=B"),
+            (
+                error::Arg::Type {
+                    key: "amount".to_owned(),
+                    expected: "money".to_owned()
+                },
+                None
+            )
+        );
+        assert_eq!(
+            err("deliver Mate from=A to=B round=neares").1,
+            suggested("nearest")
+        );
+        assert_eq!(
+            err("create entitiy A"),
+            (
+                error::Arg::Form("one of `entity`, `concept` or `object`".to_owned()),
+                suggested("entity")
+            )
+        );
+        assert_eq!(
+            err("assert balance from=A to=B"),
+            (error::Arg::Missing("= <expected>".to_owned()), None)
+        );
+    }
+
+    #[test]
+    fn complete() {
+        let keywords: Vec<_> = Command::ALL.iter().map(Command::keyword).collect();
+        assert_eq!(COMMANDS, keywords);
+        for command in Command::ALL {
+            assert!(Schema::forms(&command).next().is_some(), "{command}");
+        }
+        let forms: Vec<_> = SCHEMAS.iter().filter_map(|schema| schema.form).collect();
+        assert_eq!(FORMS, forms);
+    }
+
+    #[test]
+    fn choices() {
+        for period in PERIODS {
+            assert!(period.parse::<Period>().is_ok());
+        }
+        for rounding in ROUNDINGS {
+            assert!(rounding.parse::<Rounding>().is_ok());
+        }
+        for grouping in GROUPINGS {
+            assert!(grouping.parse::<Grouping>().is_ok());
+        }
+        for format in FORMATS {
+            assert!(format.parse::<Format>().is_ok());
+        }
+    }
+}
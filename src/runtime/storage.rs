@@ -0,0 +1,334 @@
+//! Where the runtime writes down what happened.
+//!
+//! The [`Runtime`][super::Runtime] always keeps everything in its in-memory [`State`],
+//! which is a [`Storage`] itself.
+//! Optionally, it can write into another one on the side,
+//! e.g. a database other tools can query.
+
+use std::{
+    fmt, mem,
+    sync::{Mutex, PoisonError},
+};
+
+use crate::{Map, ext::Balance};
+
+use super::{
+    State,
+    cmd::{Budget, Name, NameRef},
+    error,
+    model::{Actor, Concept, Entity, Object, Pair, Product, Transfer},
+};
+
+/// Receives every change to the runtime state, in order.
+pub trait Storage: fmt::Debug {
+    /// `entity` was created.
+    fn entity(&mut self, entity: &Entity) -> Result<(), error::Storage>;
+
+    /// `concept` was created, possibly shadowing a previous one.
+    fn concept(&mut self, concept: &Concept) -> Result<(), error::Storage>;
+
+    /// `object` was created, possibly shadowing a previous one.
+    fn object(&mut self, object: &Object) -> Result<(), error::Storage>;
+
+    /// `transfer` happened after all previous ones.
+    fn transfer(&mut self, transfer: &Transfer) -> Result<(), error::Storage>;
+
+    /// `concept` was tagged with `tag`, which it didn't have yet.
+    /// Everything referring to it, including past transfers, has the tag from now on.
+    fn tag(&mut self, concept: &Concept, tag: NameRef) -> Result<(), error::Storage>;
+
+    /// `budget` was set, replacing any previous one
+    /// for the same entity and category.
+    fn budget(&mut self, budget: &Budget) -> Result<(), error::Storage>;
+
+    /// The transfer at position `idx` in the ledger, counting from 0, was voided.
+    /// Its effect on the balances is undone separately.
+    fn void(&mut self, idx: usize) -> Result<(), error::Storage>;
+
+    /// `actor` can now also be referred to as `name`.
+    fn alias(&mut self, actor: &Actor, name: NameRef) -> Result<(), error::Storage>;
+
+    /// `actor` is now referred to as `to` instead of `from`.
+    ///
+    /// If `from` is the [name][Actor::name] of `actor`,
+    /// it keeps its identity and balances under the new name
+    /// and everything referring to it, including past transfers, shows the new one.
+    /// Otherwise `from` is one of its aliases,
+    /// which is just replaced.
+    fn rename(&mut self, actor: &Actor, from: NameRef, to: NameRef) -> Result<(), error::Storage>;
+
+    /// The balance of `pair` is now `balance`,
+    /// how much [`Pair::a`] owes [`Pair::b`].
+    fn balance(&mut self, pair: &Pair, balance: &Balance) -> Result<(), error::Storage>;
+
+    /// A transaction was opened, possibly inside another one.
+    /// Everything until the matching [`Storage::commit`] or [`Storage::abort`]
+    /// belongs to it.
+    ///
+    /// Does nothing by default,
+    /// the [`Runtime`][super::Runtime] rolls back its [`State`] on its own.
+    fn begin(&mut self) -> Result<(), error::Storage> {
+        Ok(())
+    }
+
+    /// The innermost open transaction is kept.
+    fn commit(&mut self) -> Result<(), error::Storage> {
+        Ok(())
+    }
+
+    /// Everything in the innermost open transaction is undone.
+    fn abort(&mut self) -> Result<(), error::Storage> {
+        Ok(())
+    }
+}
+
+impl Storage for State {
+    fn entity(&mut self, entity: &Entity) -> Result<(), error::Storage> {
+        // shadows an alias just like another entity
+        self.aliases.entities.remove(&entity.name);
+        self.entities.insert(entity.name.clone(), entity.clone());
+        Ok(())
+    }
+
+    fn concept(&mut self, concept: &Concept) -> Result<(), error::Storage> {
+        // if it has a GTIN, we also need to remember it separately
+        // so it is still stored when it is shadowed by name
+        if let Some(gtin) = concept.gtin {
+            self.concepts_gtin.insert(gtin, concept.clone());
+        }
+
+        self.aliases.concepts.remove(&concept.name);
+        self.concepts.insert(concept.name.clone(), concept.clone());
+        Ok(())
+    }
+
+    fn object(&mut self, object: &Object) -> Result<(), error::Storage> {
+        // objects are only created with a name
+        // and are only anonymous as instance of a concept during delivery
+        let name = object.name.clone().expect("created objects to have a name");
+        self.aliases.objects.remove(&name);
+        self.objects.insert(name, object.clone());
+        Ok(())
+    }
+
+    fn transfer(&mut self, transfer: &Transfer) -> Result<(), error::Storage> {
+        self.ledger.push(transfer.clone());
+        Ok(())
+    }
+
+    fn tag(&mut self, concept: &Concept, tag: NameRef) -> Result<(), error::Storage> {
+        let mut tagged = concept.clone();
+        tagged.tags.push(tag.to_owned());
+        self.each_concept(|other| replace(other, concept, &tagged));
+        Ok(())
+    }
+
+    fn budget(&mut self, budget: &Budget) -> Result<(), error::Storage> {
+        let same = |other: &Budget| other.of == budget.of && other.category == budget.category;
+        match self.budgets.iter_mut().find(|other| same(other)) {
+            Some(other) => *other = budget.clone(),
+            None => self.budgets.push(budget.clone()),
+        }
+        Ok(())
+    }
+
+    fn void(&mut self, idx: usize) -> Result<(), error::Storage> {
+        match &mut self.ledger[idx] {
+            Transfer::Pay(pay) => pay.voided = true,
+            Transfer::Deliver(deliver) => deliver.voided = true,
+        }
+        Ok(())
+    }
+
+    fn alias(&mut self, actor: &Actor, name: NameRef) -> Result<(), error::Storage> {
+        let name = name.to_owned();
+        match actor.clone() {
+            Actor::Entity(entity) => {
+                self.aliases.entities.insert(name, entity);
+            }
+            Actor::Concept(concept) => {
+                self.aliases.concepts.insert(name, concept);
+            }
+            Actor::Object(object) => {
+                self.aliases.objects.insert(name, object);
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&mut self, actor: &Actor, from: NameRef, to: NameRef) -> Result<(), error::Storage> {
+        if actor.name() != Some(from) {
+            match actor {
+                Actor::Entity(_) => move_key(&mut self.aliases.entities, from, to),
+                Actor::Concept(_) => move_key(&mut self.aliases.concepts, from, to),
+                Actor::Object(_) => move_key(&mut self.aliases.objects, from, to),
+            }
+            return Ok(());
+        }
+
+        match actor {
+            Actor::Entity(old) => {
+                let new = Entity {
+                    name: to.to_owned(),
+                };
+                self.each_entity(|entity| replace(entity, old, &new));
+                self.balances = mem::take(&mut self.balances)
+                    .into_iter()
+                    .map(|(pair, balance)| {
+                        let [a, b] = <[Entity; 2]>::from(pair).map(|mut entity| {
+                            replace(&mut entity, old, &new);
+                            entity
+                        });
+                        // the pair might be ordered the other way around now
+                        let Balance(balance) = balance;
+                        let balance = if a > b { -balance } else { balance };
+                        let pair = Pair::new(a, b).expect("new name to not be taken");
+                        (pair, Balance(balance))
+                    })
+                    .collect();
+                move_key(&mut self.entities, from, to);
+            }
+            Actor::Concept(old) => {
+                let new = Concept {
+                    name: to.to_owned(),
+                    ..old.clone()
+                };
+                self.each_concept(|concept| replace(concept, old, &new));
+                move_key(&mut self.concepts, from, to);
+            }
+            Actor::Object(old) => {
+                let new = Object {
+                    name: Some(to.to_owned()),
+                    ..old.clone()
+                };
+                self.each_object(|object| replace(object, old, &new));
+                move_key(&mut self.objects, from, to);
+            }
+        }
+        Ok(())
+    }
+
+    fn balance(&mut self, pair: &Pair, balance: &Balance) -> Result<(), error::Storage> {
+        self.balances.insert(pair.clone(), balance.clone());
+        Ok(())
+    }
+}
+
+/// Where actors are referred to, for [`Storage::rename`] and [`Storage::tag`].
+/// Balances are keyed by entities, so they can't be visited in place.
+impl State {
+    fn each_entity(&mut self, f: impl FnMut(&mut Entity)) {
+        let in_ledger = self.ledger.iter_mut().flat_map(|transfer| {
+            let who = match transfer {
+                Transfer::Pay(pay) => &mut pay.who,
+                Transfer::Deliver(deliver) => &mut deliver.who,
+            };
+            [&mut who.source, &mut who.target]
+        });
+
+        self.entities
+            .values_mut()
+            .chain(self.aliases.entities.values_mut())
+            .chain(in_ledger)
+            .chain(self.budgets.iter_mut().map(|budget| &mut budget.of))
+            .for_each(f);
+    }
+
+    fn each_concept(&mut self, f: impl FnMut(&mut Concept)) {
+        let in_objects = self
+            .objects
+            .values_mut()
+            .chain(self.aliases.objects.values_mut())
+            .filter_map(|object| object.parent.as_mut());
+        let in_ledger = self
+            .ledger
+            .iter_mut()
+            .filter_map(|transfer| match transfer {
+                Transfer::Deliver(deliver) => match &mut deliver.product {
+                    Product::Concept(concept) => Some(concept),
+                    Product::Object(object) => object.parent.as_mut(),
+                },
+                Transfer::Pay(_) => None,
+            });
+
+        self.concepts
+            .values_mut()
+            .chain(self.concepts_gtin.values_mut())
+            .chain(self.aliases.concepts.values_mut())
+            .chain(in_objects)
+            .chain(in_ledger)
+            .for_each(f);
+    }
+
+    fn each_object(&mut self, f: impl FnMut(&mut Object)) {
+        let in_ledger = self
+            .ledger
+            .iter_mut()
+            .filter_map(|transfer| match transfer {
+                Transfer::Deliver(deliver) => match &mut deliver.product {
+                    Product::Object(object) => Some(object),
+                    Product::Concept(_) => None,
+                },
+                Transfer::Pay(_) => None,
+            });
+
+        self.objects
+            .values_mut()
+            .chain(self.aliases.objects.values_mut())
+            .chain(in_ledger)
+            .for_each(f);
+    }
+}
+
+fn replace<T: Clone + PartialEq>(actor: &mut T, old: &T, new: &T) {
+    if actor == old {
+        *actor = new.clone();
+    }
+}
+
+fn move_key<T>(map: &mut Map<Name, T>, from: NameRef, to: NameRef) {
+    if let Some(value) = map.remove(from) {
+        map.insert(to.to_owned(), value);
+    }
+}
+
+/// Another [`Storage`] the runtime writes into besides its [`State`].
+///
+/// Owned by exactly one runtime,
+/// which is also why runtimes can't be cloned.
+// the mutex is only there to keep the runtime `Sync`,
+// all writes go through `&mut self` and don't need to lock
+#[derive(Default)]
+pub struct Backend(Option<Mutex<Box<dyn Storage + Send>>>);
+
+impl Backend {
+    pub fn new(storage: impl Storage + Send + 'static) -> Self {
+        Self(Some(Mutex::new(Box::new(storage))))
+    }
+
+    /// Lets `op` write into the backend, if there is one.
+    pub(super) fn write(
+        &mut self,
+        op: impl FnOnce(&mut dyn Storage) -> Result<(), error::Storage>,
+    ) -> Result<(), error::Storage> {
+        match &mut self.0 {
+            Some(storage) => {
+                let storage = storage.get_mut().unwrap_or_else(PoisonError::into_inner);
+                op(storage.as_mut())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(storage) => {
+                let storage = storage.lock().unwrap_or_else(PoisonError::into_inner);
+                write!(f, "Backend({:?})", *storage)
+            }
+            None => write!(f, "Backend(None)"),
+        }
+    }
+}
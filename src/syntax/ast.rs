@@ -1,15 +1,77 @@
 use crate::{
     aux::{Owned, Stack},
-    ext::{Gtin, Money, Natural},
+    ext::{Date, Gtin, Money, Natural},
 };
 
+use super::parse;
+
 #[derive(Owned!)]
-pub struct Script<'tok>(pub Vec<Stmt<'tok>>);
+pub struct Script<'tok>(pub Vec<Spanned<Stmt<'tok>>>);
+
+/// Where something is in the source code, in bytes.
+#[derive(Stack!)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Something that was parsed, together with where it was.
+#[derive(Owned!)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
 
 #[derive(Owned!)]
 pub struct Stmt<'tok> {
     pub cmd: Command,
     pub args: Args<'tok>,
+    pub block: Option<Block<'tok>>,
+    /// Statements in braces, as in `whatif { pay 1€ from=A to=B }`.
+    /// Only commands that [take one][Command::takes_body] have it,
+    /// instead of a [`Block`].
+    pub body: Option<Script<'tok>>,
+    /// What a [`Command::Select`] asks for, instead of arguments.
+    pub query: Option<Query<'tok>>,
+}
+
+impl<'tok> Stmt<'tok> {
+    /// A statement without block.
+    pub fn new(cmd: Command, args: Vec<Arg<'tok>>) -> Self {
+        Self {
+            cmd,
+            args: Args(args),
+            block: None,
+            body: None,
+            query: None,
+        }
+    }
+
+    /// Resolves the [`Block`], if any,
+    /// into one statement per line.
+    /// Each line gets the arguments in front of the block prepended.
+    ///
+    /// A statement without a block is returned as-is,
+    /// and so is a [`Command::Receipt`], which understands its lines on its own.
+    pub fn unblock(self) -> Vec<Self> {
+        if self.cmd == Command::Receipt {
+            return vec![self];
+        }
+        let Some(Block(lines)) = self.block else {
+            return vec![self];
+        };
+
+        lines
+            .into_iter()
+            .map(|Args(line)| Self {
+                cmd: self.cmd.clone(),
+                args: Args(self.args.0.iter().cloned().chain(line).collect()),
+                block: None,
+                body: None,
+                query: None,
+            })
+            .collect()
+    }
 }
 
 #[derive(Owned!)]
@@ -17,12 +79,97 @@ pub enum Command {
     Create,
     Pay,
     Deliver,
+    Receipt,
+    Void,
     Balance,
+    Assert,
+    Begin,
+    Commit,
+    Whatif,
+    Every,
+    Budget,
+    Tag,
+    Report,
+    History,
+    Select,
+    Alias,
+    Rename,
+    Help,
 }
 
-#[derive(Owned!)]
+impl Command {
+    pub const ALL: [Command; 19] = [
+        Command::Create,
+        Command::Pay,
+        Command::Deliver,
+        Command::Receipt,
+        Command::Void,
+        Command::Balance,
+        Command::Assert,
+        Command::Begin,
+        Command::Commit,
+        Command::Whatif,
+        Command::Every,
+        Command::Budget,
+        Command::Tag,
+        Command::Report,
+        Command::History,
+        Command::Select,
+        Command::Alias,
+        Command::Rename,
+        Command::Help,
+    ];
+
+    /// What this command is written as in source code.
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            Command::Create => "create",
+            Command::Pay => "pay",
+            Command::Deliver => "deliver",
+            Command::Receipt => "receipt",
+            Command::Void => "void",
+            Command::Balance => "balance",
+            Command::Assert => "assert",
+            Command::Begin => "begin",
+            Command::Commit => "commit",
+            Command::Whatif => "whatif",
+            Command::Every => "every",
+            Command::Budget => "budget",
+            Command::Tag => "tag",
+            Command::Report => "report",
+            Command::History => "history",
+            Command::Select => "select",
+            Command::Alias => "alias",
+            Command::Rename => "rename",
+            Command::Help => "help",
+        }
+    }
+
+    /// Whether the statement has a [body][Stmt::body] of statements
+    /// instead of a [`Block`] of arguments.
+    pub fn takes_body(&self) -> bool {
+        matches!(self, Command::Whatif | Command::Every)
+    }
+
+    /// Whether a `=` with whitespace before it is an [`Arg::Equals`] on its own
+    /// instead of belonging to a named argument.
+    pub fn takes_equals(&self) -> bool {
+        matches!(self, Command::Assert | Command::Alias)
+    }
+
+    /// Whether the statement has a [query][Stmt::query] instead of arguments.
+    pub fn takes_query(&self) -> bool {
+        matches!(self, Command::Select)
+    }
+}
+
+#[derive(Owned!, Default)]
 pub struct Args<'tok>(pub Vec<Arg<'tok>>);
 
+/// Lines in braces, as in `create entity { A; B }`.
+#[derive(Owned!)]
+pub struct Block<'tok>(pub Vec<Args<'tok>>);
+
 #[derive(Owned!)]
 pub enum Arg<'tok> {
     Named {
@@ -30,14 +177,54 @@ pub enum Arg<'tok> {
         value: Value<'tok>,
     },
     Pos(Value<'tok>),
+    /// A lone `=` with whitespace around it,
+    /// separating what's on its left from what's on its right.
+    Equals,
+}
+
+impl<'tok> Arg<'tok> {
+    /// `key=value`
+    pub fn named(key: &'tok str, value: Value<'tok>) -> Self {
+        Self::Named {
+            key: Ident(key),
+            value,
+        }
+    }
 }
 
 #[derive(Owned!)]
 pub enum Value<'tok> {
     Money(Money),
+    /// A negative amount of money, as in `-2€`.
+    Negative(Money),
     Split(Split),
+    /// Days from one to another, both included,
+    /// as in `2025-01-01..2025-12-31`.
+    Dates(Dates),
     Gtin(Gtin),
+    Date(Date),
+    Natural(Natural),
+    /// A negative integer, as in `-3`.
+    NegativeNatural(Natural),
+    /// A string in double quotes, as in `"Mama's Pizza"`,
+    /// with escapes already resolved.
+    /// Can be used wherever a name can.
+    Quoted(String),
     Name(Name<'tok>),
+    /// Several values separated by commas, as in `drinks,bio`.
+    /// Always at least 2, and none of them a list itself.
+    List(Vec<Value<'tok>>),
+}
+
+impl<'tok> Value<'tok> {
+    /// `name` as name, quoted if it isn't an identifier on its own.
+    pub fn name(name: &'tok str) -> Self {
+        if parse::is_ident(name) {
+            Self::Name(Name(Ident(name)))
+        } else {
+            Self::Quoted(name.to_owned())
+        }
+    }
 }
 
 #[derive(Owned!)]
@@ -46,6 +233,73 @@ pub struct Split {
     pub to: Natural,
 }
 
+#[derive(Stack!)]
+pub struct Dates {
+    pub from: Date,
+    pub to: Date,
+}
+
+/// What a `select` statement reads,
+/// as in `select sum(price) from deliveries where to = B group by month`.
+#[derive(Owned!)]
+pub struct Query<'tok> {
+    /// What each row of the result shows, in order.
+    pub columns: Vec<Column<'tok>>,
+    /// The table to read rows from.
+    pub from: Ident<'tok>,
+    /// All of these need to hold for a row to be read.
+    pub filter: Vec<Condition<'tok>>,
+    /// A column or a period of the `date` column to group rows by.
+    pub group: Option<Ident<'tok>>,
+    pub order: Option<Order<'tok>>,
+    /// How many rows to show at most.
+    pub limit: Option<Natural>,
+}
+
+#[derive(Owned!)]
+pub enum Column<'tok> {
+    /// `*`, every column of the table.
+    All,
+    Field(Ident<'tok>),
+    /// A function over all rows in a group, as in `sum(price)`.
+    /// Without field for `count(*)`.
+    Aggregate {
+        func: Ident<'tok>,
+        of: Option<Ident<'tok>>,
+    },
+}
+
+/// `<field> <compare> <value>`, as in `to = B`.
+#[derive(Owned!)]
+pub struct Condition<'tok> {
+    pub field: Ident<'tok>,
+    pub compare: Compare,
+    pub value: Value<'tok>,
+}
+
+#[derive(Stack!)]
+pub enum Compare {
+    /// `=`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+/// `order by <column>`, optionally followed by `asc` or `desc`.
+#[derive(Owned!)]
+pub struct Order<'tok> {
+    pub by: Column<'tok>,
+    pub descending: bool,
+}
+
 /// a name in specific is a value, whereas an ident is just somewhere something resembling an
 /// identifier
 #[derive(Stack!)]
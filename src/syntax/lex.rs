@@ -11,18 +11,72 @@ pub enum Token<'src> {
     Pay,
     #[token("deliver")]
     Deliver,
+    #[token("receipt")]
+    Receipt,
+    #[token("void")]
+    Void,
     #[token("balance")]
     Balance,
+    #[token("assert")]
+    Assert,
+    #[token("begin")]
+    Begin,
+    #[token("commit")]
+    Commit,
+    #[token("whatif")]
+    Whatif,
+    #[token("every")]
+    Every,
+    #[token("budget")]
+    Budget,
+    #[token("tag")]
+    Tag,
+    #[token("report")]
+    Report,
+    #[token("history")]
+    History,
+    #[token("select")]
+    Select,
+    #[token("alias")]
+    Alias,
+    #[token("rename")]
+    Rename,
+    #[token("help")]
+    Help,
 
     // punctuation
     #[token(".")]
     Dot,
     #[token(":")]
     Colon,
+    #[token(",")]
+    Comma,
     #[token(";")]
     Semicolon,
     #[token("=")]
     Equals,
+    #[token("!=")]
+    NotEquals,
+    #[token("<")]
+    Less,
+    #[token("<=")]
+    LessEquals,
+    #[token(">")]
+    Greater,
+    #[token(">=")]
+    GreaterEquals,
+    #[token("*")]
+    Star,
+    #[token("-")]
+    Minus,
+    #[token("{")]
+    BraceOpen,
+    #[token("}")]
+    BraceClose,
+    #[token("(")]
+    ParenOpen,
+    #[token(")")]
+    ParenClose,
     #[regex(r"\r?\n")]
     Newline,
     #[regex(r"eur(os)?|EUR|€", priority = 10)]
@@ -30,7 +84,14 @@ pub enum Token<'src> {
     #[regex(r"cents?|ct|¢", priority = 10)]
     SignCent,
 
-    #[regex(r"\d+\.\d{2}")]
+    #[regex(r"\d{4}-\d{2}-\d{2}")]
+    Date(&'src str),
+    /// Including the quotes, escapes not resolved yet.
+    #[regex(r#""([^"\\\r\n]|\\.)*""#)]
+    Quoted(&'src str),
+    /// Digits with separators, as in `1.234,56`.
+    /// Which separator is which is up to the [locale][crate::ext::locale].
+    #[regex(r"\d+([.,']\d+)+")]
     Decimal(&'src str),
     #[regex(r"\d+")]
     Natural(&'src str),
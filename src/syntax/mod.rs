@@ -6,3 +6,4 @@ mod tests;
 pub mod ast;
 pub mod lex;
 pub mod parse;
+pub mod print;
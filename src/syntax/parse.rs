@@ -7,31 +7,53 @@ use chumsky::{
 };
 use logos::Logos;
 
-use crate::ext::{Money, Natural};
+use crate::ext::{Date, Money, Natural, locale::Locale};
 
 use super::{ast::*, lex::Token};
 
 pub type Error<'tok, 'src> = Rich<'tok, Token<'src>, SimpleSpan>;
 pub type Ctx<'tok, 'src> = extra::Err<Error<'tok, 'src>>;
 
+/// Anything the parsers below can run on.
+pub trait Tokens<'tok, 'src: 'tok>:
+    ValueInput<'tok, Token = Token<'src>, Span = SimpleSpan>
+{
+}
+
+impl<'tok, 'src: 'tok, I> Tokens<'tok, 'src> for I where
+    I: ValueInput<'tok, Token = Token<'src>, Span = SimpleSpan>
+{
+}
+
+/// Turns source code into tokens
+/// which any of the parsers in this module can run on.
+pub fn lex<'src>(source: &'src str) -> impl Tokens<'src, 'src> {
+    // based on https://github.com/zesterer/chumsky/blob/main/examples/logos.rs
+    let iter = Token::lexer(source).spanned().map(|(tok, span)| match tok {
+        Ok(tok) => (tok, SimpleSpan::from(span)),
+        Err(()) => (Token::Error, span.into()),
+    });
+
+    // used for EOF tokens
+    let end_span = SimpleSpan::from(source.len()..source.len());
+
+    // navigatable by chumsky beyond just individual advancing
+    Stream::from_iter(iter).map(end_span, |(t, s): (_, _)| (t, s))
+}
+
+/// Whether `src` can be used as a name in source code.
+pub fn is_ident(src: &str) -> bool {
+    ident()
+        .then_ignore(end())
+        .parse(lex(src))
+        .into_result()
+        .is_ok()
+}
+
 impl<'tok> Script<'tok> {
     /// [`FromStr::from_str`] but not, since that doesn't allow lifetime constraints.
     pub fn parse<'src: 'tok>(source: &'src str) -> ParseResult<Self, Error<'tok, 'src>> {
-        // based on https://github.com/zesterer/chumsky/blob/main/examples/logos.rs
-        let iter = Token::lexer(source)
-            .spanned()
-            .map(|x| dbg!(x))
-            .map(|(tok, span)| match tok {
-                Ok(tok) => (tok, span.into()),
-                Err(()) => (Token::Error, span.into()),
-            });
-
-        // used for EOF tokens
-        let end_span = (source.len()..source.len()).into();
-
-        // navigatable by chumsky beyond just individual advancing
-        let stream = Stream::from_iter(iter).map(end_span, |(t, s): (_, _)| (t, s));
-        parser().parse(stream)
+        parser().parse(lex(source))
     }
 }
 
@@ -43,77 +65,411 @@ macro_rules! from_str {
     };
 }
 
-pub fn parser<'tok, 'src: 'tok, I>() -> impl Parser<'tok, I, Script<'tok>, Ctx<'tok, 'src>>
-where
-    I: ValueInput<'tok, Token = Token<'src>, Span = SimpleSpan>,
-{
-    use Token as T;
-
-    const DOT_SHIFT: u8 = 10u8.pow(2);
+/// Declares a parser function over any [`Tokens`].
+macro_rules! parser {
+    ($( $(#[$attr:meta])* $name:ident -> $out:ty $body:block )*) => {
+        $(
+            $(#[$attr])*
+            pub fn $name<'tok, 'src: 'tok, I>(
+            ) -> impl Parser<'tok, I, $out, Ctx<'tok, 'src>> + Clone
+            where
+                I: Tokens<'tok, 'src>,
+            $body
+        )*
+    };
+}
 
-    let optional_space = just(T::Whitespace).repeated();
-    let hard_space = optional_space.at_least(1);
+const DOT_SHIFT: u8 = 10u8.pow(2);
 
-    let statement_delimiter = one_of([T::Semicolon, T::Newline]).padded_by(optional_space);
+parser! {
+    /// Whitespace that can, but doesn't have to be there.
+    optional_space -> () {
+        just(Token::Whitespace).repeated()
+    }
 
-    let ident = select! { T::Ident(id) => Ident(id) };
+    /// At least one whitespace.
+    hard_space -> () {
+        just(Token::Whitespace).repeated().at_least(1)
+    }
 
-    // pretty much just faking and directly converting into an integer
-    // since we know there's only 2 digits after the dot
-    let decimal = select! { T::Decimal(src) => src }.map(|src| {
-        let (whole, fraction) = src
-            .rsplit_once('.')
-            .expect("lexer to emit decimal token only with a dot");
+    /// Separates 2 statements (or lines in a block).
+    statement_delimiter -> () {
+        one_of([Token::Semicolon, Token::Newline])
+            .padded_by(optional_space())
+            .repeated()
+            .at_least(1)
+    }
 
-        let nat = |src: &str| src.parse::<Natural>().unwrap();
+    /// Command keywords are fine as identifiers, too,
+    /// e.g. in `assert balance from=A to=B = 0€`.
+    ident -> Ident<'tok> {
+        choice((
+            select! { Token::Ident(id) => Ident(id) },
+            command().map(|cmd| Ident(cmd.keyword())),
+        ))
+    }
 
-        nat(whole) * DOT_SHIFT + nat(fraction)
-    });
     // expectation: lexer already made sure that this is, in fact, a natural number
-    let natural = select! { T::Natural(src) => src }.from_str().unwrapped();
+    natural -> Natural {
+        select! { Token::Natural(src) => src }.from_str().unwrapped()
+    }
 
-    let cents = natural.then_ignore(optional_space.then(just(T::SignCent)));
-    let euros = choice((decimal, natural.map(|num| num * DOT_SHIFT)))
-        .then_ignore(optional_space.then(just(T::SignEuro)));
-    let money = choice((euros, cents)).map(Money);
+    /// An amount with a currency sign after it, or a euro sign before it.
+    /// Separators are read as the [current locale][Locale::current] says.
+    money -> Money {
+        let decimal = select! { Token::Decimal(src) => src }.try_map(|src, span| {
+            Locale::current()
+                .parse(src)
+                .map_err(|err| Error::custom(span, err))
+        });
 
-    let split = group((natural, just(T::Colon).padded_by(optional_space), natural))
-        .map(|(from, _, to)| Split { from, to });
+        let cents = natural().then_ignore(optional_space().then(just(Token::SignCent)));
+        let amount = choice((decimal, natural().map(|num| num * DOT_SHIFT)));
+        let euros = choice((
+            amount
+                .clone()
+                .then_ignore(optional_space().then(just(Token::SignEuro))),
+            just(Token::SignEuro)
+                .then(optional_space())
+                .ignore_then(amount),
+        ));
 
-    let gtin = from_str!(select! { T::Natural(src) => src });
+        choice((euros, cents)).map(Money)
+    }
 
-    let name = ident.map(Name);
+    date -> Date {
+        from_str!(select! { Token::Date(src) => src })
+    }
 
-    let value = choice((
-        money.map(Value::Money),
-        split.map(Value::Split),
-        gtin.map(Value::Gtin),
-        name.map(Value::Name),
-    ));
+    /// A string in double quotes,
+    /// with `\"`, `\\`, `\n` and `\t` as escapes.
+    quoted -> String {
+        select! { Token::Quoted(src) => src }
+            .try_map(|src, span| unescape(src).map_err(|err| Error::custom(span, err)))
+    }
 
-    let named = group((ident, just(T::Equals).padded_by(optional_space), value))
-        .map(|(key, _, value)| Arg::Named { key, value });
-    let positional = value.map(Arg::Pos);
+    split -> Split {
+        group((natural(), just(Token::Colon).padded_by(optional_space()), natural()))
+            .map(|(from, _, to)| Split { from, to })
+    }
 
-    let arguments = choice((named, positional))
-        .separated_by(hard_space)
-        .collect::<Vec<_>>()
-        .map(Args);
+    /// `<date>..<date>`
+    dates -> Dates {
+        group((date(), just(Token::Dot), just(Token::Dot), date()))
+            .map(|(from, _, _, to)| Dates { from, to })
+    }
 
-    let command = select! {
-        T::Create => Command::Create,
-        T::Pay => Command::Pay,
-        T::Deliver => Command::Deliver,
-        T::Balance => Command::Balance,
-    };
+    value -> Value<'tok> {
+        let gtin = from_str!(select! { Token::Natural(src) => src });
+        let name = ident().map(Name);
+
+        let single = choice((
+            money().map(Value::Money),
+            just(Token::Minus).ignore_then(money()).map(Value::Negative),
+            split().map(Value::Split),
+            dates().map(Value::Dates),
+            date().map(Value::Date),
+            gtin.map(Value::Gtin),
+            natural().map(Value::Natural),
+            just(Token::Minus).ignore_then(natural()).map(Value::NegativeNatural),
+            quoted().map(Value::Quoted),
+            name.map(Value::Name),
+        ));
+
+        single
+            .separated_by(just(Token::Comma))
+            .at_least(1)
+            .collect::<Vec<_>>()
+            .map(|mut values| match values.len() {
+                1 => values.remove(0),
+                _ => Value::List(values),
+            })
+    }
+
+    /// Arguments as in most statements,
+    /// named ones can have whitespace around their `=`.
+    arguments -> Args<'tok> {
+        argument_list(false)
+    }
+
+    /// Arguments of an `assert` or `alias` statement.
+    /// A `=` with whitespace before it is a lone one,
+    /// separating what's checked from what's expected.
+    assertion_arguments -> Args<'tok> {
+        argument_list(true)
+    }
+
+    /// Lines of arguments in braces,
+    /// each of them appended to the arguments in front of the block.
+    block -> Block<'tok> {
+        block_of(arguments())
+    }
+
+    command -> Command {
+        select! {
+            Token::Create => Command::Create,
+            Token::Pay => Command::Pay,
+            Token::Deliver => Command::Deliver,
+            Token::Receipt => Command::Receipt,
+            Token::Void => Command::Void,
+            Token::Balance => Command::Balance,
+            Token::Assert => Command::Assert,
+            Token::Begin => Command::Begin,
+            Token::Commit => Command::Commit,
+            Token::Whatif => Command::Whatif,
+            Token::Every => Command::Every,
+            Token::Budget => Command::Budget,
+            Token::Tag => Command::Tag,
+            Token::Report => Command::Report,
+            Token::History => Command::History,
+            Token::Select => Command::Select,
+            Token::Alias => Command::Alias,
+            Token::Rename => Command::Rename,
+            Token::Help => Command::Help,
+        }
+    }
 
-    let statement =
-        group((command, hard_space, arguments)).map(|(cmd, _, args)| Stmt { cmd, args });
+    /// `*`, a field, or a function of a field or `*` as in `sum(price)`.
+    query_column -> Column<'tok> {
+        let star = just(Token::Star);
+        let aggregate = group((
+            ident(),
+            just(Token::ParenOpen),
+            choice((star.to(None), ident().map(Some))).padded_by(optional_space()),
+            just(Token::ParenClose),
+        ))
+        .map(|(func, _, of, _)| Column::Aggregate { func, of });
 
+        choice((star.to(Column::All), aggregate, ident().map(Column::Field)))
+    }
+
+    compare -> Compare {
+        select! {
+            Token::Equals => Compare::Eq,
+            Token::NotEquals => Compare::Ne,
+            Token::Less => Compare::Lt,
+            Token::LessEquals => Compare::Le,
+            Token::Greater => Compare::Gt,
+            Token::GreaterEquals => Compare::Ge,
+        }
+    }
+
+    /// `<field> <compare> <value>`, whitespace around the comparison optional.
+    condition -> Condition<'tok> {
+        group((ident(), compare().padded_by(optional_space()), value()))
+            .map(|(field, compare, value)| Condition { field, compare, value })
+    }
+
+    /// Everything after `select`,
+    /// its clauses in the same order as in SQL.
+    query -> Query<'tok> {
+        let clause = |word| hard_space().then(keyword(word)).then(hard_space());
+        let by = |word| hard_space().then(keyword(word)).then(clause("by"));
+
+        let columns = query_column()
+            .separated_by(just(Token::Comma).then(optional_space()))
+            .at_least(1)
+            .collect::<Vec<_>>();
+        let filter = clause("where").ignore_then(
+            condition()
+                .separated_by(clause("and"))
+                .at_least(1)
+                .collect::<Vec<_>>(),
+        );
+        let direction = hard_space().ignore_then(choice((
+            keyword("asc").to(false),
+            keyword("desc").to(true),
+        )));
+        let order = by("order")
+            .ignore_then(group((query_column(), direction.or_not())))
+            .map(|(by, descending)| Order {
+                by,
+                descending: descending.unwrap_or(false),
+            });
+
+        group((
+            columns,
+            clause("from").ignore_then(ident()),
+            filter.or_not(),
+            by("group").ignore_then(ident()).or_not(),
+            order.or_not(),
+            clause("limit").ignore_then(natural()).or_not(),
+        ))
+        .map(|(columns, from, filter, group, order, limit)| Query {
+            columns,
+            from,
+            filter: filter.unwrap_or_default(),
+            group,
+            order,
+            limit,
+        })
+    }
+
+    statement -> Stmt<'tok> {
+        recursive(|statement| {
+            let assertion = group((
+                command().filter(Command::takes_equals),
+                hard_space().ignore_then(assertion_arguments()).or_not(),
+                optional_space()
+                    .ignore_then(block_of(assertion_arguments()))
+                    .or_not(),
+            ))
+            .map(|(cmd, args, block)| (cmd, args, block, None, None));
+            let nested = group((
+                command().filter(Command::takes_body),
+                hard_space().ignore_then(arguments()).or_not(),
+                optional_space().ignore_then(
+                    statements_of(statement)
+                        .delimited_by(just(Token::BraceOpen), just(Token::BraceClose)),
+                ),
+            ))
+            .map(|(cmd, args, body)| (cmd, args, None, Some(body), None));
+            let select = group((
+                command().filter(Command::takes_query),
+                hard_space().ignore_then(query()),
+            ))
+            .map(|(cmd, query)| (cmd, None, None, None, Some(query)));
+            let other = group((
+                command().filter(|cmd| {
+                    !cmd.takes_body() && !cmd.takes_equals() && !cmd.takes_query()
+                }),
+                hard_space().ignore_then(arguments()).or_not(),
+                optional_space().ignore_then(block()).or_not(),
+            ))
+            .map(|(cmd, args, block)| (cmd, args, block, None, None));
+
+            choice((assertion, nested, select, other)).map(|(cmd, args, block, body, query)| {
+                Stmt {
+                    cmd,
+                    args: args.unwrap_or_default(),
+                    block,
+                    body,
+                    query,
+                }
+            })
+        })
+    }
+
+    parser -> Script<'tok> {
+        statements_of(statement())
+    }
+}
+
+/// Resolves the escapes in a [`Token::Quoted`], removing the quotes.
+fn unescape(src: &str) -> Result<String, String> {
+    let inner = &src[1..src.len() - 1];
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => {
+                return Err(format!(
+                    "unknown escape `\\{other}`, only `\\\"`, `\\\\`, `\\n` and `\\t` exist"
+                ));
+            }
+            None => unreachable!("lexer to never end a quoted string on a backslash"),
+        }
+    }
+
+    Ok(out)
+}
+
+/// An identifier that only means something at this point of a [`Query`],
+/// as `where` does.
+fn keyword<'tok, 'src: 'tok, I>(
+    word: &'static str,
+) -> impl Parser<'tok, I, (), Ctx<'tok, 'src>> + Clone
+where
+    I: Tokens<'tok, 'src>,
+{
+    ident().filter(move |Ident(id)| *id == word).ignored()
+}
+
+/// Statements separated by delimiters, each with their span.
+fn statements_of<'tok, 'src: 'tok, I>(
+    statement: impl Parser<'tok, I, Stmt<'tok>, Ctx<'tok, 'src>> + Clone,
+) -> impl Parser<'tok, I, Script<'tok>, Ctx<'tok, 'src>> + Clone
+where
+    I: Tokens<'tok, 'src>,
+{
     statement
-        .separated_by(statement_delimiter.repeated().at_least(1))
+        .map_with(|node, e| {
+            let span: SimpleSpan = e.span();
+            Spanned {
+                node,
+                span: Span {
+                    start: span.start,
+                    end: span.end,
+                },
+            }
+        })
+        .separated_by(statement_delimiter())
         .allow_leading()
         .allow_trailing()
         .collect::<Vec<_>>()
+        .padded_by(optional_space())
         .map(Script)
 }
+
+/// Arguments separated by whitespace.
+///
+/// If `lone_equals` is set, named arguments can't have whitespace before their `=`,
+/// since such a `=` is an [`Arg::Equals`] on its own.
+fn argument_list<'tok, 'src: 'tok, I>(
+    lone_equals: bool,
+) -> impl Parser<'tok, I, Args<'tok>, Ctx<'tok, 'src>> + Clone
+where
+    I: Tokens<'tok, 'src>,
+{
+    let before_equals = if lone_equals {
+        empty().boxed()
+    } else {
+        optional_space().boxed()
+    };
+    let named = group((
+        ident(),
+        before_equals,
+        just(Token::Equals),
+        optional_space(),
+        value(),
+    ))
+    .map(|(key, _, _, _, value)| Arg::Named { key, value });
+    let positional = value().map(Arg::Pos);
+
+    let arg = if lone_equals {
+        let equals = just(Token::Equals).to(Arg::Equals);
+        choice((named, positional, equals)).boxed()
+    } else {
+        choice((named, positional)).boxed()
+    };
+
+    arg.separated_by(hard_space())
+        .at_least(1)
+        .collect::<Vec<_>>()
+        .map(Args)
+}
+
+/// Lines of `args` in braces.
+fn block_of<'tok, 'src: 'tok, I>(
+    args: impl Parser<'tok, I, Args<'tok>, Ctx<'tok, 'src>> + Clone,
+) -> impl Parser<'tok, I, Block<'tok>, Ctx<'tok, 'src>> + Clone
+where
+    I: Tokens<'tok, 'src>,
+{
+    args.separated_by(statement_delimiter())
+        .allow_leading()
+        .allow_trailing()
+        .collect::<Vec<_>>()
+        .padded_by(optional_space())
+        .delimited_by(just(Token::BraceOpen), just(Token::BraceClose))
+        .map(Block)
+}
@@ -0,0 +1,220 @@
+//! Turn an AST back into source code.
+//!
+//! Parsing the output again yields the same AST.
+
+use std::fmt;
+
+use crate::ext::{
+    Gtin, Money,
+    locale::{Fraction, Locale},
+};
+
+use super::ast::*;
+
+/// [`Money`] as it'd be written in source code in the current [`Locale`],
+/// as opposed to its [`fmt::Display`] impl meant for humans.
+pub struct SourceMoney<'a>(pub &'a Money);
+
+impl fmt::Display for SourceMoney<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Locale::current().write(f, self.0, Fraction::IfAny, false)
+    }
+}
+
+/// [`Gtin`] as it'd be written in source code,
+/// padded to the minimum amount of digits.
+pub struct SourceGtin(pub Gtin);
+
+impl fmt::Display for SourceGtin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = Gtin::DIGITS_MIN as usize;
+        write!(f, "{:0width$}", self.0.get())
+    }
+}
+
+impl fmt::Display for Script<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stmt in &self.0 {
+            writeln!(f, "{}", stmt.node)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Stmt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cmd)?;
+        if !self.args.0.is_empty() {
+            write!(f, " {}", self.args)?;
+        }
+        if let Some(block) = &self.block {
+            write!(f, " {block}")?;
+        }
+        if let Some(query) = &self.query {
+            write!(f, " {query}")?;
+        }
+        if let Some(body) = &self.body {
+            writeln!(f, " {{")?;
+            for line in body.to_string().lines() {
+                writeln!(f, "\t{line}")?;
+            }
+            write!(f, "}}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.keyword())
+    }
+}
+
+impl fmt::Display for Block<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{{")?;
+        for line in &self.0 {
+            writeln!(f, "\t{line}")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for Args<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut args = self.0.iter();
+        if let Some(first) = args.next() {
+            write!(f, "{first}")?;
+        }
+        for arg in args {
+            write!(f, " {arg}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Arg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Arg::Named { key, value } => write!(f, "{key}={value}"),
+            Arg::Pos(value) => write!(f, "{value}"),
+            Arg::Equals => write!(f, "="),
+        }
+    }
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Money(money) => write!(f, "{}", SourceMoney(money)),
+            Value::Negative(money) => write!(f, "-{}", SourceMoney(money)),
+            Value::Split(split) => write!(f, "{split}"),
+            Value::Dates(dates) => write!(f, "{dates}"),
+            Value::Gtin(gtin) => write!(f, "{}", SourceGtin(*gtin)),
+            Value::Date(date) => write!(f, "{date}"),
+            Value::Natural(n) => write!(f, "{n}"),
+            Value::NegativeNatural(n) => write!(f, "-{n}"),
+            Value::Quoted(s) => {
+                let escaped = s
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+                    .replace('\t', "\\t");
+                write!(f, "\"{escaped}\"")
+            }
+            Value::Name(name) => write!(f, "{name}"),
+            Value::List(values) => {
+                let mut values = values.iter();
+                if let Some(first) = values.next() {
+                    write!(f, "{first}")?;
+                }
+                for value in values {
+                    write!(f, ",{value}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Split {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.from, self.to)
+    }
+}
+
+impl fmt::Display for Dates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.from, self.to)
+    }
+}
+
+impl fmt::Display for Query<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns: Vec<_> = self.columns.iter().map(ToString::to_string).collect();
+        write!(f, "{} from {}", columns.join(", "), self.from)?;
+        let mut filter = self.filter.iter();
+        if let Some(first) = filter.next() {
+            write!(f, " where {first}")?;
+        }
+        for condition in filter {
+            write!(f, " and {condition}")?;
+        }
+        if let Some(group) = &self.group {
+            write!(f, " group by {group}")?;
+        }
+        if let Some(Order { by, descending }) = &self.order {
+            write!(f, " order by {by}")?;
+            if *descending {
+                write!(f, " desc")?;
+            }
+        }
+        if let Some(limit) = &self.limit {
+            write!(f, " limit {limit}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Column<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Column::All => write!(f, "*"),
+            Column::Field(field) => write!(f, "{field}"),
+            Column::Aggregate { func, of: Some(of) } => write!(f, "{func}({of})"),
+            Column::Aggregate { func, of: None } => write!(f, "{func}(*)"),
+        }
+    }
+}
+
+impl fmt::Display for Condition<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.field, self.compare, self.value)
+    }
+}
+
+impl fmt::Display for Compare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Compare::Eq => "=",
+            Compare::Ne => "!=",
+            Compare::Lt => "<",
+            Compare::Le => "<=",
+            Compare::Gt => ">",
+            Compare::Ge => ">=",
+        };
+        write!(f, "{op}")
+    }
+}
+
+impl fmt::Display for Name<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for Ident<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -1,69 +1,418 @@
-use std::fmt;
-
 use chumsky::{Parser, prelude::*};
 
-use crate::ext::{Gtin, Money};
+use crate::ext::{Gtin, Money, locale::Locale};
 
 use super::{ast::*, parse::*};
 
-fn assert<'a, T>(parser: impl P<'a, T>, src: &'a str, intended: T)
-where
-    T: fmt::Debug + PartialEq,
-{
-    let output = parser.parse(src).unwrap();
-    assert_eq!(output, intended);
+/// Runs the parser on the whole source and compares to what it should've been.
+macro_rules! assert_parses {
+    ($parser:expr, $src:expr, $intended:expr $(,)?) => {
+        let output = $parser.then_ignore(end()).parse(lex($src)).unwrap();
+        assert_eq!(output, $intended);
+    };
+}
+
+fn pos(value: Value) -> Arg {
+    Arg::Pos(value)
+}
+
+fn name(name: &str) -> Value<'_> {
+    Value::Name(Name(Ident(name)))
+}
+
+fn stmt<'tok>(cmd: Command, args: impl IntoIterator<Item = Arg<'tok>>) -> Stmt<'tok> {
+    Stmt::new(cmd, args.into_iter().collect())
 }
 
 #[test]
 fn basic() {
-    let a = || Ident::new("A");
-    let b = || Ident::new("B");
     let gtin = 12345678901234;
 
-    assert(
-        comment().ignore_then(just("abc")),
-        "# this is a comment with ✨ special ✨ emojis\nabc",
-        "abc",
+    let script = Script::parse("# this is a comment with ✨ special ✨ emojis\ncreate entity A");
+    assert_eq!(
+        script.unwrap().0,
+        [Spanned {
+            node: stmt(Command::Create, [pos(name("entity")), pos(name("A"))]),
+            span: Span { start: 48, end: 63 },
+        }],
     );
 
-    assert(
-        pay(),
+    assert_parses!(
+        statement(),
         "pay 30ct from A to B",
-        Pay {
-            amount: Money(30u8.into()),
-            who: Dir {
-                source: a(),
-                target: b(),
-            },
-        },
-    );
-
-    assert(
-        deliver(),
-        &format!("deliver {gtin} price 1€ from A to B"),
-        Deliver {
-            what: Product::Id(Gtin::new(gtin).unwrap()),
-            who: Dir {
-                source: a(),
-                target: b(),
-            },
-            price: Some(Money(100u8.into())),
-            split: None,
-        },
+        stmt(
+            Command::Pay,
+            [
+                pos(Value::Money(Money(30u8.into()))),
+                pos(name("from")),
+                pos(name("A")),
+                pos(name("to")),
+                pos(name("B")),
+            ]
+        ),
+    );
+
+    let src = format!("deliver {gtin} price=1€ from=A to=B");
+    assert_parses!(
+        statement(),
+        &src,
+        stmt(
+            Command::Deliver,
+            [
+                pos(Value::Gtin(Gtin::new(gtin).unwrap())),
+                Arg::Named {
+                    key: Ident("price"),
+                    value: Value::Money(Money(100u8.into())),
+                },
+                Arg::Named {
+                    key: Ident("from"),
+                    value: name("A"),
+                },
+                Arg::Named {
+                    key: Ident("to"),
+                    value: name("B"),
+                },
+            ]
+        ),
+    );
+}
+
+#[test]
+fn block() {
+    let script = Script::parse("create entity { A; B\n\tC }").unwrap();
+    let lines = script
+        .0
+        .into_iter()
+        .flat_map(|stmt| stmt.node.unblock())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        lines,
+        ["A", "B", "C"]
+            .map(|entity| stmt(Command::Create, [pos(name("entity")), pos(name(entity))])),
     );
 }
 
+#[test]
+fn body() {
+    let src = "whatif {\n\tpay 1€ from=A to=B\n\twhatif { receipt from=A { Mate for B } }\n}";
+    let script = Script::parse(src).unwrap();
+    let [whatif] = &nodes(script.clone())[..] else {
+        panic!("expected exactly one statement");
+    };
+
+    assert_eq!(whatif.cmd, Command::Whatif);
+    assert_eq!(whatif.block, None);
+    let body = nodes(whatif.body.clone().unwrap());
+    assert_eq!(
+        body.iter().map(|stmt| stmt.cmd.clone()).collect::<Vec<_>>(),
+        [Command::Pay, Command::Whatif],
+    );
+    assert_eq!(
+        body[1].body.as_ref().unwrap().0[0].node.cmd,
+        Command::Receipt
+    );
+
+    // spans are still relative to the whole source
+    let span = whatif.body.as_ref().unwrap().0[0].span;
+    assert_eq!(&src[span.start..span.end], "pay 1€ from=A to=B");
+
+    // nested spans move when printed, so compare the printed sources
+    let printed = script.to_string();
+    assert_eq!(Script::parse(&printed).unwrap().to_string(), printed);
+}
+
+#[test]
+fn every() {
+    let script = Script::parse("every month from=2025-01-01 {\n\tpay 450€ from A to L\n}").unwrap();
+    let [every] = &nodes(script)[..] else {
+        panic!("expected exactly one statement");
+    };
+
+    assert_eq!(every.cmd, Command::Every);
+    assert_eq!(every.args.0[0], pos(name("month")));
+    let body = nodes(every.body.clone().unwrap());
+    assert_eq!(body[0].cmd, Command::Pay);
+}
+
+#[test]
+fn list() {
+    let script = Script::parse("create concept Mate tags=drinks,\"bio\",2025-01-01").unwrap();
+    let [create] = &nodes(script.clone())[..] else {
+        panic!("expected exactly one statement");
+    };
+
+    assert_eq!(
+        create.args.0[2],
+        Arg::named(
+            "tags",
+            Value::List(vec![
+                name("drinks"),
+                Value::Quoted("bio".to_owned()),
+                Value::Date("2025-01-01".parse().unwrap()),
+            ])
+        )
+    );
+    assert_eq!(
+        script.to_string(),
+        "create concept Mate tags=drinks,\"bio\",2025-01-01\n"
+    );
+
+    // a single value stays one
+    let script = Script::parse("tag Mate drinks").unwrap();
+    assert_eq!(nodes(script)[0].args.0[1], pos(name("drinks")));
+}
+
+#[test]
+fn dates() {
+    let script = Script::parse("report spending of=A during=2025-01-01..2025-12-31").unwrap();
+    let during = Dates {
+        from: "2025-01-01".parse().unwrap(),
+        to: "2025-12-31".parse().unwrap(),
+    };
+    assert_eq!(
+        nodes(script.clone())[0].args.0[2],
+        Arg::named("during", Value::Dates(during))
+    );
+    assert_eq!(
+        script.to_string(),
+        "report spending of=A during=2025-01-01..2025-12-31\n"
+    );
+}
+
+#[test]
+fn query() {
+    let src = "select month, sum(price) from deliveries where to = B and qty>=2 group by month order by sum(price) desc limit 3";
+    let script = Script::parse(src).unwrap();
+    let [select] = &nodes(script.clone())[..] else {
+        panic!("expected exactly one statement");
+    };
+
+    let sum = Column::Aggregate {
+        func: Ident("sum"),
+        of: Some(Ident("price")),
+    };
+    assert_eq!(select.cmd, Command::Select);
+    assert!(select.args.0.is_empty());
+    assert_eq!(
+        select.query,
+        Some(Query {
+            columns: vec![Column::Field(Ident("month")), sum.clone()],
+            from: Ident("deliveries"),
+            filter: vec![
+                Condition {
+                    field: Ident("to"),
+                    compare: Compare::Eq,
+                    value: name("B"),
+                },
+                Condition {
+                    field: Ident("qty"),
+                    compare: Compare::Ge,
+                    value: Value::Natural(2u8.into()),
+                },
+            ],
+            group: Some(Ident("month")),
+            order: Some(Order {
+                by: sum,
+                descending: true,
+            }),
+            limit: Some(3u8.into()),
+        })
+    );
+    assert_eq!(
+        script.to_string(),
+        "select month, sum(price) from deliveries where to = B and qty >= 2 group by month order by sum(price) desc limit 3\n"
+    );
+
+    // clause keywords are still fine as column names
+    let script = Script::parse("select count(*), from from payments where from != A").unwrap();
+    let query = nodes(script)[0].query.clone().unwrap();
+    assert_eq!(query.columns[1], Column::Field(Ident("from")));
+    assert_eq!(query.filter[0].compare, Compare::Ne);
+
+    assert!(Script::parse("select from payments").has_errors());
+}
+
 #[test]
 fn stonks() {
     // all of these should be the same!
-    for src in ["1337", "1337¢", "1337 ct", "13.37€"] {
-        assert(money(), src, Money(1337u16.into()));
+    for src in ["1337¢", "1337 ct", "13.37€"] {
+        assert_parses!(money(), src, Money(1337u16.into()));
     }
 
     // what about a non-fractional euro?
-    assert(money(), "1 EUR", Money(100u16.into()));
+    assert_parses!(money(), "1 EUR", Money(100u16.into()));
 
     // what about absurdly large numbers?
-    let src = u128::MAX.to_string();
-    assert(money(), &src, Money(u128::MAX.into()));
+    let src = format!("{}ct", u128::MAX);
+    assert_parses!(money(), &src, Money(u128::MAX.into()));
+}
+
+#[test]
+fn locale() {
+    Locale::DE.set();
+    for src in [
+        "1.234,56€",
+        "1234,56 €",
+        "€1.234,56",
+        "€ 1.234,56",
+        "123456ct",
+    ] {
+        assert_parses!(money(), src, Money(123456u32.into()));
+    }
+    for src in ["1.50€", "1,5€", "1,50", "1,50ct"] {
+        assert!(
+            money().then_ignore(end()).parse(lex(src)).has_errors(),
+            "{src}"
+        );
+    }
+
+    // printed source parses again in the same locale
+    let money = Money(123450u32.into());
+    let printed = Value::Money(money.clone()).to_string();
+    assert_eq!(printed, "1.234,50€");
+    assert_parses!(value(), &printed, Value::Money(money));
+
+    // still not mistaken for ratios or GTINs
+    assert_parses!(
+        value(),
+        "1:2",
+        Value::Split(Split {
+            from: 1u8.into(),
+            to: 2u8.into()
+        })
+    );
+    assert!(value().then_ignore(end()).parse(lex("1,50")).has_errors());
+
+    Locale::PLAIN.set();
+}
+
+fn nodes(script: Script<'_>) -> Vec<Stmt<'_>> {
+    script.0.into_iter().map(|stmt| stmt.node).collect()
+}
+
+#[test]
+fn roundtrip() {
+    let src = include_str!("../../asset/examples/typical.nyan");
+    let script = Script::parse(src).unwrap();
+    let printed = script.to_string();
+
+    assert_eq!(nodes(Script::parse(&printed).unwrap()), nodes(script));
+}
+
+#[test]
+fn assertion() {
+    assert_parses!(
+        statement(),
+        "assert balance from=A to=B = -2€",
+        stmt(
+            Command::Assert,
+            [
+                pos(name("balance")),
+                Arg::Named {
+                    key: Ident("from"),
+                    value: name("A"),
+                },
+                Arg::Named {
+                    key: Ident("to"),
+                    value: name("B"),
+                },
+                Arg::Equals,
+                pos(Value::Negative(Money(200u8.into()))),
+            ]
+        ),
+    );
+
+    // outside of assertions, the `=` still belongs to the named argument
+    assert_parses!(
+        statement(),
+        "create concept x price = 1€",
+        stmt(
+            Command::Create,
+            [
+                pos(name("concept")),
+                pos(name("x")),
+                Arg::Named {
+                    key: Ident("price"),
+                    value: Value::Money(Money(100u8.into())),
+                },
+            ]
+        ),
+    );
+    assert_parses!(
+        statement(),
+        "assert price x = 1€",
+        stmt(
+            Command::Assert,
+            [
+                pos(name("price")),
+                pos(name("x")),
+                Arg::Equals,
+                pos(Value::Money(Money(100u8.into()))),
+            ]
+        ),
+    );
+}
+
+#[test]
+fn alias() {
+    assert_parses!(
+        statement(),
+        "alias entity Bob = Robert",
+        stmt(
+            Command::Alias,
+            [
+                pos(name("entity")),
+                pos(name("Bob")),
+                Arg::Equals,
+                pos(name("Robert")),
+            ]
+        ),
+    );
+    assert_parses!(
+        statement(),
+        "rename Späti to Kiosk",
+        stmt(
+            Command::Rename,
+            [pos(name("Späti")), pos(name("to")), pos(name("Kiosk"))]
+        ),
+    );
+}
+
+#[test]
+fn date() {
+    assert_parses!(
+        value(),
+        "2024-12-31",
+        Value::Date("2024-12-31".parse().unwrap()),
+    );
+    assert!(
+        Script::parse("pay 1€ date=2024-13-01")
+            .into_result()
+            .is_err()
+    );
+}
+
+#[test]
+fn quoted() {
+    assert_parses!(
+        value(),
+        r#""Mama's \"Pizza\"\\\n""#,
+        Value::Quoted("Mama's \"Pizza\"\\\n".to_owned()),
+    );
+    assert!(
+        Script::parse(r#"create entity "\x""#)
+            .into_result()
+            .is_err()
+    );
+    assert!(Script::parse("create entity \"open").into_result().is_err());
+
+    // printed with escapes again
+    let quoted = Value::name("Club Mate 0,5l\t\"cold\"");
+    assert_eq!(quoted.to_string(), r#""Club Mate 0,5l\t\"cold\"""#);
+    let printed = quoted.to_string();
+    assert_parses!(value(), &printed, quoted);
+
+    // identifiers stay as they are
+    assert_eq!(Value::name("Mate"), name("Mate"));
 }